@@ -0,0 +1,67 @@
+-- =============================================================
+--  Migración 2: la búsqueda de tiendas incluye sucursal y alias
+--
+--  stores_fts solo indexaba el nombre, así que una tienda no aparecía
+--  buscando por el nombre con que figura en los tickets ("COTO CICSA")
+--  ni por su sucursal. Se recrea con una columna por cada uno; los alias
+--  se guardan concatenados y se recalculan ante cualquier cambio en
+--  store_aliases, igual que las notas del desglose en movements_fts.
+-- =============================================================
+DROP TRIGGER trg_stores_fts_insert;
+DROP TRIGGER trg_stores_fts_update;
+DROP TRIGGER trg_stores_fts_delete;
+DROP TABLE stores_fts;
+
+CREATE VIRTUAL TABLE stores_fts USING fts5 (
+    name,
+    branch,
+    aliases, -- alias de store_aliases, separados por espacio
+    tokenize = 'unicode61 remove_diacritics 2',
+    prefix = '2 3'
+);
+
+INSERT INTO stores_fts (rowid, name, branch, aliases)
+SELECT s.id, s.name, COALESCE(s.branch, ''),
+       (SELECT COALESCE(group_concat(alias, ' '), '') FROM store_aliases WHERE store_id = s.id)
+FROM stores s;
+
+-- Al deshacer el borrado de una tienda, sus alias pueden volver antes que ella
+CREATE TRIGGER trg_stores_fts_insert AFTER INSERT ON stores BEGIN
+    INSERT INTO stores_fts (rowid, name, branch, aliases)
+    VALUES (
+        NEW.id,
+        NEW.name,
+        COALESCE(NEW.branch, ''),
+        (SELECT COALESCE(group_concat(alias, ' '), '') FROM store_aliases WHERE store_id = NEW.id)
+    );
+END;
+
+CREATE TRIGGER trg_stores_fts_update AFTER UPDATE OF name, branch ON stores BEGIN
+    UPDATE stores_fts SET name = NEW.name, branch = COALESCE(NEW.branch, '') WHERE rowid = NEW.id;
+END;
+
+CREATE TRIGGER trg_stores_fts_delete AFTER DELETE ON stores BEGIN
+    DELETE FROM stores_fts WHERE rowid = OLD.id;
+END;
+
+CREATE TRIGGER trg_store_aliases_fts_insert AFTER INSERT ON store_aliases BEGIN
+    UPDATE stores_fts
+    SET aliases = (SELECT COALESCE(group_concat(alias, ' '), '') FROM store_aliases WHERE store_id = NEW.store_id)
+    WHERE rowid = NEW.store_id;
+END;
+
+-- Un alias puede pasar a otra tienda (al fusionar): se recalculan las dos
+CREATE TRIGGER trg_store_aliases_fts_update AFTER UPDATE ON store_aliases BEGIN
+    UPDATE stores_fts
+    SET aliases = (SELECT COALESCE(group_concat(alias, ' '), '') FROM store_aliases WHERE store_id = OLD.store_id)
+    WHERE rowid = OLD.store_id;
+    UPDATE stores_fts
+    SET aliases = (SELECT COALESCE(group_concat(alias, ' '), '') FROM store_aliases WHERE store_id = NEW.store_id)
+    WHERE rowid = NEW.store_id;
+END;
+
+CREATE TRIGGER trg_store_aliases_fts_delete AFTER DELETE ON store_aliases BEGIN
+    UPDATE stores_fts
+    SET aliases = (SELECT COALESCE(group_concat(alias, ' '), '') FROM store_aliases WHERE store_id = OLD.store_id)
+    WHERE rowid = OLD.store_id;
+END;
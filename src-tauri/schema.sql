@@ -4,6 +4,8 @@
 --    - Monetary values stored as INTEGER (cents / centavos)
 --    - Dates stored as TEXT in ISO 8601 format (YYYY-MM-DD)
 --    - created_at as TEXT datetime
+--    - This file is migration 1 (see db.rs): later changes go in new
+--      migrations, existing databases never run it again
 -- =============================================================
 
 PRAGMA foreign_keys = ON;
@@ -82,6 +84,28 @@ CREATE TABLE movements (
     FOREIGN KEY (category_id) REFERENCES categories  (id) ON DELETE SET NULL
 );
 
+-- -------------------------------------------------------------
+--  MOVEMENT SPLITS
+--  Desglose opcional de un movimiento en varias líneas, cada una con
+--  su propio monto y categoría (ej: ticket de súper con comida + limpieza).
+--
+--    - amount: monto de la línea en la moneda original del movimiento (centavos)
+--    - La suma de amount debe coincidir con movements.original_amount
+--      (validado en la aplicación al guardar el desglose).
+--    - Si un movimiento tiene líneas, los reportes por categoría usan las
+--      líneas en lugar de movements.category_id.
+-- -------------------------------------------------------------
+CREATE TABLE movement_splits (
+    id          INTEGER PRIMARY KEY NOT NULL,
+    mov_id      INTEGER NOT NULL,
+    amount      INTEGER NOT NULL, -- en centavos, moneda original del movimiento
+    category_id INTEGER,
+    note        TEXT,
+    created_at  TEXT    NOT NULL DEFAULT (datetime('now')),
+    FOREIGN KEY (mov_id)      REFERENCES movements  (id) ON DELETE CASCADE,
+    FOREIGN KEY (category_id) REFERENCES categories (id) ON DELETE SET NULL
+);
+
 -- -------------------------------------------------------------
 --  TRANSFERS
 --  Vincula los dos movimientos de una transferencia interna.
@@ -118,34 +142,197 @@ CREATE TABLE movements_groups (
     UNIQUE (mov_id, group_id)
 );
 
+-- -------------------------------------------------------------
+--  TAGS
+--  Etiquetas libres y livianas para movimientos (a diferencia de groups,
+--  no tienen descripción ni se editan como una lista cerrada).
+--  El nombre es único sin distinguir mayúsculas/minúsculas.
+-- -------------------------------------------------------------
+CREATE TABLE tags (
+    id         INTEGER PRIMARY KEY NOT NULL,
+    name       TEXT NOT NULL UNIQUE COLLATE NOCASE,
+    created_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+-- Relación N:M entre movements y tags
+CREATE TABLE movements_tags (
+    id     INTEGER PRIMARY KEY NOT NULL,
+    mov_id INTEGER NOT NULL,
+    tag_id INTEGER NOT NULL,
+    FOREIGN KEY (mov_id) REFERENCES movements (id) ON DELETE CASCADE,
+    FOREIGN KEY (tag_id) REFERENCES tags      (id) ON DELETE CASCADE,
+    UNIQUE (mov_id, tag_id)
+);
+
+-- -------------------------------------------------------------
+--  SCHEDULED MOVEMENTS
+--  Movimientos futuros conocidos: cuotas de una compra, un alquiler
+--  pactado, un plazo fijo que vence. No son movimientos todavía; el
+--  pronóstico de flujo de caja (forecast.rs) los proyecta.
+--  Una compra en 12 cuotas: interval_months = 1, occurrences = 12.
+-- -------------------------------------------------------------
+CREATE TABLE scheduled_movements (
+    id              INTEGER PRIMARY KEY NOT NULL,
+    details         TEXT    NOT NULL,
+    mov_type        TEXT    NOT NULL CHECK (mov_type IN ('income', 'expense')),
+    amount          INTEGER NOT NULL CHECK (amount > 0), -- en centavos, moneda de la cuenta
+    account_id      INTEGER NOT NULL,
+    category_id     INTEGER,
+    start_date      TEXT    NOT NULL, -- fecha de la primera ocurrencia
+    interval_months INTEGER NOT NULL DEFAULT 1 CHECK (interval_months >= 1),
+    occurrences     INTEGER CHECK (occurrences IS NULL OR occurrences > 0), -- NULL = sin fin
+    created_at      TEXT    NOT NULL DEFAULT (datetime('now')),
+    FOREIGN KEY (account_id)  REFERENCES accounts   (id) ON DELETE CASCADE,
+    FOREIGN KEY (category_id) REFERENCES categories (id) ON DELETE SET NULL
+);
+
+-- -------------------------------------------------------------
+--  SUBSCRIPTIONS
+--  Suscripciones confirmadas por el usuario (streaming, gimnasio,
+--  software). merchant es el detalle normalizado (utils::normalize_text)
+--  con el que se reconocen sus cobros entre los movimientos.
+--  subscription_prices guarda cada precio con su fecha de vigencia;
+--  subscriptions.amount es siempre el vigente.
+-- -------------------------------------------------------------
+CREATE TABLE subscriptions (
+    id               INTEGER PRIMARY KEY NOT NULL,
+    name             TEXT    NOT NULL,
+    merchant         TEXT    NOT NULL,
+    amount           INTEGER NOT NULL CHECK (amount > 0), -- en centavos, moneda de la suscripción
+    currency         TEXT    NOT NULL CHECK (currency IN ('ARS', 'USD')) DEFAULT 'ARS',
+    period           TEXT    NOT NULL DEFAULT 'monthly'
+                     CHECK (period IN ('weekly', 'biweekly', 'monthly', 'quarterly', 'yearly')),
+    account_id       INTEGER NOT NULL,
+    category_id      INTEGER,
+    next_charge_date TEXT    NOT NULL,
+    is_active        INTEGER NOT NULL DEFAULT 1,
+    created_at       TEXT    NOT NULL DEFAULT (datetime('now')),
+    FOREIGN KEY (account_id)  REFERENCES accounts   (id) ON DELETE CASCADE,
+    FOREIGN KEY (category_id) REFERENCES categories (id) ON DELETE SET NULL
+);
+
+CREATE TABLE subscription_prices (
+    id              INTEGER PRIMARY KEY NOT NULL,
+    subscription_id INTEGER NOT NULL,
+    amount          INTEGER NOT NULL CHECK (amount > 0),
+    effective_date  TEXT    NOT NULL,
+    FOREIGN KEY (subscription_id) REFERENCES subscriptions (id) ON DELETE CASCADE,
+    UNIQUE (subscription_id, effective_date)
+);
+
+-- -------------------------------------------------------------
+--  SAVINGS GOALS
+--  Objetivos de ahorro ligados a una cuenta:
+--    - earmarked = 0: el saldo de toda la cuenta cuenta como ahorrado
+--      (la moneda del objetivo debe ser la de la cuenta).
+--    - earmarked = 1: solo una parte de la cuenta está reservada; lo
+--      ahorrado es initial_amount más los aportes vinculados.
+--  Los aportes son movimientos de la cuenta vinculados al objetivo:
+--  income y transfer suman, expense resta (retiro).
+-- -------------------------------------------------------------
+CREATE TABLE goals (
+    id             INTEGER PRIMARY KEY NOT NULL,
+    name           TEXT    NOT NULL,
+    target_amount  INTEGER NOT NULL CHECK (target_amount > 0), -- en centavos, moneda del objetivo
+    currency       TEXT    NOT NULL CHECK (currency IN ('ARS', 'USD')) DEFAULT 'ARS',
+    target_date    TEXT,             -- NULL = sin fecha límite
+    account_id     INTEGER NOT NULL,
+    earmarked      INTEGER NOT NULL DEFAULT 0,
+    initial_amount INTEGER NOT NULL DEFAULT 0 CHECK (initial_amount >= 0),
+    created_at     TEXT    NOT NULL DEFAULT (datetime('now')),
+    FOREIGN KEY (account_id) REFERENCES accounts (id) ON DELETE CASCADE
+);
+
+CREATE TABLE goal_contributions (
+    id      INTEGER PRIMARY KEY NOT NULL,
+    goal_id INTEGER NOT NULL,
+    mov_id  INTEGER NOT NULL,
+    FOREIGN KEY (goal_id) REFERENCES goals     (id) ON DELETE CASCADE,
+    FOREIGN KEY (mov_id)  REFERENCES movements (id) ON DELETE CASCADE,
+    UNIQUE (goal_id, mov_id)
+);
+
+-- -------------------------------------------------------------
+--  CPI (IPC)
+--  Índice de precios al consumidor mensual, cargado por el usuario
+--  (típicamente desde el CSV del INDEC). Solo importa la relación
+--  entre meses: cualquier base sirve. Lo usa cpi.rs para expresar
+--  montos en pesos constantes de un mes de referencia.
+-- -------------------------------------------------------------
+CREATE TABLE cpi_index (
+    id    INTEGER PRIMARY KEY NOT NULL,
+    month TEXT    NOT NULL UNIQUE, -- YYYY-MM
+    value REAL    NOT NULL CHECK (value > 0)
+);
+
+-- -------------------------------------------------------------
+--  EXCHANGE RATES
+--  Cotización histórica del dólar por tipo, cargada por el usuario.
+--  rate: centavos de ARS por USD, igual que movements.exchange_rate.
+--  La usa rates.rs para expresar reportes en USD a la cotización de
+--  la fecha de cada movimiento.
+-- -------------------------------------------------------------
+CREATE TABLE exchange_rates (
+    id        INTEGER PRIMARY KEY NOT NULL,
+    date      TEXT    NOT NULL,
+    rate_type TEXT    NOT NULL CHECK (rate_type IN ('blue', 'oficial', 'mep', 'ccl', 'cripto')),
+    rate      INTEGER NOT NULL CHECK (rate > 0),
+    UNIQUE (rate_type, date)
+);
+
 -- -------------------------------------------------------------
 --  ITEMS & STORES
 --  Para registrar el detalle de compras (qué se compró, dónde).
+--
+--  Unidad de medida:
+--    - items.unit: cómo se mide el ítem ('unit', 'kg', 'g', 'l', 'ml').
+--    - purchases.package_size: contenido de cada paquete en esa unidad
+--      (500 para un paquete de 500 g). NULL = 1.
+--    - purchases.quantity: cantidad de paquetes, con decimales (0.350 kg de queso).
+--    - purchases.price: precio de cada paquete.
+--  Ver v_purchase_unit_prices para el precio normalizado por kg, litro o unidad.
 -- -------------------------------------------------------------
 
 CREATE TABLE items (
     id         INTEGER PRIMARY KEY NOT NULL,
     name       TEXT NOT NULL,
     brand      TEXT,
+    unit       TEXT NOT NULL DEFAULT 'unit' CHECK (unit IN ('unit', 'kg', 'g', 'l', 'ml')),
+    barcode    TEXT,             -- EAN/UPC, solo dígitos. Único entre los ítems que lo tienen.
     is_archived INTEGER NOT NULL DEFAULT 0, -- 0 = Activo, 1 = Archivado (Soft Delete)
     created_at TEXT NOT NULL DEFAULT (datetime('now'))
 );
 
+-- Los nombres de tienda se comparan normalizados (sin mayúsculas, acentos ni
+-- puntuación) contra el nombre y los alias: "Coto", "COTO " y "coto" son la
+-- misma tienda. La unicidad se controla en stores.rs.
 CREATE TABLE stores (
     id         INTEGER PRIMARY KEY NOT NULL,
     name       TEXT NOT NULL,
     color      TEXT,
+    branch     TEXT,             -- Sucursal, opcional
+    address    TEXT,
     created_at TEXT NOT NULL DEFAULT (datetime('now'))
 );
 
-CREATE TABLE purchases (
+-- Otros nombres con los que aparece una tienda (en tickets, al cargar compras)
+CREATE TABLE store_aliases (
     id         INTEGER PRIMARY KEY NOT NULL,
-    price      INTEGER NOT NULL, -- En centavos, misma moneda que el movimiento
-    quantity   INTEGER NOT NULL DEFAULT 1,
-    mov_id     INTEGER NOT NULL, -- La compra debe estar asociada a un movimiento, sin excepciones.
-    item_id    INTEGER NOT NULL, -- Al igual que el movimiento, el ítem debe existir antes de la compra.
-    store_id   INTEGER,          -- Nullable para soportar ON DELETE SET NULL
-    created_at TEXT    NOT NULL DEFAULT (datetime('now')),
+    store_id   INTEGER NOT NULL,
+    alias      TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    FOREIGN KEY (store_id) REFERENCES stores (id) ON DELETE CASCADE
+);
+
+CREATE TABLE purchases (
+    id           INTEGER PRIMARY KEY NOT NULL,
+    price        INTEGER NOT NULL, -- En centavos, misma moneda que el movimiento. Precio por paquete.
+    quantity     REAL    NOT NULL DEFAULT 1, -- Cantidad de paquetes, admite decimales
+    package_size REAL,             -- Contenido del paquete en la unidad del ítem, NULL = 1
+    mov_id       INTEGER NOT NULL, -- La compra debe estar asociada a un movimiento, sin excepciones.
+    item_id      INTEGER NOT NULL, -- Al igual que el movimiento, el ítem debe existir antes de la compra.
+    store_id     INTEGER,          -- Nullable para soportar ON DELETE SET NULL
+    created_at   TEXT    NOT NULL DEFAULT (datetime('now')),
 
     -- Si se borra el movimiento padre, se elimina el desglose automáticamente
     FOREIGN KEY (mov_id)   REFERENCES movements (id) ON DELETE CASCADE,
@@ -158,6 +345,68 @@ CREATE TABLE purchases (
     FOREIGN KEY (store_id) REFERENCES stores (id) ON DELETE SET NULL
 );
 
+-- -------------------------------------------------------------
+--  ATTACHMENTS
+--  Comprobantes (tickets, facturas PDF) guardados en el directorio de
+--  datos de la app. Direccionados por contenido: el archivo se guarda
+--  como attachments/<hash[0..2]>/<hash>, así el mismo archivo adjuntado
+--  dos veces ocupa lugar una sola vez.
+--
+--  attachment_links vincula cada archivo con un movimiento o una compra
+--  (exactamente uno de los dos). attachment_links forma parte del
+--  historial de undo, así que deshacer el borrado de un movimiento
+--  recupera sus adjuntos. Un archivo sin vínculos es huérfano, pero
+--  remove_orphans (ver attachments.rs) solo lo borra del disco cuando
+--  ninguna fila de journal_changes lo referencia.
+-- -------------------------------------------------------------
+CREATE TABLE attachments (
+    id         INTEGER PRIMARY KEY NOT NULL,
+    hash       TEXT    NOT NULL UNIQUE, -- SHA-256 en hexadecimal
+    file_name  TEXT    NOT NULL,        -- nombre original, para mostrar
+    mime_type  TEXT    NOT NULL,
+    size       INTEGER NOT NULL,        -- en bytes
+    created_at TEXT    NOT NULL DEFAULT (datetime('now'))
+);
+
+CREATE TABLE attachment_links (
+    id            INTEGER PRIMARY KEY NOT NULL,
+    attachment_id INTEGER NOT NULL,
+    mov_id        INTEGER,
+    purchase_id   INTEGER,
+    created_at    TEXT    NOT NULL DEFAULT (datetime('now')),
+    CHECK ((mov_id IS NULL) <> (purchase_id IS NULL)),
+    FOREIGN KEY (attachment_id) REFERENCES attachments (id) ON DELETE CASCADE,
+    FOREIGN KEY (mov_id)        REFERENCES movements   (id) ON DELETE CASCADE,
+    FOREIGN KEY (purchase_id)   REFERENCES purchases   (id) ON DELETE CASCADE
+);
+
+-- -------------------------------------------------------------
+--  SHOPPING LISTS
+--  Listas de compras sobre los ítems existentes. Al completar una
+--  lista se genera un movimiento con sus compras (mov_id); desde ese
+--  momento la lista queda cerrada.
+-- -------------------------------------------------------------
+CREATE TABLE shopping_lists (
+    id           INTEGER PRIMARY KEY NOT NULL,
+    name         TEXT NOT NULL,
+    mov_id       INTEGER,          -- Movimiento generado al completar la lista
+    completed_at TEXT,             -- NULL = lista abierta
+    created_at   TEXT NOT NULL DEFAULT (datetime('now')),
+    FOREIGN KEY (mov_id) REFERENCES movements (id) ON DELETE SET NULL
+);
+
+CREATE TABLE shopping_list_items (
+    id         INTEGER PRIMARY KEY NOT NULL,
+    list_id    INTEGER NOT NULL,
+    item_id    INTEGER NOT NULL,
+    quantity   REAL    NOT NULL DEFAULT 1, -- Cantidad deseada de paquetes
+    checked    INTEGER NOT NULL DEFAULT 0, -- 1 = ya está en el carrito
+    created_at TEXT    NOT NULL DEFAULT (datetime('now')),
+    FOREIGN KEY (list_id) REFERENCES shopping_lists (id) ON DELETE CASCADE,
+    FOREIGN KEY (item_id) REFERENCES items          (id) ON DELETE CASCADE,
+    UNIQUE (list_id, item_id)
+);
+
 -- =============================================================
 --  INDEXES
 -- =============================================================
@@ -166,13 +415,176 @@ CREATE INDEX idx_movements_category_id       ON movements        (category_id);
 CREATE INDEX idx_movements_date              ON movements        (date);
 CREATE INDEX idx_movements_groups_mov        ON movements_groups (mov_id);
 CREATE INDEX idx_movements_groups_group      ON movements_groups (group_id);
+CREATE INDEX idx_movements_tags_mov          ON movements_tags   (mov_id);
+CREATE INDEX idx_movements_tags_tag          ON movements_tags   (tag_id);
 CREATE INDEX idx_purchases_mov               ON purchases        (mov_id);
 CREATE INDEX idx_purchases_item              ON purchases        (item_id);
 CREATE INDEX idx_purchases_store             ON purchases        (store_id);
+CREATE INDEX idx_store_aliases_store        ON store_aliases    (store_id);
+CREATE INDEX idx_movement_splits_mov         ON movement_splits  (mov_id);
+CREATE INDEX idx_movement_splits_category    ON movement_splits  (category_id);
 CREATE INDEX idx_balance_snapshots_account   ON balance_snapshots (account_id, snapshot_date);
+CREATE INDEX idx_attachment_links_mov        ON attachment_links (mov_id);
+CREATE INDEX idx_attachment_links_purchase   ON attachment_links (purchase_id);
+-- Un mismo archivo no se vincula dos veces al mismo registro
+CREATE UNIQUE INDEX idx_attachment_links_mov_unique      ON attachment_links (attachment_id, mov_id)      WHERE mov_id IS NOT NULL;
+CREATE UNIQUE INDEX idx_attachment_links_purchase_unique ON attachment_links (attachment_id, purchase_id) WHERE purchase_id IS NOT NULL;
+-- Un código de barras identifica a un solo ítem
+CREATE UNIQUE INDEX idx_items_barcode                    ON items            (barcode)                     WHERE barcode IS NOT NULL;
 -- CREATE INDEX idx_transfers_debit             ON transfers        (debit_mov_id);
 -- CREATE INDEX idx_transfers_credit            ON transfers        (credit_mov_id);
 
+-- =============================================================
+--  HISTORIAL DE OPERACIONES (undo / redo)
+--  Cada comando que modifica datos abre una operación en journal_ops.
+--  Mientras está abierta (journal_state.current_op), los triggers de
+--  journal (generados al iniciar, ver history.rs) guardan cada fila
+--  insertada/modificada/borrada en journal_changes, con su estado
+--  anterior y posterior como JSON. Deshacer = aplicar esos cambios al revés.
+-- =============================================================
+CREATE TABLE journal_ops (
+    id         INTEGER PRIMARY KEY NOT NULL,
+    label      TEXT    NOT NULL, -- descripción legible: "Eliminar movimiento"
+    status     TEXT    NOT NULL CHECK (status IN ('done', 'undone')) DEFAULT 'done',
+    created_at TEXT    NOT NULL DEFAULT (datetime('now'))
+);
+
+CREATE TABLE journal_changes (
+    id         INTEGER PRIMARY KEY NOT NULL,
+    op_id      INTEGER NOT NULL,
+    table_name TEXT    NOT NULL,
+    row_id     INTEGER NOT NULL,
+    old_row    TEXT, -- JSON, NULL si fue un INSERT
+    new_row    TEXT, -- JSON, NULL si fue un DELETE
+    FOREIGN KEY (op_id) REFERENCES journal_ops (id) ON DELETE CASCADE
+);
+
+-- Fila única con la operación abierta (NULL = no se registra nada).
+CREATE TABLE journal_state (
+    id         INTEGER PRIMARY KEY NOT NULL CHECK (id = 1),
+    current_op INTEGER
+);
+
+INSERT INTO journal_state (id, current_op) VALUES (1, NULL);
+
+CREATE INDEX idx_journal_changes_op ON journal_changes (op_id);
+
+-- =============================================================
+--  AUDITORÍA
+--  Registro de solo-agregado de cada cambio sobre los registros
+--  financieros. Lo llenan triggers generados al iniciar (ver audit.rs),
+--  así queda registrado cualquier cambio, venga del comando que venga
+--  (incluidos undo/redo).
+-- =============================================================
+CREATE TABLE audit_log (
+    id          INTEGER PRIMARY KEY NOT NULL,
+    entity      TEXT    NOT NULL, -- nombre de la tabla: 'movements', 'accounts', ...
+    entity_id   INTEGER NOT NULL,
+    action      TEXT    NOT NULL CHECK (action IN ('insert', 'update', 'delete')),
+    before_json TEXT, -- NULL en insert
+    after_json  TEXT, -- NULL en delete
+    changed_at  TEXT    NOT NULL DEFAULT (datetime('now'))
+);
+
+CREATE INDEX idx_audit_log_entity     ON audit_log (entity, entity_id);
+CREATE INDEX idx_audit_log_changed_at ON audit_log (changed_at);
+
+-- Solo-agregado: nada puede modificar ni borrar el historial.
+CREATE TRIGGER trg_audit_log_no_update BEFORE UPDATE ON audit_log BEGIN
+    SELECT RAISE(ABORT, 'audit_log es de solo-agregado');
+END;
+
+CREATE TRIGGER trg_audit_log_no_delete BEFORE DELETE ON audit_log BEGIN
+    SELECT RAISE(ABORT, 'audit_log es de solo-agregado');
+END;
+
+-- =============================================================
+--  FULL-TEXT SEARCH (FTS5)
+--  Un índice por entidad, con rowid = id de la fila original, para que
+--  los triggers puedan actualizar/borrar por rowid sin recorrer el índice.
+--  unicode61 + remove_diacritics: "cafe" matchea "Café" (sin acentos ni mayúsculas).
+--  prefix: acelera las búsquedas por prefijo usadas al tipear ("caf*").
+-- =============================================================
+CREATE VIRTUAL TABLE movements_fts USING fts5 (
+    details,
+    notes, -- notas de las líneas de desglose (movement_splits)
+    tokenize = 'unicode61 remove_diacritics 2',
+    prefix = '2 3'
+);
+
+CREATE VIRTUAL TABLE items_fts USING fts5 (
+    name,
+    brand,
+    tokenize = 'unicode61 remove_diacritics 2',
+    prefix = '2 3'
+);
+
+-- La migración 2 (migrations/002_stores_fts_aliases.sql) la recrea con
+-- sucursal y alias.
+CREATE VIRTUAL TABLE stores_fts USING fts5 (
+    name,
+    tokenize = 'unicode61 remove_diacritics 2',
+    prefix = '2 3'
+);
+
+-- -------------------------------------------------------------
+--  Triggers de sincronización
+-- -------------------------------------------------------------
+CREATE TRIGGER trg_movements_fts_insert AFTER INSERT ON movements BEGIN
+    INSERT INTO movements_fts (rowid, details, notes) VALUES (NEW.id, NEW.details, '');
+END;
+
+CREATE TRIGGER trg_movements_fts_update AFTER UPDATE OF details ON movements BEGIN
+    UPDATE movements_fts SET details = NEW.details WHERE rowid = NEW.id;
+END;
+
+CREATE TRIGGER trg_movements_fts_delete AFTER DELETE ON movements BEGIN
+    DELETE FROM movements_fts WHERE rowid = OLD.id;
+END;
+
+-- Las notas del desglose se recalculan completas ante cualquier cambio de líneas.
+CREATE TRIGGER trg_movement_splits_fts_insert AFTER INSERT ON movement_splits BEGIN
+    UPDATE movements_fts
+    SET notes = (SELECT COALESCE(group_concat(note, ' '), '') FROM movement_splits WHERE mov_id = NEW.mov_id)
+    WHERE rowid = NEW.mov_id;
+END;
+
+CREATE TRIGGER trg_movement_splits_fts_update AFTER UPDATE ON movement_splits BEGIN
+    UPDATE movements_fts
+    SET notes = (SELECT COALESCE(group_concat(note, ' '), '') FROM movement_splits WHERE mov_id = NEW.mov_id)
+    WHERE rowid = NEW.mov_id;
+END;
+
+CREATE TRIGGER trg_movement_splits_fts_delete AFTER DELETE ON movement_splits BEGIN
+    UPDATE movements_fts
+    SET notes = (SELECT COALESCE(group_concat(note, ' '), '') FROM movement_splits WHERE mov_id = OLD.mov_id)
+    WHERE rowid = OLD.mov_id;
+END;
+
+CREATE TRIGGER trg_items_fts_insert AFTER INSERT ON items BEGIN
+    INSERT INTO items_fts (rowid, name, brand) VALUES (NEW.id, NEW.name, COALESCE(NEW.brand, ''));
+END;
+
+CREATE TRIGGER trg_items_fts_update AFTER UPDATE OF name, brand ON items BEGIN
+    UPDATE items_fts SET name = NEW.name, brand = COALESCE(NEW.brand, '') WHERE rowid = NEW.id;
+END;
+
+CREATE TRIGGER trg_items_fts_delete AFTER DELETE ON items BEGIN
+    DELETE FROM items_fts WHERE rowid = OLD.id;
+END;
+
+CREATE TRIGGER trg_stores_fts_insert AFTER INSERT ON stores BEGIN
+    INSERT INTO stores_fts (rowid, name) VALUES (NEW.id, NEW.name);
+END;
+
+CREATE TRIGGER trg_stores_fts_update AFTER UPDATE OF name ON stores BEGIN
+    UPDATE stores_fts SET name = NEW.name WHERE rowid = NEW.id;
+END;
+
+CREATE TRIGGER trg_stores_fts_delete AFTER DELETE ON stores BEGIN
+    DELETE FROM stores_fts WHERE rowid = OLD.id;
+END;
+
 -- =============================================================
 --  VIEWS
 -- =============================================================
@@ -195,27 +607,65 @@ FROM movements m
 JOIN  accounts   a ON m.account_id  = a.id
 LEFT JOIN categories c ON m.category_id = c.id;
 
+-- Líneas imputables por categoría: una fila por línea de desglose si el
+-- movimiento tiene movement_splits, o el movimiento completo si no tiene.
+-- Expone las mismas columnas que movements (id = id del movimiento), así
+-- los filtros armados para movements se pueden aplicar tal cual.
+-- El ars_amount de cada línea es proporcional al de su movimiento.
+CREATE VIEW v_movement_lines AS
+SELECT
+    m.id,
+    m.details,
+    m.date,
+    m.mov_type,
+    m.currency,
+    m.original_amount,
+    m.ars_amount,
+    m.account_id,
+    m.category_id,
+    NULL AS split_id
+FROM movements m
+WHERE NOT EXISTS (SELECT 1 FROM movement_splits s WHERE s.mov_id = m.id)
+UNION ALL
+SELECT
+    m.id,
+    m.details,
+    m.date,
+    m.mov_type,
+    m.currency,
+    s.amount AS original_amount,
+    CASE
+        WHEN m.original_amount = 0 THEN 0
+        ELSE CAST(ROUND(s.amount * 1.0 * m.ars_amount / m.original_amount) AS INTEGER)
+    END AS ars_amount,
+    m.account_id,
+    s.category_id,
+    s.id AS split_id
+FROM movements m
+JOIN movement_splits s ON s.mov_id = m.id;
+
 -- Gastos mensuales agrupados por categoría (en ARS)
--- Solo incluye mov_type = 'expense'
+-- Solo incluye mov_type = 'expense'. Usa las líneas de desglose si existen.
 CREATE VIEW v_monthly_expenses_by_category AS
 SELECT
-    strftime('%Y-%m', m.date) AS month,
+    strftime('%Y-%m', l.date) AS month,
     COALESCE(c.name, 'Sin categoría') AS category,
-    SUM(m.ars_amount) AS total_ars
-FROM movements m
-LEFT JOIN categories c ON m.category_id = c.id
-WHERE m.mov_type = 'expense'
+    SUM(l.ars_amount) AS total_ars
+FROM v_movement_lines l
+LEFT JOIN categories c ON l.category_id = c.id
+WHERE l.mov_type = 'expense'
 GROUP BY month, c.name;
 
 -- Ingresos mensuales agrupados por categoría (en ARS)
+-- Usa las líneas de desglose si existen.
 CREATE VIEW v_monthly_income_by_category AS
 SELECT
-    strftime('%Y-%m', m.date) AS month,
+    strftime('%Y-%m', l.date) AS month,
     COALESCE(c.name, 'Sin categoría') AS category,
-    SUM(m.ars_amount) AS total_ars
-FROM movements m
-LEFT JOIN categories c ON m.category_id = c.id
-WHERE m.mov_type = 'income'
+    SUM(l.ars_amount) AS total_ars
+FROM v_movement_lines l
+LEFT JOIN categories c ON l.category_id = c.id
+WHERE l.mov_type = 'income'
 GROUP BY month, c.name;
 
 -- Historial de compras con detalle de ítem y tienda
@@ -234,6 +684,32 @@ JOIN items     i ON p.item_id  = i.id
 JOIN movements m ON p.mov_id   = m.id
 LEFT JOIN stores s ON p.store_id = s.id;
 
+-- Precio de cada compra normalizado a una unidad estándar, para comparar
+-- tiendas y tamaños de paquete: por kg (kg, g), por litro (l, ml) o por unidad.
+-- unit_price: centavos por unidad estándar, en la moneda del movimiento.
+CREATE VIEW v_purchase_unit_prices AS
+SELECT
+    p.id       AS purchase_id,
+    p.item_id,
+    p.store_id,
+    p.mov_id,
+    m.date,
+    m.currency,
+    p.price,
+    p.quantity,
+    p.package_size,
+    i.unit,
+    CASE i.unit
+        WHEN 'g'  THEN 'kg'
+        WHEN 'ml' THEN 'l'
+        ELSE i.unit
+    END AS standard_unit,
+    CAST(ROUND(p.price / (COALESCE(p.package_size, 1) *
+        CASE WHEN i.unit IN ('g', 'ml') THEN 0.001 ELSE 1 END)) AS INTEGER) AS unit_price
+FROM purchases p
+JOIN items     i ON p.item_id = i.id
+JOIN movements m ON p.mov_id  = m.id;
+
 -- Balance actual por cuenta
 -- Uso: filtrar por account_id en la aplicación.
 CREATE VIEW v_account_balance AS
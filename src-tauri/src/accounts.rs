@@ -1,7 +1,7 @@
 use rusqlite::{params, ToSql};
 use serde::{Deserialize, Serialize};
 
-use crate::AppState;
+use crate::{errors::OrbitError, history, AppState};
 
 #[derive(Debug, Deserialize, Serialize)]
 pub enum Currency {
@@ -73,8 +73,12 @@ pub struct AddAccount {
 }
 
 #[tauri::command]
-pub fn add_account(state: tauri::State<AppState>, account: AddAccount) -> Account {
+pub fn add_account(
+    state: tauri::State<AppState>,
+    account: AddAccount,
+) -> Result<Account, OrbitError> {
     let conn = state.conn.lock().unwrap();
+    let op = history::begin(&conn, "Agregar cuenta")?;
 
     conn.execute(
         "INSERT INTO accounts (name, acc_type, currency, notes) VALUES (?1, ?2, ?3, ?4)",
@@ -84,8 +88,7 @@ pub fn add_account(state: tauri::State<AppState>, account: AddAccount) -> Accoun
             account.currency,
             account.notes
         ],
-    )
-    .unwrap();
+    )?;
 
     let account_id = conn.last_insert_rowid();
     let initial_balance_in_cents = (account.initial_balance * 100.0) as i64;
@@ -94,10 +97,9 @@ pub fn add_account(state: tauri::State<AppState>, account: AddAccount) -> Accoun
     conn.execute(
         "INSERT INTO balance_snapshots (account_id, balance, snapshot_date) VALUES (?1, ?2, ?3)",
         params![account_id, initial_balance_in_cents, now],
-    )
-    .unwrap();
+    )?;
 
-    conn.query_row(
+    let account = conn.query_row(
         "SELECT a.id, a.name, a.acc_type, a.currency, a.created_at, a.notes,
                 COALESCE(SUM(bs.balance), 0) as balance
          FROM accounts a
@@ -116,25 +118,31 @@ pub fn add_account(state: tauri::State<AppState>, account: AddAccount) -> Accoun
                 balance: row.get(6)?,
             })
         },
-    )
-    .unwrap()
+    )?;
+
+    op.finish();
+
+    Ok(account)
 }
 
 #[tauri::command]
-pub fn delete_account(state: tauri::State<AppState>, id: i64) {
+pub fn delete_account(state: tauri::State<AppState>, id: i64) -> Result<(), OrbitError> {
     let conn = state.conn.lock().unwrap();
+    let op = history::begin(&conn, "Eliminar cuenta")?;
 
     // First we delete all snapshots for this account
     // WARNING: if you try to delete the account first and skip this step, you'll get a foreign key constraint violation, therefore the app will crash
     conn.execute(
         "DELETE FROM balance_snapshots WHERE account_id = ?1",
         params![id],
-    )
-    .unwrap();
+    )?;
 
     // Then we delete the account itself
-    conn.execute("DELETE FROM accounts WHERE id = ?1", params![id])
-        .unwrap();
+    conn.execute("DELETE FROM accounts WHERE id = ?1", params![id])?;
+
+    op.finish();
+
+    Ok(())
 }
 
 #[derive(Debug, Deserialize)]
@@ -145,16 +153,20 @@ pub struct UpdateAccount {
 }
 
 #[tauri::command]
-pub fn update_account(state: tauri::State<AppState>, id: i64, account: UpdateAccount) -> Account {
+pub fn update_account(
+    state: tauri::State<AppState>,
+    id: i64,
+    account: UpdateAccount,
+) -> Result<Account, OrbitError> {
     let conn = state.conn.lock().unwrap();
+    let op = history::begin(&conn, "Editar cuenta")?;
 
     conn.execute(
         "UPDATE accounts SET name = ?1, acc_type = ?2, notes = ?3 WHERE id = ?4",
         params![account.name, account.acc_type, account.notes, id],
-    )
-    .unwrap();
+    )?;
 
-    conn.query_row(
+    let account = conn.query_row(
         "SELECT a.id, a.name, a.acc_type, a.currency, a.created_at, a.notes,
                 COALESCE(SUM(bs.balance), 0) as balance
          FROM accounts a
@@ -173,6 +185,9 @@ pub fn update_account(state: tauri::State<AppState>, id: i64, account: UpdateAcc
                 balance: row.get(6)?,
             })
         },
-    )
-    .unwrap()
+    )?;
+
+    op.finish();
+
+    Ok(account)
 }
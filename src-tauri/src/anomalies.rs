@@ -0,0 +1,566 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+
+use chrono::NaiveDate;
+use rusqlite::params;
+use serde::Serialize;
+use tauri::{Emitter, Manager};
+
+use crate::{
+    errors::OrbitError,
+    forecast::{detect_period, median},
+    utils::{format_date, normalize_text, parse_date},
+};
+
+/// Event emitted with each new insight that involves a just-added movement.
+pub const INSIGHT_EVENT: &str = "anomaly-detected";
+
+/// Days back from today whose movements are analyzed by default.
+const DEFAULT_WINDOW_DAYS: i64 = 30;
+
+/// Months of history used as the baseline.
+const BASELINE_MONTHS: u32 = 12;
+
+/// An amount this many times the category median is unusual...
+const UNUSUAL_FACTOR: f64 = 3.0;
+
+/// ...and this many times, critical.
+const CRITICAL_FACTOR: f64 = 6.0;
+
+/// Minimum baseline movements in a category to judge an amount.
+const MIN_CATEGORY_SAMPLES: usize = 5;
+
+/// Minimum baseline movements before new merchants are reported.
+const MIN_HISTORY_FOR_NEW_MERCHANTS: usize = 20;
+
+/// Maximum days between two identical charges to flag them as a double charge.
+const DOUBLE_CHARGE_DAYS: i64 = 2;
+
+/// Minimum increase (percent) of a recurring charge to report it.
+const PRICE_JUMP_PERCENT: f64 = 10.0;
+
+/// Previous months compared against the current one for category spend.
+const SPIKE_BASELINE_MONTHS: u32 = 6;
+
+/// Month-to-date spend this many times the baseline median is a spike.
+const SPIKE_FACTOR: f64 = 1.5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Warning,
+    Critical,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InsightKind {
+    /// Amount far above the category's historical median
+    UnusualAmount,
+    /// First expense with a merchant never seen before
+    NewMerchant,
+    /// Same amount and merchant charged twice within a few days
+    DoubleCharge,
+    /// A recurring charge (subscription, service) got more expensive
+    PriceIncrease,
+    /// Category spend for the current month well above its usual level
+    CategorySpike,
+}
+
+/// Something unusual in the recent movements.
+#[derive(Debug, Clone, Serialize)]
+pub struct Insight {
+    /// Stable identifier: the same anomaly keeps the same key across scans
+    pub key: String,
+    pub kind: InsightKind,
+    pub severity: Severity,
+    pub message: String,
+    pub movement_ids: Vec<i64>,
+    pub category_id: Option<i64>,
+    /// Date of the most recent movement involved
+    pub date: String,
+    /// Amount that triggered the insight, in cents of `currency`
+    pub amount: i64,
+    /// What the history suggested instead, same currency
+    pub expected: Option<i64>,
+    pub currency: String,
+}
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Un gasto (movimiento completo o línea de desglose) del historial analizado.
+struct Expense {
+    id: i64,
+    details: String,
+    /// `details` normalizado, para agrupar por comercio
+    merchant: String,
+    date: NaiveDate,
+    ars_amount: i64,
+    original_amount: i64,
+    currency: String,
+    account_id: i64,
+    category_id: Option<i64>,
+    category: String,
+}
+
+/// Gastos entre `since` y `today`, ordenados por fecha. `source` debe ser
+/// `movements` (uno por movimiento) o `v_movement_lines` (uno por línea).
+fn load_expenses(
+    conn: &rusqlite::Connection,
+    source: &str,
+    since: NaiveDate,
+    today: NaiveDate,
+) -> Result<Vec<Expense>, OrbitError> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT e.id, e.details, e.date, e.ars_amount, e.original_amount, e.currency,
+                e.account_id, e.category_id, COALESCE(c.name, 'Sin categoría')
+         FROM {source} e
+         LEFT JOIN categories c ON c.id = e.category_id
+         WHERE e.mov_type = 'expense' AND e.date >= ?1 AND e.date <= ?2
+         ORDER BY e.date, e.id"
+    ))?;
+
+    let rows = stmt.query_map(params![format_date(since), format_date(today)], |row| {
+        Ok((
+            row.get::<_, i64>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, String>(2)?,
+            row.get::<_, i64>(3)?,
+            row.get::<_, i64>(4)?,
+            row.get::<_, String>(5)?,
+            row.get::<_, i64>(6)?,
+            row.get::<_, Option<i64>>(7)?,
+            row.get::<_, String>(8)?,
+        ))
+    })?;
+
+    let mut expenses = Vec::new();
+    for row in rows {
+        let (
+            id,
+            details,
+            date,
+            ars_amount,
+            original_amount,
+            currency,
+            account_id,
+            category_id,
+            category,
+        ) = row?;
+        // Fechas mal cargadas no se pueden ubicar en el tiempo; se ignoran
+        let Ok(date) = parse_date(&date) else {
+            continue;
+        };
+        expenses.push(Expense {
+            id,
+            merchant: normalize_text(&details),
+            details,
+            date,
+            ars_amount,
+            original_amount,
+            currency,
+            account_id,
+            category_id,
+            category,
+        });
+    }
+
+    Ok(expenses)
+}
+
+/// Gastos recientes muy por encima de la mediana histórica de su categoría.
+fn unusual_amounts(lines: &[Expense], since: NaiveDate) -> Vec<Insight> {
+    let mut samples: HashMap<Option<i64>, Vec<i64>> = HashMap::new();
+    for line in lines.iter().filter(|l| l.date < since) {
+        samples
+            .entry(line.category_id)
+            .or_default()
+            .push(line.ars_amount);
+    }
+    let typical: HashMap<Option<i64>, i64> = samples
+        .into_iter()
+        .filter(|(_, amounts)| amounts.len() >= MIN_CATEGORY_SAMPLES)
+        .filter_map(|(category, mut amounts)| Some((category, median(&mut amounts)?)))
+        .filter(|(_, median)| *median > 0)
+        .collect();
+
+    lines
+        .iter()
+        .filter(|l| l.date >= since)
+        .filter_map(|line| {
+            let expected = *typical.get(&line.category_id)?;
+            let factor = line.ars_amount as f64 / expected as f64;
+            if factor < UNUSUAL_FACTOR {
+                return None;
+            }
+            Some(Insight {
+                key: format!(
+                    "unusual_amount:{}:{}",
+                    line.id,
+                    line.category_id.unwrap_or(0)
+                ),
+                kind: InsightKind::UnusualAmount,
+                severity: if factor >= CRITICAL_FACTOR {
+                    Severity::Critical
+                } else {
+                    Severity::Warning
+                },
+                message: format!(
+                    "{:?} es {:.1} veces el gasto típico en {}",
+                    line.details, factor, line.category
+                ),
+                movement_ids: vec![line.id],
+                category_id: line.category_id,
+                date: format_date(line.date),
+                amount: line.ars_amount,
+                expected: Some(expected),
+                currency: "ARS".into(),
+            })
+        })
+        .collect()
+}
+
+/// Primer gasto reciente con un comercio que no aparece en el historial.
+fn new_merchants(movements: &[Expense], since: NaiveDate) -> Vec<Insight> {
+    let (history, recent): (Vec<&Expense>, Vec<&Expense>) =
+        movements.iter().partition(|m| m.date < since);
+    // Con poco historial todo parece nuevo
+    if history.len() < MIN_HISTORY_FOR_NEW_MERCHANTS {
+        return Vec::new();
+    }
+
+    let mut seen: HashSet<&str> = history.iter().map(|m| m.merchant.as_str()).collect();
+    recent
+        .into_iter()
+        .filter(|m| !m.merchant.is_empty() && seen.insert(m.merchant.as_str()))
+        .map(|m| Insight {
+            key: format!("new_merchant:{}", m.merchant),
+            kind: InsightKind::NewMerchant,
+            severity: Severity::Info,
+            message: format!("Primer gasto en {:?}", m.details),
+            movement_ids: vec![m.id],
+            category_id: m.category_id,
+            date: format_date(m.date),
+            amount: m.original_amount,
+            expected: None,
+            currency: m.currency.clone(),
+        })
+        .collect()
+}
+
+/// Dos cobros iguales (cuenta, moneda, monto y comercio) con pocos días de diferencia.
+fn double_charges(movements: &[Expense], since: NaiveDate) -> Vec<Insight> {
+    let mut insights = Vec::new();
+
+    // Ordenados por fecha: alcanza con mirar hacia atrás dentro de la ventana
+    for (i, current) in movements.iter().enumerate() {
+        if current.date < since {
+            continue;
+        }
+        let previous = movements[..i]
+            .iter()
+            .rev()
+            .take_while(|p| (current.date - p.date).num_days() <= DOUBLE_CHARGE_DAYS)
+            .find(|p| {
+                p.account_id == current.account_id
+                    && p.currency == current.currency
+                    && p.original_amount == current.original_amount
+                    && p.merchant == current.merchant
+            });
+        let Some(previous) = previous else {
+            continue;
+        };
+
+        insights.push(Insight {
+            key: format!("double_charge:{}:{}", previous.id, current.id),
+            kind: InsightKind::DoubleCharge,
+            severity: Severity::Warning,
+            message: format!(
+                "{:?} se cobró dos veces por el mismo monto ({} y {})",
+                current.details,
+                format_date(previous.date),
+                format_date(current.date)
+            ),
+            movement_ids: vec![previous.id, current.id],
+            category_id: current.category_id,
+            date: format_date(current.date),
+            amount: current.original_amount,
+            expected: None,
+            currency: current.currency.clone(),
+        });
+    }
+
+    insights
+}
+
+/// Cargos recurrentes cuyo último monto subió respecto de los anteriores.
+fn price_increases(movements: &[Expense], since: NaiveDate) -> Vec<Insight> {
+    let mut groups: HashMap<(i64, &str, &str), Vec<&Expense>> = HashMap::new();
+    for movement in movements.iter().filter(|m| !m.merchant.is_empty()) {
+        groups
+            .entry((
+                movement.account_id,
+                movement.currency.as_str(),
+                movement.merchant.as_str(),
+            ))
+            .or_default()
+            .push(movement);
+    }
+
+    let mut insights = Vec::new();
+    for occurrences in groups.values() {
+        let [.., previous, last] = occurrences.as_slice() else {
+            continue;
+        };
+        if last.date < since {
+            continue;
+        }
+        let dates: Vec<NaiveDate> = occurrences.iter().map(|m| m.date).collect();
+        if detect_period(&dates).is_none() {
+            continue;
+        }
+
+        // Las ocurrencias anteriores más recientes reflejan el precio vigente
+        let mut before: Vec<i64> = occurrences[..occurrences.len() - 1]
+            .iter()
+            .rev()
+            .take(3)
+            .map(|m| m.original_amount)
+            .collect();
+        let Some(expected) = median(&mut before).filter(|e| *e > 0) else {
+            continue;
+        };
+        let percent = (last.original_amount - expected) as f64 / expected as f64 * 100.0;
+        if percent < PRICE_JUMP_PERCENT {
+            continue;
+        }
+
+        insights.push(Insight {
+            key: format!("price_increase:{}", last.id),
+            kind: InsightKind::PriceIncrease,
+            severity: Severity::Warning,
+            message: format!("{:?} aumentó un {:.0}%", last.details, percent),
+            movement_ids: vec![previous.id, last.id],
+            category_id: last.category_id,
+            date: format_date(last.date),
+            amount: last.original_amount,
+            expected: Some(expected),
+            currency: last.currency.clone(),
+        });
+    }
+
+    insights
+}
+
+/// Categorías cuyo gasto del mes en curso ya supera con holgura la mediana de
+/// los meses anteriores (los meses sin gastos cuentan como cero).
+fn category_spikes(lines: &[Expense], today: NaiveDate) -> Vec<Insight> {
+    let month_of = |date: NaiveDate| date.format("%Y-%m").to_string();
+    let current_month = month_of(today);
+    let first_month = today
+        .checked_sub_months(chrono::Months::new(SPIKE_BASELINE_MONTHS))
+        .map(month_of)
+        .unwrap_or_else(|| current_month.clone());
+
+    // Por categoría: total de cada mes anterior y líneas del mes en curso
+    type Spend<'a> = (HashMap<String, i64>, Vec<&'a Expense>);
+    let mut by_category: HashMap<Option<i64>, Spend> = HashMap::new();
+    for line in lines {
+        let month = month_of(line.date);
+        if month < first_month {
+            continue;
+        }
+        let (months, current) = by_category.entry(line.category_id).or_default();
+        if month == current_month {
+            current.push(line);
+        } else {
+            *months.entry(month).or_default() += line.ars_amount;
+        }
+    }
+
+    let mut insights = Vec::new();
+    for (category_id, (months, current)) in by_category {
+        let Some(latest) = current.last() else {
+            continue;
+        };
+        let mut baseline: Vec<i64> = months.values().copied().collect();
+        baseline.resize(SPIKE_BASELINE_MONTHS as usize, 0);
+        let Some(expected) = median(&mut baseline).filter(|e| *e > 0) else {
+            continue;
+        };
+        let spent: i64 = current.iter().map(|l| l.ars_amount).sum();
+        let factor = spent as f64 / expected as f64;
+        if factor < SPIKE_FACTOR {
+            continue;
+        }
+
+        let mut movement_ids: Vec<i64> = current.iter().map(|l| l.id).collect();
+        movement_ids.dedup();
+
+        insights.push(Insight {
+            key: format!(
+                "category_spike:{}:{current_month}",
+                category_id.unwrap_or(0)
+            ),
+            kind: InsightKind::CategorySpike,
+            severity: if factor >= SPIKE_FACTOR * 2.0 {
+                Severity::Critical
+            } else {
+                Severity::Warning
+            },
+            message: format!(
+                "El gasto en {} de este mes ya es {:.1} veces el habitual",
+                latest.category, factor
+            ),
+            movement_ids,
+            category_id,
+            date: format_date(latest.date),
+            amount: spent,
+            expected: Some(expected),
+            currency: "ARS".into(),
+        });
+    }
+
+    insights
+}
+
+/// Corre todos los análisis sobre los gastos de los últimos `days` días,
+/// comparándolos con el historial. Más graves y más recientes primero.
+fn detect(
+    conn: &rusqlite::Connection,
+    today: NaiveDate,
+    days: i64,
+) -> Result<Vec<Insight>, OrbitError> {
+    let since = today - chrono::Duration::days(days);
+    let history_since = today
+        .checked_sub_months(chrono::Months::new(BASELINE_MONTHS))
+        .unwrap_or(today)
+        .min(since);
+
+    let movements = load_expenses(conn, "movements", history_since, today)?;
+    let lines = load_expenses(conn, "v_movement_lines", history_since, today)?;
+
+    let mut insights = unusual_amounts(&lines, since);
+    insights.extend(new_merchants(&movements, since));
+    insights.extend(double_charges(&movements, since));
+    insights.extend(price_increases(&movements, since));
+    insights.extend(category_spikes(&lines, today));
+
+    insights.sort_by(|a, b| {
+        b.severity
+            .cmp(&a.severity)
+            .then(b.date.cmp(&a.date))
+            .then(a.key.cmp(&b.key))
+    });
+
+    Ok(insights)
+}
+
+/// Emite `INSIGHT_EVENT` por cada anomalía que involucra a `mov_id` y no se
+/// había notificado antes. Un error en el análisis no debe hacer fallar la
+/// carga del movimiento, así que se ignora. Devuelve las notificadas.
+fn notify_new_insights(
+    app: &tauri::AppHandle,
+    conn: &rusqlite::Connection,
+    notified: &Mutex<HashSet<String>>,
+    mov_id: i64,
+) -> Vec<Insight> {
+    let today = chrono::Local::now().date_naive();
+    let Ok(insights) = detect(conn, today, DEFAULT_WINDOW_DAYS) else {
+        return Vec::new();
+    };
+
+    let mut notified = notified.lock().unwrap();
+    insights
+        .into_iter()
+        .filter(|insight| insight.movement_ids.contains(&mov_id))
+        .filter(|insight| notified.insert(insight.key.clone()))
+        .inspect(|insight| {
+            let _ = app.emit(INSIGHT_EVENT, insight);
+        })
+        .collect()
+}
+
+/// Corre `notify_new_insights` en segundo plano, con su propio lock de la
+/// base. Llamar después de soltar el lock: el análisis recorre todo el
+/// período y la carga del movimiento no tiene por qué esperarlo.
+pub(crate) fn spawn_insight_check(app: tauri::AppHandle, mov_id: i64) {
+    tauri::async_runtime::spawn_blocking(move || {
+        let state = app.state::<crate::AppState>();
+        // Si otro comando entró en pánico con el lock tomado, no analizamos nada
+        let Ok(conn) = state.conn.lock() else {
+            return;
+        };
+        notify_new_insights(&app, &conn, &state.notified_insights, mov_id);
+    });
+}
+
+// ---------------------------------------------------------------------------
+// Commands
+// ---------------------------------------------------------------------------
+
+/// Anomalías en los gastos de los últimos `days` días (30 por defecto):
+/// montos fuera de lo común, comercios nuevos, cobros duplicados, aumentos
+/// de cargos recurrentes y categorías con gasto del mes muy alto.
+#[tauri::command]
+pub fn get_insights(
+    state: tauri::State<crate::AppState>,
+    days: Option<i64>,
+) -> Result<Vec<Insight>, OrbitError> {
+    let days = days.unwrap_or(DEFAULT_WINDOW_DAYS);
+    if !(1..=365).contains(&days) {
+        return Err(OrbitError::ValidationError(
+            "El período analizado debe ser de entre 1 y 365 días".into(),
+        ));
+    }
+
+    let conn = state.conn.lock().unwrap();
+    detect(&conn, chrono::Local::now().date_naive(), days)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expense(id: i64, details: &str, date: &str, amount: i64) -> Expense {
+        Expense {
+            id,
+            details: details.into(),
+            merchant: normalize_text(details),
+            date: NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap(),
+            ars_amount: amount,
+            original_amount: amount,
+            currency: "ARS".into(),
+            account_id: 1,
+            category_id: Some(1),
+            category: "Servicios".into(),
+        }
+    }
+
+    #[test]
+    fn test_double_charge_and_price_increase() {
+        let movements = vec![
+            expense(1, "Netflix", "2024-01-10", 1000),
+            expense(2, "Netflix", "2024-02-10", 1000),
+            expense(3, "Netflix", "2024-03-10", 1000),
+            expense(4, "Netflix", "2024-04-10", 1300),
+            expense(5, "Luz", "2024-04-11", 5000),
+            expense(6, "LUZ ", "2024-04-12", 5000),
+        ];
+        let since = NaiveDate::from_ymd_opt(2024, 4, 1).unwrap();
+
+        let doubles = double_charges(&movements, since);
+        assert_eq!(doubles.len(), 1);
+        assert_eq!(doubles[0].movement_ids, [5, 6]);
+
+        let increases = price_increases(&movements, since);
+        assert_eq!(increases.len(), 1);
+        assert_eq!(
+            (increases[0].amount, increases[0].expected),
+            (1300, Some(1000))
+        );
+    }
+}
@@ -0,0 +1,307 @@
+use std::path::{Path, PathBuf};
+
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tauri_plugin_opener::OpenerExt;
+
+use crate::{errors::OrbitError, history};
+
+/// A file (receipt, invoice) attached to a movement or a purchase.
+///
+/// The same file can be attached to several records: each link is a separate
+/// `Attachment`, but they all share the stored file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Attachment {
+    /// ID of the link; used to open or remove this attachment
+    pub id: i64,
+    pub attachment_id: i64,
+    /// Exactly one of `mov_id` / `purchase_id` is set
+    pub mov_id: Option<i64>,
+    pub purchase_id: Option<i64>,
+    /// SHA-256 of the content, in hex
+    pub hash: String,
+    /// Original file name, for display
+    pub file_name: String,
+    pub mime_type: String,
+    /// Size in bytes
+    pub size: i64,
+    pub created_at: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AddAttachment {
+    /// Absolute path of the file to attach (as returned by the file picker)
+    pub source_path: String,
+    pub mov_id: Option<i64>,
+    pub purchase_id: Option<i64>,
+}
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Ruta del archivo dentro del directorio de adjuntos: `<hash[0..2]>/<hash>`.
+/// El primer nivel evita tener miles de archivos en una sola carpeta.
+fn stored_path(dir: &Path, hash: &str) -> PathBuf {
+    dir.join(&hash[..2]).join(hash)
+}
+
+fn hash_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+/// Tipo MIME según la extensión; los comprobantes suelen ser fotos o PDF.
+fn mime_type_for(file_name: &str) -> &'static str {
+    let extension = Path::new(file_name)
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase());
+
+    match extension.as_deref() {
+        Some("pdf") => "application/pdf",
+        Some("jpg") | Some("jpeg") => "image/jpeg",
+        Some("png") => "image/png",
+        Some("webp") => "image/webp",
+        Some("heic") => "image/heic",
+        Some("txt") => "text/plain",
+        Some("xml") => "application/xml",
+        _ => "application/octet-stream",
+    }
+}
+
+fn row_to_attachment(row: &rusqlite::Row) -> rusqlite::Result<Attachment> {
+    Ok(Attachment {
+        id: row.get(0)?,
+        attachment_id: row.get(1)?,
+        mov_id: row.get(2)?,
+        purchase_id: row.get(3)?,
+        hash: row.get(4)?,
+        file_name: row.get(5)?,
+        mime_type: row.get(6)?,
+        size: row.get(7)?,
+        created_at: row.get(8)?,
+    })
+}
+
+const ATTACHMENT_SELECT: &str = "SELECT l.id, a.id, l.mov_id, l.purchase_id, a.hash, \
+     a.file_name, a.mime_type, a.size, l.created_at \
+     FROM attachment_links l \
+     INNER JOIN attachments a ON a.id = l.attachment_id";
+
+fn fetch_attachment(conn: &rusqlite::Connection, link_id: i64) -> Result<Attachment, OrbitError> {
+    match conn.query_row(
+        &format!("{ATTACHMENT_SELECT} WHERE l.id = ?1"),
+        params![link_id],
+        row_to_attachment,
+    ) {
+        Ok(attachment) => Ok(attachment),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Err(OrbitError::NotFound(format!(
+            "No se encontró el adjunto con ID {}",
+            link_id
+        ))),
+        Err(e) => Err(OrbitError::Database(e)),
+    }
+}
+
+/// Elimina los archivos que quedaron sin ningún vínculo (por ejemplo, al
+/// borrar el movimiento o la compra a la que estaban adjuntos).
+///
+/// Los vínculos están en el historial, pero `attachments` no: mientras alguna
+/// operación del historial pueda restaurar un vínculo al archivo, se conserva.
+/// Recién cuando esa operación se descarta (ver history::MAX_HISTORY) el
+/// archivo pasa a ser huérfano y se borra en la siguiente limpieza.
+///
+/// Llamar después del commit: si la transacción se revierte, los archivos
+/// todavía tienen que estar. Devuelve la cantidad de archivos eliminados.
+pub(crate) fn remove_orphans(conn: &rusqlite::Connection, dir: &Path) -> Result<usize, OrbitError> {
+    let mut stmt = conn.prepare(
+        "SELECT id, hash FROM attachments a
+         WHERE NOT EXISTS (SELECT 1 FROM attachment_links l WHERE l.attachment_id = a.id)
+           AND NOT EXISTS (
+               SELECT 1 FROM journal_changes c
+               WHERE c.table_name = 'attachment_links'
+                 AND a.id IN (json_extract(c.old_row, '$.attachment_id'),
+                              json_extract(c.new_row, '$.attachment_id'))
+           )",
+    )?;
+
+    let orphans = stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+        })?
+        .collect::<Result<Vec<(i64, String)>, rusqlite::Error>>()?;
+
+    for (id, hash) in &orphans {
+        conn.execute("DELETE FROM attachments WHERE id = ?1", params![id])?;
+
+        match std::fs::remove_file(stored_path(dir, hash)) {
+            Ok(()) => {}
+            // Ya no estaba en disco: nada que limpiar
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(OrbitError::Io(e)),
+        }
+    }
+
+    Ok(orphans.len())
+}
+
+// ---------------------------------------------------------------------------
+// Commands
+// ---------------------------------------------------------------------------
+
+/// Copia el archivo al directorio de adjuntos y lo vincula al movimiento o a la
+/// compra. Si ese mismo contenido ya estaba guardado, se reutiliza.
+#[tauri::command]
+pub fn add_attachment(
+    state: tauri::State<crate::AppState>,
+    attachment: AddAttachment,
+) -> Result<Attachment, OrbitError> {
+    let (table, target_id) = match (attachment.mov_id, attachment.purchase_id) {
+        (Some(mov_id), None) => ("movements", mov_id),
+        (None, Some(purchase_id)) => ("purchases", purchase_id),
+        _ => {
+            return Err(OrbitError::ValidationError(
+                "El adjunto debe vincularse a un movimiento o a una compra (solo uno)".into(),
+            ))
+        }
+    };
+
+    let source = PathBuf::from(&attachment.source_path);
+    let file_name = source
+        .file_name()
+        .and_then(|n| n.to_str())
+        .map(str::to_string)
+        .ok_or_else(|| {
+            OrbitError::ValidationError(format!(
+                "Ruta de archivo inválida: {:?}",
+                attachment.source_path
+            ))
+        })?;
+
+    let bytes = std::fs::read(&source)?;
+    let hash = hash_hex(&bytes);
+
+    let conn = state.conn.lock().unwrap();
+
+    let exists: bool = conn.query_row(
+        &format!("SELECT EXISTS (SELECT 1 FROM {table} WHERE id = ?1)"),
+        params![target_id],
+        |row| row.get(0),
+    )?;
+    if !exists {
+        return Err(OrbitError::NotFound(format!(
+            "No se encontró el registro con ID {}",
+            target_id
+        )));
+    }
+
+    let op = history::begin(&conn, "Adjuntar archivo")?;
+
+    let path = stored_path(&state.attachments_dir, &hash);
+    if !path.exists() {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&path, &bytes)?;
+    }
+
+    conn.execute(
+        "INSERT OR IGNORE INTO attachments (hash, file_name, mime_type, size) VALUES (?1, ?2, ?3, ?4)",
+        params![hash, file_name, mime_type_for(&file_name), bytes.len() as i64],
+    )?;
+    let attachment_id: i64 = conn.query_row(
+        "SELECT id FROM attachments WHERE hash = ?1",
+        params![hash],
+        |row| row.get(0),
+    )?;
+
+    conn.execute(
+        "INSERT OR IGNORE INTO attachment_links (attachment_id, mov_id, purchase_id) VALUES (?1, ?2, ?3)",
+        params![attachment_id, attachment.mov_id, attachment.purchase_id],
+    )?;
+
+    // Si ya estaba vinculado, devolvemos el vínculo existente
+    let link_id: i64 = conn.query_row(
+        "SELECT id FROM attachment_links
+         WHERE attachment_id = ?1 AND mov_id IS ?2 AND purchase_id IS ?3",
+        params![attachment_id, attachment.mov_id, attachment.purchase_id],
+        |row| row.get(0),
+    )?;
+
+    op.finish();
+
+    fetch_attachment(&conn, link_id)
+}
+
+/// Lista los adjuntos de un movimiento (incluidos los de sus compras) o de una
+/// compra puntual.
+#[tauri::command]
+pub fn get_attachments(
+    state: tauri::State<crate::AppState>,
+    mov_id: Option<i64>,
+    purchase_id: Option<i64>,
+) -> Result<Vec<Attachment>, OrbitError> {
+    let conn = state.conn.lock().unwrap();
+
+    let mut stmt = conn.prepare(&format!(
+        "{ATTACHMENT_SELECT}
+         WHERE (?1 IS NOT NULL AND (l.mov_id = ?1
+                OR l.purchase_id IN (SELECT id FROM purchases WHERE mov_id = ?1)))
+            OR (?2 IS NOT NULL AND l.purchase_id = ?2)
+         ORDER BY l.created_at ASC, l.id ASC"
+    ))?;
+
+    let attachments = stmt
+        .query_map(params![mov_id, purchase_id], row_to_attachment)?
+        .collect::<Result<Vec<Attachment>, rusqlite::Error>>()?;
+
+    Ok(attachments)
+}
+
+/// Abre el adjunto con la aplicación predeterminada del sistema.
+#[tauri::command]
+pub fn open_attachment(
+    app: tauri::AppHandle,
+    state: tauri::State<crate::AppState>,
+    id: i64,
+) -> Result<(), OrbitError> {
+    let conn = state.conn.lock().unwrap();
+    let attachment = fetch_attachment(&conn, id)?;
+
+    let path = stored_path(&state.attachments_dir, &attachment.hash);
+    if !path.exists() {
+        return Err(OrbitError::NotFound(format!(
+            "El archivo de {:?} no está en el directorio de adjuntos",
+            attachment.file_name
+        )));
+    }
+
+    app.opener()
+        .open_path(path.to_string_lossy(), None::<&str>)
+        .map_err(|e| OrbitError::Io(std::io::Error::other(e.to_string())))
+}
+
+/// Desvincula el adjunto. El archivo se conserva mientras se pueda deshacer.
+#[tauri::command]
+pub fn remove_attachment(state: tauri::State<crate::AppState>, id: i64) -> Result<(), OrbitError> {
+    let conn = state.conn.lock().unwrap();
+    let op = history::begin(&conn, "Quitar adjunto")?;
+
+    let rows_affected = conn.execute("DELETE FROM attachment_links WHERE id = ?1", params![id])?;
+    if rows_affected == 0 {
+        return Err(OrbitError::NotFound(format!(
+            "No se encontró el adjunto con ID {}",
+            id
+        )));
+    }
+
+    op.finish();
+
+    remove_orphans(&conn, &state.attachments_dir)?;
+
+    Ok(())
+}
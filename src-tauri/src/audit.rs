@@ -0,0 +1,236 @@
+use rusqlite::{params_from_iter, types::Value, Connection};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    errors::OrbitError,
+    utils::{format_date, json_row_expr, parse_date, table_columns},
+};
+
+/// Financial record kinds tracked by the audit log.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditEntity {
+    Account,
+    BalanceSnapshot,
+    Category,
+    Movement,
+    MovementSplit,
+    Purchase,
+}
+
+impl AuditEntity {
+    const ALL: [AuditEntity; 6] = [
+        AuditEntity::Account,
+        AuditEntity::BalanceSnapshot,
+        AuditEntity::Category,
+        AuditEntity::Movement,
+        AuditEntity::MovementSplit,
+        AuditEntity::Purchase,
+    ];
+
+    /// Tabla auditada (whitelist) y valor guardado en `audit_log.entity`.
+    fn table(self) -> &'static str {
+        match self {
+            AuditEntity::Account => "accounts",
+            AuditEntity::BalanceSnapshot => "balance_snapshots",
+            AuditEntity::Category => "categories",
+            AuditEntity::Movement => "movements",
+            AuditEntity::MovementSplit => "movement_splits",
+            AuditEntity::Purchase => "purchases",
+        }
+    }
+
+    fn from_table(table: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|e| e.table() == table)
+    }
+}
+
+/// A single recorded change, with the row state before and after it.
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditEntry {
+    pub id: i64,
+    pub entity: AuditEntity,
+    pub entity_id: i64,
+    /// "insert", "update" or "delete"
+    pub action: String,
+    /// Row before the change (None for inserts)
+    pub before: Option<serde_json::Value>,
+    /// Row after the change (None for deletes)
+    pub after: Option<serde_json::Value>,
+    pub changed_at: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditFeedFilters {
+    /// Fechas YYYY-MM-DD, ambas inclusive
+    #[serde(default)]
+    pub from: Option<String>,
+    #[serde(default)]
+    pub to: Option<String>,
+    #[serde(default)]
+    pub entity: Option<AuditEntity>,
+    pub limit: i64,
+    #[serde(default)]
+    pub offset: i64,
+}
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Crea los triggers que vuelcan cada INSERT/UPDATE/DELETE de las tablas
+/// auditadas en `audit_log`. Las columnas salen de `PRAGMA table_info`.
+pub fn install_triggers(conn: &Connection) -> Result<(), OrbitError> {
+    for entity in AuditEntity::ALL {
+        let table = entity.table();
+        let columns = table_columns(conn, table)?;
+        let old_row = json_row_expr(&columns, "OLD");
+        let new_row = json_row_expr(&columns, "NEW");
+
+        conn.execute_batch(&format!(
+            "CREATE TRIGGER IF NOT EXISTS trg_audit_{table}_insert AFTER INSERT ON {table} BEGIN
+                 INSERT INTO audit_log (entity, entity_id, action, before_json, after_json)
+                 VALUES ('{table}', NEW.id, 'insert', NULL, {new_row});
+             END;
+
+             -- Solo si algo cambió realmente
+             CREATE TRIGGER IF NOT EXISTS trg_audit_{table}_update AFTER UPDATE ON {table}
+             WHEN {old_row} IS NOT {new_row}
+             BEGIN
+                 INSERT INTO audit_log (entity, entity_id, action, before_json, after_json)
+                 VALUES ('{table}', NEW.id, 'update', {old_row}, {new_row});
+             END;
+
+             CREATE TRIGGER IF NOT EXISTS trg_audit_{table}_delete AFTER DELETE ON {table} BEGIN
+                 INSERT INTO audit_log (entity, entity_id, action, before_json, after_json)
+                 VALUES ('{table}', OLD.id, 'delete', {old_row}, NULL);
+             END;"
+        ))?;
+    }
+
+    Ok(())
+}
+
+fn row_to_entry(row: &rusqlite::Row) -> rusqlite::Result<AuditEntry> {
+    let entity: String = row.get(1)?;
+    let before: Option<String> = row.get(4)?;
+    let after: Option<String> = row.get(5)?;
+
+    Ok(AuditEntry {
+        id: row.get(0)?,
+        // Los triggers solo escriben tablas de AuditEntity::ALL
+        entity: AuditEntity::from_table(&entity).unwrap_or(AuditEntity::Movement),
+        entity_id: row.get(2)?,
+        action: row.get(3)?,
+        before: before.and_then(|json| serde_json::from_str(&json).ok()),
+        after: after.and_then(|json| serde_json::from_str(&json).ok()),
+        changed_at: row.get(6)?,
+    })
+}
+
+fn query_entries(
+    conn: &Connection,
+    sql: &str,
+    params: Vec<Value>,
+) -> Result<Vec<AuditEntry>, OrbitError> {
+    let mut stmt = conn.prepare(sql)?;
+    let entries = stmt
+        .query_map(params_from_iter(params), row_to_entry)?
+        .collect::<Result<Vec<AuditEntry>, rusqlite::Error>>()?;
+
+    Ok(entries)
+}
+
+// ---------------------------------------------------------------------------
+// Commands
+// ---------------------------------------------------------------------------
+
+/// Historial completo de un registro, del cambio más reciente al más viejo.
+///
+/// Para un movimiento incluye también los cambios de sus compras y líneas de
+/// desglose; para una cuenta, los de sus snapshots de saldo.
+#[tauri::command]
+pub fn get_audit_history(
+    state: tauri::State<crate::AppState>,
+    entity: AuditEntity,
+    entity_id: i64,
+) -> Result<Vec<AuditEntry>, OrbitError> {
+    let conn = state.conn.lock().unwrap();
+
+    // (tabla hija, columna FK hacia el registro pedido)
+    let children: &[(&str, &str)] = match entity {
+        AuditEntity::Movement => &[("purchases", "mov_id"), ("movement_splits", "mov_id")],
+        AuditEntity::Account => &[("balance_snapshots", "account_id")],
+        _ => &[],
+    };
+
+    let mut sql = String::from(
+        "SELECT id, entity, entity_id, action, before_json, after_json, changed_at
+         FROM audit_log
+         WHERE (entity = ? AND entity_id = ?)",
+    );
+    let mut params = vec![
+        Value::Text(entity.table().to_string()),
+        Value::Integer(entity_id),
+    ];
+
+    for (table, fk) in children {
+        sql.push_str(&format!(
+            " OR (entity = ? AND (json_extract(before_json, '$.{fk}') = ? \
+              OR json_extract(after_json, '$.{fk}') = ?))"
+        ));
+        params.push(Value::Text(table.to_string()));
+        params.push(Value::Integer(entity_id));
+        params.push(Value::Integer(entity_id));
+    }
+
+    sql.push_str(" ORDER BY id DESC");
+
+    query_entries(&conn, &sql, params)
+}
+
+/// Feed global de cambios, del más reciente al más viejo, filtrable por
+/// rango de fechas y tipo de registro.
+#[tauri::command]
+pub fn get_audit_feed(
+    state: tauri::State<crate::AppState>,
+    filters: AuditFeedFilters,
+) -> Result<Vec<AuditEntry>, OrbitError> {
+    let conn = state.conn.lock().unwrap();
+
+    let mut conditions: Vec<&str> = Vec::new();
+    let mut params: Vec<Value> = Vec::new();
+
+    if let Some(from) = filters.from.as_deref().filter(|f| !f.trim().is_empty()) {
+        conditions.push("changed_at >= ?");
+        params.push(Value::Text(format_date(parse_date(from)?)));
+    }
+
+    // changed_at tiene hora: "hasta" incluye todo ese día
+    if let Some(to) = filters.to.as_deref().filter(|t| !t.trim().is_empty()) {
+        conditions.push("changed_at < date(?, '+1 day')");
+        params.push(Value::Text(format_date(parse_date(to)?)));
+    }
+
+    if let Some(entity) = filters.entity {
+        conditions.push("entity = ?");
+        params.push(Value::Text(entity.table().to_string()));
+    }
+
+    let mut sql = String::from(
+        "SELECT id, entity, entity_id, action, before_json, after_json, changed_at
+         FROM audit_log",
+    );
+
+    if !conditions.is_empty() {
+        sql.push_str(" WHERE ");
+        sql.push_str(&conditions.join(" AND "));
+    }
+
+    sql.push_str(" ORDER BY id DESC LIMIT ? OFFSET ?");
+    params.push(Value::Integer(filters.limit));
+    params.push(Value::Integer(filters.offset));
+
+    query_entries(&conn, &sql, params)
+}
@@ -0,0 +1,246 @@
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    attachments,
+    errors::OrbitError,
+    history,
+    tags::{link_tags, normalize_tag_names, resolve_tag_ids, unlink_tags},
+    utils::parse_date,
+};
+
+/// Operation applied to every movement of a bulk request.
+///
+/// The frontend sends `{ "action": "recategorize", "category_id": 3 }`,
+/// `{ "action": "delete" }`, etc.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum BulkAction {
+    /// `None` leaves the movements uncategorized
+    Recategorize {
+        category_id: Option<i64>,
+    },
+    AddTags {
+        tags: Vec<String>,
+    },
+    RemoveTags {
+        tags: Vec<String>,
+    },
+    AddToGroup {
+        group_id: i64,
+    },
+    RemoveFromGroup {
+        group_id: i64,
+    },
+    MoveToAccount {
+        account_id: i64,
+    },
+    /// New date in ISO 8601 format (YYYY-MM-DD)
+    SetDate {
+        date: String,
+    },
+    Delete,
+}
+
+/// Outcome of the bulk action for a single movement.
+#[derive(Debug, Clone, Serialize)]
+pub struct BulkItemResult {
+    pub id: i64,
+    pub ok: bool,
+    /// Reason why the movement was skipped, if `ok` is false
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BulkResult {
+    pub succeeded: i64,
+    pub failed: i64,
+    pub results: Vec<BulkItemResult>,
+}
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Verifica que exista una fila con ese id en la tabla indicada.
+/// `table` es siempre un literal del código, nunca texto del usuario.
+fn ensure_exists(
+    conn: &rusqlite::Connection,
+    table: &str,
+    id: i64,
+    label: &str,
+) -> Result<(), OrbitError> {
+    let exists: bool = conn.query_row(
+        &format!("SELECT EXISTS (SELECT 1 FROM {table} WHERE id = ?1)"),
+        params![id],
+        |row| row.get(0),
+    )?;
+
+    if !exists {
+        return Err(OrbitError::NotFound(format!(
+            "No se encontró {label} con ID {id}"
+        )));
+    }
+
+    Ok(())
+}
+
+/// Validaciones que no dependen de cada movimiento: si fallan, no se toca nada.
+fn validate_action(conn: &rusqlite::Connection, action: &BulkAction) -> Result<(), OrbitError> {
+    match action {
+        BulkAction::Recategorize {
+            category_id: Some(category_id),
+        } => ensure_exists(conn, "categories", *category_id, "la categoría"),
+        BulkAction::AddToGroup { group_id } | BulkAction::RemoveFromGroup { group_id } => {
+            ensure_exists(conn, "groups", *group_id, "el grupo")
+        }
+        BulkAction::MoveToAccount { account_id } => {
+            ensure_exists(conn, "accounts", *account_id, "la cuenta")
+        }
+        BulkAction::SetDate { date } => parse_date(date).map(|_| ()),
+        BulkAction::AddTags { tags } if normalize_tag_names(tags).is_empty() => Err(
+            OrbitError::ValidationError("Debe indicar al menos una etiqueta".into()),
+        ),
+        _ => Ok(()),
+    }
+}
+
+/// Aplica la acción a un único movimiento.
+fn apply_to_movement(
+    conn: &rusqlite::Connection,
+    mov_id: i64,
+    action: &BulkAction,
+    tag_ids: &[i64],
+) -> Result<(), OrbitError> {
+    ensure_exists(conn, "movements", mov_id, "el movimiento")?;
+
+    match action {
+        BulkAction::Recategorize { category_id } => {
+            let has_splits: bool = conn.query_row(
+                "SELECT EXISTS (SELECT 1 FROM movement_splits WHERE mov_id = ?1)",
+                params![mov_id],
+                |row| row.get(0),
+            )?;
+            // Un movimiento dividido se categoriza por sus líneas
+            if has_splits {
+                return Err(OrbitError::ValidationError(
+                    "El movimiento está dividido en líneas; edite su desglose".into(),
+                ));
+            }
+            conn.execute(
+                "UPDATE movements SET category_id = ?1 WHERE id = ?2",
+                params![category_id, mov_id],
+            )?;
+        }
+        BulkAction::AddTags { .. } => {
+            link_tags(conn, &[mov_id], tag_ids)?;
+        }
+        BulkAction::RemoveTags { .. } => {
+            unlink_tags(conn, &[mov_id], tag_ids)?;
+        }
+        BulkAction::AddToGroup { group_id } => {
+            conn.execute(
+                "INSERT OR IGNORE INTO movements_groups (mov_id, group_id) VALUES (?1, ?2)",
+                params![mov_id, group_id],
+            )?;
+        }
+        BulkAction::RemoveFromGroup { group_id } => {
+            conn.execute(
+                "DELETE FROM movements_groups WHERE mov_id = ?1 AND group_id = ?2",
+                params![mov_id, group_id],
+            )?;
+        }
+        BulkAction::MoveToAccount { account_id } => {
+            conn.execute(
+                "UPDATE movements SET account_id = ?1 WHERE id = ?2",
+                params![account_id, mov_id],
+            )?;
+        }
+        BulkAction::SetDate { date } => {
+            conn.execute(
+                "UPDATE movements SET date = ?1 WHERE id = ?2",
+                params![date, mov_id],
+            )?;
+        }
+        BulkAction::Delete => {
+            // purchases, splits, grupos y etiquetas se limpian por ON DELETE CASCADE
+            conn.execute("DELETE FROM movements WHERE id = ?1", params![mov_id])?;
+        }
+    }
+
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Commands
+// ---------------------------------------------------------------------------
+
+/// Aplica una misma acción a varios movimientos en una sola transacción.
+///
+/// Cada movimiento corre en su propio savepoint: si uno falla (no existe, está
+/// dividido, etc.) se revierte solo ese y el resto se confirma. El resultado
+/// informa qué pasó con cada ID.
+#[tauri::command]
+pub fn bulk_update_movements(
+    state: tauri::State<crate::AppState>,
+    movement_ids: Vec<i64>,
+    action: BulkAction,
+) -> Result<BulkResult, OrbitError> {
+    let mut conn = state.conn.lock().unwrap();
+
+    validate_action(&conn, &action)?;
+
+    let tx = conn.transaction()?;
+    let op = history::begin(&tx, "Edición masiva de movimientos")?;
+
+    // Las etiquetas se resuelven una sola vez para todos los movimientos
+    let tag_ids: Vec<i64> = match &action {
+        BulkAction::AddTags { tags } => resolve_tag_ids(&tx, &normalize_tag_names(tags), true)?,
+        BulkAction::RemoveTags { tags } => resolve_tag_ids(&tx, &normalize_tag_names(tags), false)?,
+        _ => Vec::new(),
+    };
+
+    let mut results: Vec<BulkItemResult> = Vec::with_capacity(movement_ids.len());
+
+    for mov_id in movement_ids {
+        // SAVEPOINT a mano (no `tx.savepoint()`): la operación del historial
+        // ya tiene tomada la transacción prestada.
+        tx.execute_batch("SAVEPOINT bulk_item")?;
+
+        match apply_to_movement(&tx, mov_id, &action, &tag_ids) {
+            Ok(()) => {
+                tx.execute_batch("RELEASE bulk_item")?;
+                results.push(BulkItemResult {
+                    id: mov_id,
+                    ok: true,
+                    error: None,
+                });
+            }
+            Err(e) => {
+                // Se revierte solo este movimiento
+                tx.execute_batch("ROLLBACK TO bulk_item; RELEASE bulk_item")?;
+                results.push(BulkItemResult {
+                    id: mov_id,
+                    ok: false,
+                    error: Some(e.to_string()),
+                });
+            }
+        }
+    }
+
+    op.finish();
+    tx.commit()?;
+
+    if matches!(action, BulkAction::Delete) {
+        attachments::remove_orphans(&conn, &state.attachments_dir)?;
+    }
+
+    let succeeded = results.iter().filter(|r| r.ok).count() as i64;
+    let failed = results.len() as i64 - succeeded;
+
+    Ok(BulkResult {
+        succeeded,
+        failed,
+        results,
+    })
+}
@@ -1,7 +1,7 @@
 use rusqlite::params;
 use serde::{Deserialize, Serialize};
 
-use crate::errors::OrbitError;
+use crate::{errors::OrbitError, history};
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Category {
@@ -18,9 +18,9 @@ pub fn get_categories(state: tauri::State<crate::AppState>) -> Vec<Category> {
     let mut stmt = conn
         .prepare(
             "
-            SELECT c.id, c.name, c.created_at, COUNT(m.id) as movement_count
+            SELECT c.id, c.name, c.created_at, COUNT(DISTINCT l.id) as movement_count
             FROM categories c
-            LEFT JOIN movements m ON c.id = m.category_id
+            LEFT JOIN v_movement_lines l ON c.id = l.category_id
             GROUP BY c.id",
         )
         .unwrap();
@@ -42,12 +42,15 @@ pub fn get_categories(state: tauri::State<crate::AppState>) -> Vec<Category> {
 #[tauri::command]
 pub fn add_category(state: tauri::State<crate::AppState>, name: String) -> Result<i64, OrbitError> {
     let conn = state.conn.lock().unwrap();
+    let op = history::begin(&conn, "Agregar categoría")?;
 
     conn.execute("INSERT INTO categories (name) VALUES (?1)", params![name])?;
 
     // Recuperamos el ID autoincremental generado por SQLite para esa fila
     let id = conn.last_insert_rowid();
 
+    op.finish();
+
     // Devolvemos el ID en el Ok
     Ok(id)
 }
@@ -55,6 +58,7 @@ pub fn add_category(state: tauri::State<crate::AppState>, name: String) -> Resul
 #[tauri::command]
 pub fn delete_category(state: tauri::State<crate::AppState>, id: i64) -> Result<(), OrbitError> {
     let conn = state.conn.lock().unwrap();
+    let op = history::begin(&conn, "Eliminar categoría")?;
 
     let rows_affected = conn.execute("DELETE FROM categories WHERE id = ?1", params![id])?;
 
@@ -62,6 +66,8 @@ pub fn delete_category(state: tauri::State<crate::AppState>, id: i64) -> Result<
         return Err(OrbitError::NotFound("Categoría no encontrada".into()));
     }
 
+    op.finish();
+
     Ok(())
 }
 
@@ -72,6 +78,7 @@ pub fn update_category(
     name: String,
 ) -> Result<(), OrbitError> {
     let conn = state.conn.lock().unwrap();
+    let op = history::begin(&conn, "Editar categoría")?;
 
     let rows_affected = conn.execute(
         "UPDATE categories SET name = ? WHERE id = ?",
@@ -82,5 +89,7 @@ pub fn update_category(
         return Err(OrbitError::NotFound("Categoría no encontrada".into()));
     }
 
+    op.finish();
+
     Ok(())
 }
@@ -0,0 +1,305 @@
+use std::collections::BTreeMap;
+
+use rusqlite::params;
+use serde::Serialize;
+
+use crate::{
+    errors::OrbitError,
+    history,
+    utils::{parse_date, parse_month},
+};
+
+/// One month of the CPI (IPC) series.
+#[derive(Debug, Clone, Serialize)]
+pub struct CpiPoint {
+    /// YYYY-MM
+    pub month: String,
+    pub value: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CpiImport {
+    pub inserted: usize,
+    pub updated: usize,
+}
+
+/// CPI series loaded in memory, used to express amounts in constant pesos.
+pub(crate) struct CpiSeries(BTreeMap<String, f64>);
+
+impl CpiSeries {
+    pub(crate) fn load(conn: &rusqlite::Connection) -> Result<Self, OrbitError> {
+        let mut stmt = conn.prepare("SELECT month, value FROM cpi_index")?;
+        let series = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<BTreeMap<String, f64>, rusqlite::Error>>()?;
+
+        Ok(CpiSeries(series))
+    }
+
+    /// Índice vigente en `month`. Si el mes todavía no se publicó se usa el
+    /// último disponible; antes del primer mes cargado no hay índice.
+    fn index_at(&self, month: &str) -> Option<f64> {
+        self.0
+            .range(..=month.to_string())
+            .next_back()
+            .map(|(_, value)| *value)
+    }
+
+    /// Factor por el que se multiplica un monto de cada mes de `months` para
+    /// llevarlo a pesos constantes de `reference`.
+    pub(crate) fn factors(
+        &self,
+        months: &[String],
+        reference: &str,
+    ) -> Result<Vec<f64>, OrbitError> {
+        let missing = |month: &str| {
+            OrbitError::ValidationError(format!(
+                "No hay IPC cargado para {month}: importá la serie desde ese mes"
+            ))
+        };
+
+        let base = self.index_at(reference).ok_or_else(|| missing(reference))?;
+        months
+            .iter()
+            .map(|month| {
+                self.index_at(month)
+                    .map(|index| base / index)
+                    .ok_or_else(|| missing(month))
+            })
+            .collect()
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Acepta YYYY-MM, YYYY-MM-DD (el formato del INDEC) y MM/YYYY.
+fn parse_csv_month(value: &str) -> Option<String> {
+    let value = value.trim().trim_matches('"');
+    if let Some((month, year)) = value.split_once('/') {
+        return parse_month(&format!("{year}-{month:0>2}")).ok();
+    }
+    if value.len() == 10 {
+        parse_date(value).ok()?;
+        return parse_month(&value[..7]).ok();
+    }
+    parse_month(value).ok()
+}
+
+/// Acepta punto o coma decimal; con coma, los puntos son de miles.
+fn parse_csv_value(value: &str) -> Option<f64> {
+    let value = value.trim().trim_matches('"');
+    let value = if value.contains(',') {
+        value.replace('.', "").replace(',', ".")
+    } else {
+        value.to_string()
+    };
+    value
+        .parse::<f64>()
+        .ok()
+        .filter(|v| v.is_finite() && *v > 0.0)
+}
+
+/// Interpreta un CSV de dos columnas (mes, índice) separado por coma, punto y
+/// coma o tabulación. Si la primera línea no empieza con un mes, se toma como
+/// encabezado. Cualquier otra línea inválida aborta la importación.
+fn parse_cpi_csv(text: &str) -> Result<Vec<CpiPoint>, OrbitError> {
+    let mut points = Vec::new();
+
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim().trim_start_matches('\u{feff}');
+        if line.is_empty() {
+            continue;
+        }
+
+        let fields = [';', '\t', ',']
+            .iter()
+            .find_map(|sep| line.split_once(*sep));
+        let Some((month, value)) = fields else {
+            return Err(OrbitError::ValidationError(format!(
+                "Línea {}: se esperaban dos columnas (mes e índice)",
+                i + 1
+            )));
+        };
+
+        let Some(month) = parse_csv_month(month) else {
+            if i == 0 {
+                continue;
+            }
+            return Err(OrbitError::ValidationError(format!(
+                "Línea {}: mes inválido {:?}",
+                i + 1,
+                month.trim()
+            )));
+        };
+        let Some(value) = parse_csv_value(value) else {
+            return Err(OrbitError::ValidationError(format!(
+                "Línea {}: índice inválido {:?}",
+                i + 1,
+                value.trim()
+            )));
+        };
+
+        points.push(CpiPoint { month, value });
+    }
+
+    if points.is_empty() {
+        return Err(OrbitError::ValidationError(
+            "El archivo no tiene valores de IPC".into(),
+        ));
+    }
+
+    Ok(points)
+}
+
+/// Inserta o actualiza el índice de un mes. Devuelve `true` si era nuevo.
+fn upsert_point(conn: &rusqlite::Connection, point: &CpiPoint) -> Result<bool, OrbitError> {
+    let exists: bool = conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM cpi_index WHERE month = ?1)",
+        params![point.month],
+        |row| row.get(0),
+    )?;
+
+    conn.execute(
+        "INSERT INTO cpi_index (month, value) VALUES (?1, ?2)
+         ON CONFLICT (month) DO UPDATE SET value = excluded.value",
+        params![point.month, point.value],
+    )?;
+
+    Ok(!exists)
+}
+
+// ---------------------------------------------------------------------------
+// Commands
+// ---------------------------------------------------------------------------
+
+#[tauri::command]
+pub fn get_cpi(state: tauri::State<crate::AppState>) -> Result<Vec<CpiPoint>, OrbitError> {
+    let conn = state.conn.lock().unwrap();
+    let mut stmt = conn.prepare("SELECT month, value FROM cpi_index ORDER BY month ASC")?;
+    let points = stmt
+        .query_map([], |row| {
+            Ok(CpiPoint {
+                month: row.get(0)?,
+                value: row.get(1)?,
+            })
+        })?
+        .collect::<Result<Vec<CpiPoint>, rusqlite::Error>>()?;
+
+    Ok(points)
+}
+
+#[tauri::command]
+pub fn set_cpi_value(
+    state: tauri::State<crate::AppState>,
+    month: String,
+    value: f64,
+) -> Result<CpiPoint, OrbitError> {
+    let month = parse_month(&month)?;
+    if !value.is_finite() || value <= 0.0 {
+        return Err(OrbitError::ValidationError(
+            "El índice debe ser mayor a cero".into(),
+        ));
+    }
+
+    let conn = state.conn.lock().unwrap();
+    let op = history::begin(&conn, "Cargar IPC")?;
+
+    let point = CpiPoint { month, value };
+    upsert_point(&conn, &point)?;
+
+    op.finish();
+
+    Ok(point)
+}
+
+#[tauri::command]
+pub fn delete_cpi_value(
+    state: tauri::State<crate::AppState>,
+    month: String,
+) -> Result<(), OrbitError> {
+    let month = parse_month(&month)?;
+
+    let conn = state.conn.lock().unwrap();
+    let op = history::begin(&conn, "Eliminar IPC")?;
+
+    let deleted = conn.execute("DELETE FROM cpi_index WHERE month = ?1", params![month])?;
+    if deleted == 0 {
+        return Err(OrbitError::NotFound(format!(
+            "No se encontró el IPC de {month}"
+        )));
+    }
+
+    op.finish();
+
+    Ok(())
+}
+
+/// Importa la serie de IPC desde un CSV (mes, índice). Los meses que ya
+/// estaban cargados se reemplazan; todo se guarda en una sola operación.
+#[tauri::command]
+pub fn import_cpi_csv(
+    state: tauri::State<crate::AppState>,
+    path: String,
+) -> Result<CpiImport, OrbitError> {
+    let text = std::fs::read_to_string(&path)?;
+    let points = parse_cpi_csv(&text)?;
+
+    let mut conn = state.conn.lock().unwrap();
+    let tx = conn.transaction()?;
+    let op = history::begin(&tx, "Importar IPC")?;
+
+    let mut result = CpiImport {
+        inserted: 0,
+        updated: 0,
+    };
+    for point in &points {
+        if upsert_point(&tx, point)? {
+            result.inserted += 1;
+        } else {
+            result.updated += 1;
+        }
+    }
+
+    op.finish();
+    tx.commit()?;
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cpi_csv_formats() {
+        let text = "indice_tiempo;ipc\n\
+                    2024-01-01;4.261,25\n\
+                    2024-02;4.770,9\n\
+                    \n\
+                    03/2024;5.357,1\n";
+        let points = parse_cpi_csv(text).unwrap();
+
+        let months: Vec<&str> = points.iter().map(|p| p.month.as_str()).collect();
+        assert_eq!(months, ["2024-01", "2024-02", "2024-03"]);
+        assert_eq!(points[0].value, 4261.25);
+
+        let err = parse_cpi_csv("2024-01,100\n2024-13,110").unwrap_err();
+        assert!(err.to_string().contains("Línea 2"));
+    }
+
+    #[test]
+    fn test_factors_carry_last_index_forward() {
+        let series = CpiSeries(BTreeMap::from([
+            ("2024-01".to_string(), 100.0),
+            ("2024-02".to_string(), 125.0),
+        ]));
+
+        let months = ["2024-01", "2024-02", "2024-03"].map(String::from);
+        let factors = series.factors(&months, "2024-02").unwrap();
+        assert_eq!(factors, [1.25, 1.0, 1.0]);
+
+        assert!(series.factors(&["2023-12".to_string()], "2024-02").is_err());
+    }
+}
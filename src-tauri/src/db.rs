@@ -0,0 +1,95 @@
+use rusqlite::Connection;
+use rusqlite_migration::{Migrations, M};
+
+use crate::{audit, errors::OrbitError, history};
+
+/// Versión del esquema antes y después de migrar (`PRAGMA user_version`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SchemaVersion {
+    /// 0 si la base se acaba de crear
+    pub previous: i64,
+    pub current: i64,
+}
+
+impl SchemaVersion {
+    pub fn is_new(&self) -> bool {
+        self.previous == 0
+    }
+
+    pub fn changed(&self) -> bool {
+        self.previous != self.current
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Migraciones del esquema, en orden; cada una sube `user_version` en uno.
+///
+/// La 1 es el esquema inicial (schema.sql). Los cambios posteriores se
+/// agregan como migraciones nuevas al final: una base existente nunca vuelve
+/// a correr las que ya aplicó, así que una migración publicada no se edita.
+fn migrations() -> Migrations<'static> {
+    Migrations::new(vec![
+        M::up(include_str!("../schema.sql")),
+        M::up(include_str!("../migrations/002_stores_fts_aliases.sql")),
+    ])
+}
+
+fn user_version(conn: &Connection) -> Result<i64, OrbitError> {
+    Ok(conn.query_row("PRAGMA user_version", [], |row| row.get(0))?)
+}
+
+/// Lleva la base a la última versión del esquema.
+pub fn migrate(conn: &mut Connection) -> Result<SchemaVersion, OrbitError> {
+    let mut previous = user_version(conn)?;
+
+    // Bases creadas antes de versionar el esquema: ya tienen la versión 1
+    if previous == 0 {
+        let has_tables: bool = conn.query_row(
+            "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'accounts')",
+            [],
+            |row| row.get(0),
+        )?;
+        if has_tables {
+            conn.pragma_update(None, "user_version", 1)?;
+            previous = 1;
+        }
+    }
+
+    migrations().to_latest(conn)?;
+
+    Ok(SchemaVersion {
+        previous,
+        current: user_version(conn)?,
+    })
+}
+
+/// Crea los triggers generados de historial y auditoría.
+///
+/// Se arman a partir de las columnas de cada tabla: si el esquema cambió, se
+/// borran y se vuelven a crear para que registren las columnas nuevas. Los
+/// triggers fijos de schema.sql (FTS, protección de `audit_log`) no se tocan.
+pub fn install_triggers(conn: &Connection, version: SchemaVersion) -> Result<(), OrbitError> {
+    if version.changed() {
+        let mut stmt = conn.prepare(
+            "SELECT name FROM sqlite_master
+             WHERE type = 'trigger'
+               AND (name LIKE 'trg\\_journal\\_%' ESCAPE '\\'
+                    OR (name LIKE 'trg\\_audit\\_%' ESCAPE '\\' AND tbl_name <> 'audit_log'))",
+        )?;
+        let names = stmt
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<Result<Vec<String>, rusqlite::Error>>()?;
+
+        for name in names {
+            conn.execute_batch(&format!("DROP TRIGGER IF EXISTS \"{name}\""))?;
+        }
+    }
+
+    history::install_triggers(conn)?;
+    audit::install_triggers(conn)?;
+
+    Ok(())
+}
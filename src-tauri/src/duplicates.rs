@@ -0,0 +1,339 @@
+use std::collections::HashMap;
+
+use rusqlite::{params, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    errors::OrbitError,
+    history,
+    movements::{row_to_movement, Movement, MOVEMENT_SELECT},
+    utils::{parse_date, text_similarity},
+};
+
+/// Parameters of a duplicate scan. Every field has a sensible default, so the
+/// frontend can send `{}`.
+#[derive(Debug, Deserialize)]
+pub struct DuplicateScanOptions {
+    /// Restrict the scan to a single account
+    #[serde(default)]
+    pub account_id: Option<i64>,
+    /// Maximum difference between amounts, in cents of the original currency
+    #[serde(default)]
+    pub amount_tolerance: i64,
+    /// Maximum distance in days between the two dates
+    #[serde(default = "default_max_days_apart")]
+    pub max_days_apart: i64,
+    /// Minimum similarity (0.0 - 1.0) between the `details` of both movements
+    #[serde(default = "default_min_similarity")]
+    pub min_similarity: f64,
+}
+
+fn default_max_days_apart() -> i64 {
+    3
+}
+
+fn default_min_similarity() -> f64 {
+    0.6
+}
+
+/// A group of movements that look like the same real transaction.
+#[derive(Debug, Clone, Serialize)]
+pub struct DuplicateCluster {
+    /// Average score (0.0 - 1.0) of the matching pairs in the cluster
+    pub confidence: f64,
+    /// Movement suggested as the one to keep: the one with most purchases,
+    /// then the oldest
+    pub suggested_keep_id: i64,
+    pub movements: Vec<Movement>,
+}
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Datos mínimos de un movimiento para comparar pares.
+struct Candidate {
+    id: i64,
+    details: String,
+    date: chrono::NaiveDate,
+    amount: i64,
+}
+
+/// Puntaje de un par, o `None` si no cumple alguno de los umbrales.
+fn pair_score(a: &Candidate, b: &Candidate, options: &DuplicateScanOptions) -> Option<f64> {
+    let amount_diff = (a.amount - b.amount).abs();
+    if amount_diff > options.amount_tolerance {
+        return None;
+    }
+
+    let days = (a.date - b.date).num_days().abs();
+    if days > options.max_days_apart {
+        return None;
+    }
+
+    let similarity = text_similarity(&a.details, &b.details);
+    if similarity < options.min_similarity {
+        return None;
+    }
+
+    // El texto pesa más; monto y fecha suman cuanto más cerca estén
+    let amount_score = 1.0 - amount_diff as f64 / (options.amount_tolerance + 1) as f64;
+    let date_score = 1.0 - days as f64 / (options.max_days_apart + 1) as f64;
+
+    Some(0.6 * similarity + 0.2 * amount_score + 0.2 * date_score)
+}
+
+fn find_root(parent: &mut HashMap<i64, i64>, id: i64) -> i64 {
+    let mut root = id;
+    while let Some(&p) = parent.get(&root).filter(|&&p| p != root) {
+        root = p;
+    }
+    parent.insert(id, root);
+    root
+}
+
+// ---------------------------------------------------------------------------
+// Commands
+// ---------------------------------------------------------------------------
+
+/// Busca movimientos que probablemente sean duplicados: misma cuenta, moneda y
+/// tipo, monto dentro de la tolerancia, fechas cercanas y descripción parecida.
+///
+/// Los pares que coinciden se agrupan en clusters (si A~B y B~C, los tres van
+/// juntos), ordenados de mayor a menor confianza.
+#[tauri::command]
+pub fn find_duplicate_movements(
+    state: tauri::State<crate::AppState>,
+    options: DuplicateScanOptions,
+) -> Result<Vec<DuplicateCluster>, OrbitError> {
+    if options.amount_tolerance < 0 || options.max_days_apart < 0 {
+        return Err(OrbitError::ValidationError(
+            "La tolerancia de monto y de días no puede ser negativa".into(),
+        ));
+    }
+
+    let conn = state.conn.lock().unwrap();
+
+    let mut stmt = conn.prepare(
+        "SELECT id, details, date, original_amount, account_id, currency, mov_type
+         FROM movements
+         WHERE ?1 IS NULL OR account_id = ?1
+         ORDER BY account_id, currency, mov_type, date, id",
+    )?;
+
+    // Solo se comparan movimientos de la misma cuenta, moneda y tipo
+    let mut buckets: HashMap<(i64, String, String), Vec<Candidate>> = HashMap::new();
+    let rows = stmt.query_map(params![options.account_id], |row| {
+        Ok((
+            row.get::<_, i64>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, String>(2)?,
+            row.get::<_, i64>(3)?,
+            (
+                row.get::<_, i64>(4)?,
+                row.get::<_, String>(5)?,
+                row.get::<_, String>(6)?,
+            ),
+        ))
+    })?;
+
+    for row in rows {
+        let (id, details, date, amount, key) = row?;
+        // Fechas mal cargadas no pueden compararse; se ignoran
+        let Ok(date) = parse_date(&date) else {
+            continue;
+        };
+        buckets.entry(key).or_default().push(Candidate {
+            id,
+            details,
+            date,
+            amount,
+        });
+    }
+
+    let mut parent: HashMap<i64, i64> = HashMap::new();
+    let mut scores: Vec<(i64, f64)> = Vec::new();
+
+    for candidates in buckets.values() {
+        // Ordenados por fecha: alcanza con mirar hacia adelante dentro de la ventana
+        for (i, a) in candidates.iter().enumerate() {
+            for b in &candidates[i + 1..] {
+                if (b.date - a.date).num_days() > options.max_days_apart {
+                    break;
+                }
+                if let Some(score) = pair_score(a, b, &options) {
+                    let root_a = find_root(&mut parent, a.id);
+                    let root_b = find_root(&mut parent, b.id);
+                    parent.insert(root_a, root_b);
+                    scores.push((a.id, score));
+                }
+            }
+        }
+    }
+
+    // Agrupa ids y puntajes por cluster
+    let ids: Vec<i64> = parent.keys().copied().collect();
+    let mut members: HashMap<i64, Vec<i64>> = HashMap::new();
+    for id in ids {
+        let root = find_root(&mut parent, id);
+        members.entry(root).or_default().push(id);
+    }
+
+    let mut cluster_scores: HashMap<i64, Vec<f64>> = HashMap::new();
+    for (id, score) in scores {
+        let root = find_root(&mut parent, id);
+        cluster_scores.entry(root).or_default().push(score);
+    }
+
+    let sql = format!(
+        "{MOVEMENT_SELECT} WHERE id IN (SELECT value FROM json_each(?1)) ORDER BY date, id"
+    );
+    let mut movements_stmt = conn.prepare(&sql)?;
+    let mut purchases_stmt = conn.prepare("SELECT COUNT(*) FROM purchases WHERE mov_id = ?1")?;
+
+    let mut clusters: Vec<DuplicateCluster> = Vec::with_capacity(members.len());
+
+    for (root, ids) in members {
+        let id_list = format!(
+            "[{}]",
+            ids.iter()
+                .map(|id| id.to_string())
+                .collect::<Vec<String>>()
+                .join(",")
+        );
+        let movements = movements_stmt
+            .query_map(params![id_list], row_to_movement)?
+            .collect::<Result<Vec<Movement>, rusqlite::Error>>()?;
+
+        let mut suggested_keep_id = movements[0].id;
+        let mut most_purchases = -1;
+        for movement in &movements {
+            let purchases: i64 =
+                purchases_stmt.query_row(params![movement.id], |row| row.get(0))?;
+            if purchases > most_purchases
+                || (purchases == most_purchases && movement.id < suggested_keep_id)
+            {
+                most_purchases = purchases;
+                suggested_keep_id = movement.id;
+            }
+        }
+
+        let pair_scores = &cluster_scores[&root];
+        let confidence = pair_scores.iter().sum::<f64>() / pair_scores.len() as f64;
+
+        clusters.push(DuplicateCluster {
+            confidence: (confidence * 100.0).round() / 100.0,
+            suggested_keep_id,
+            movements,
+        });
+    }
+
+    clusters.sort_by(|a, b| {
+        b.confidence
+            .total_cmp(&a.confidence)
+            .then(a.suggested_keep_id.cmp(&b.suggested_keep_id))
+    });
+
+    Ok(clusters)
+}
+
+/// Fusiona duplicados en un único movimiento.
+///
+/// Las compras, grupos, etiquetas, aportes a objetivos y adjuntos de los
+/// duplicados pasan a `keep_id`, y después los duplicados se eliminan. Todo
+/// ocurre en una transacción y se puede deshacer como una sola operación.
+#[tauri::command]
+pub fn merge_movements(
+    state: tauri::State<crate::AppState>,
+    keep_id: i64,
+    duplicate_ids: Vec<i64>,
+) -> Result<Movement, OrbitError> {
+    if duplicate_ids.is_empty() {
+        return Err(OrbitError::ValidationError(
+            "Debe indicar al menos un movimiento a fusionar".into(),
+        ));
+    }
+    if duplicate_ids.contains(&keep_id) {
+        return Err(OrbitError::ValidationError(
+            "El movimiento a conservar no puede estar entre los duplicados".into(),
+        ));
+    }
+
+    let mut conn = state.conn.lock().unwrap();
+
+    // Igual que en la búsqueda: solo se fusionan movimientos de la misma
+    // cuenta, moneda y tipo
+    let mut keep_key: Option<(i64, String, String)> = None;
+    for id in std::iter::once(&keep_id).chain(&duplicate_ids) {
+        let key = conn
+            .query_row(
+                "SELECT account_id, currency, mov_type FROM movements WHERE id = ?1",
+                params![id],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .optional()?
+            .ok_or_else(|| {
+                OrbitError::NotFound(format!("No se encontró el movimiento con ID {}", id))
+            })?;
+
+        match &keep_key {
+            None => keep_key = Some(key),
+            Some(keep) if *keep != key => {
+                return Err(OrbitError::ValidationError(format!(
+                    "El movimiento con ID {} no es de la misma cuenta, moneda y tipo que el que se conserva",
+                    id
+                )));
+            }
+            Some(_) => {}
+        }
+    }
+
+    let tx = conn.transaction()?;
+    let op = history::begin(&tx, "Fusionar movimientos duplicados")?;
+
+    for dup_id in &duplicate_ids {
+        tx.execute(
+            "UPDATE purchases SET mov_id = ?1 WHERE mov_id = ?2",
+            params![keep_id, dup_id],
+        )?;
+        tx.execute(
+            "UPDATE shopping_lists SET mov_id = ?1 WHERE mov_id = ?2",
+            params![keep_id, dup_id],
+        )?;
+        // OR IGNORE: si ambos ya estaban en el mismo grupo/etiqueta/objetivo, queda uno
+        tx.execute(
+            "INSERT OR IGNORE INTO movements_groups (mov_id, group_id)
+             SELECT ?1, group_id FROM movements_groups WHERE mov_id = ?2",
+            params![keep_id, dup_id],
+        )?;
+        tx.execute(
+            "INSERT OR IGNORE INTO movements_tags (mov_id, tag_id)
+             SELECT ?1, tag_id FROM movements_tags WHERE mov_id = ?2",
+            params![keep_id, dup_id],
+        )?;
+        tx.execute(
+            "INSERT OR IGNORE INTO goal_contributions (goal_id, mov_id)
+             SELECT goal_id, ?1 FROM goal_contributions WHERE mov_id = ?2",
+            params![keep_id, dup_id],
+        )?;
+        // Si el mismo archivo ya estaba adjunto a keep_id, el vínculo repetido
+        // se ignora y cae con el duplicado
+        tx.execute(
+            "UPDATE OR IGNORE attachment_links SET mov_id = ?1 WHERE mov_id = ?2",
+            params![keep_id, dup_id],
+        )?;
+        // splits y vínculos restantes se limpian por ON DELETE CASCADE
+        tx.execute("DELETE FROM movements WHERE id = ?1", params![dup_id])?;
+    }
+
+    op.finish();
+    tx.commit()?;
+
+    let movement = conn.query_row(
+        &format!("{MOVEMENT_SELECT} WHERE id = ?1"),
+        params![keep_id],
+        row_to_movement,
+    )?;
+
+    Ok(movement)
+}
@@ -8,6 +8,12 @@ pub enum OrbitError {
 
     #[error("Registro no encontrado: {0}")]
     NotFound(String),
+
+    #[error("Error de archivo: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Error al migrar la base de datos: {0}")]
+    Migration(#[from] rusqlite_migration::Error),
 }
 
 // Convertimos el error en String al serializar para que Tauri lo maneje en el frontend
@@ -0,0 +1,810 @@
+use std::collections::HashMap;
+
+use chrono::{Datelike, NaiveDate};
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    errors::OrbitError,
+    history,
+    utils::{format_date, normalize_text, parse_date},
+};
+
+/// Months of history used to detect recurring movements.
+const RECURRING_LOOKBACK_MONTHS: u32 = 18;
+
+/// Full months of history averaged to estimate variable spend.
+const VARIABLE_SPEND_MONTHS: u32 = 3;
+
+/// Minimum occurrences for a movement to count as recurring.
+const MIN_RECURRING_OCCURRENCES: usize = 3;
+
+/// Periodicity of a recurring movement.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Period {
+    Weekly,
+    Biweekly,
+    Monthly,
+    Quarterly,
+    Yearly,
+}
+
+impl Period {
+    const ALL: [Period; 5] = [
+        Period::Weekly,
+        Period::Biweekly,
+        Period::Monthly,
+        Period::Quarterly,
+        Period::Yearly,
+    ];
+
+    /// Representación tal cual se guarda en `subscriptions.period`.
+    pub fn as_db_str(self) -> &'static str {
+        match self {
+            Period::Weekly => "weekly",
+            Period::Biweekly => "biweekly",
+            Period::Monthly => "monthly",
+            Period::Quarterly => "quarterly",
+            Period::Yearly => "yearly",
+        }
+    }
+
+    pub(crate) fn from_db_str(value: &str) -> Option<Period> {
+        Period::ALL.into_iter().find(|p| p.as_db_str() == value)
+    }
+
+    /// Ocurrencias por año, para llevar un monto periódico a mensual o anual.
+    pub(crate) fn per_year(self) -> i64 {
+        match self {
+            Period::Weekly => 52,
+            Period::Biweekly => 26,
+            Period::Monthly => 12,
+            Period::Quarterly => 4,
+            Period::Yearly => 1,
+        }
+    }
+
+    /// (días nominales, tolerancia en días) entre ocurrencias.
+    pub(crate) fn days(self) -> (i64, i64) {
+        match self {
+            Period::Weekly => (7, 1),
+            Period::Biweekly => (14, 2),
+            Period::Monthly => (30, 4),
+            Period::Quarterly => (91, 8),
+            Period::Yearly => (365, 12),
+        }
+    }
+
+    /// Ocurrencia número `n` contando desde `start` (la 0 es `start`). Se
+    /// calcula desde el inicio y no encadenando ocurrencias, para no arrastrar
+    /// el recorte de fin de mes: del 31 pasa al 29 de febrero y vuelve al 31.
+    pub(crate) fn nth(self, start: NaiveDate, n: u32) -> Option<NaiveDate> {
+        let (days, months) = match self {
+            Period::Weekly => (7, 0),
+            Period::Biweekly => (14, 0),
+            Period::Monthly => (0, 1),
+            Period::Quarterly => (0, 3),
+            Period::Yearly => (0, 12),
+        };
+        if months == 0 {
+            start.checked_add_days(chrono::Days::new(days * n as u64))
+        } else {
+            start.checked_add_months(chrono::Months::new(months * n))
+        }
+    }
+}
+
+/// A movement that repeats with a detected periodicity (salary, rent...).
+#[derive(Debug, Clone, Serialize)]
+pub struct RecurringPattern {
+    pub details: String,
+    /// "income" or "expense"
+    pub mov_type: String,
+    /// Typical amount in the account currency, in cents
+    pub amount: i64,
+    pub period: Period,
+    pub category_id: Option<i64>,
+    pub occurrences: i64,
+    pub last_date: String,
+    pub next_date: String,
+}
+
+/// A known future movement: an installment, a fixed-term deposit maturing...
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduledMovement {
+    pub id: i64,
+    pub details: String,
+    /// "income" or "expense"
+    pub mov_type: String,
+    /// In cents, in the account currency
+    pub amount: i64,
+    pub account_id: i64,
+    pub category_id: Option<i64>,
+    /// Date of the first occurrence
+    pub start_date: String,
+    pub interval_months: i64,
+    /// `None` = repeats with no end
+    pub occurrences: Option<i64>,
+    pub created_at: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AddScheduledMovement {
+    pub details: String,
+    pub mov_type: String,
+    pub amount: i64,
+    pub account_id: i64,
+    #[serde(default)]
+    pub category_id: Option<i64>,
+    pub start_date: String,
+    #[serde(default = "default_interval_months")]
+    pub interval_months: i64,
+    #[serde(default)]
+    pub occurrences: Option<i64>,
+}
+
+fn default_interval_months() -> i64 {
+    1
+}
+
+/// Average monthly spend of a category outside recurring and scheduled
+/// movements.
+#[derive(Debug, Clone, Serialize)]
+pub struct VariableSpend {
+    pub category_id: Option<i64>,
+    pub category: String,
+    pub monthly_average: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DailyBalance {
+    pub date: String,
+    /// Known income of the day (recurring and scheduled)
+    pub inflow: i64,
+    /// Known expenses of the day plus the daily share of variable spend
+    pub outflow: i64,
+    pub balance: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ForecastWarning {
+    /// First day of the period with a negative balance
+    pub date: String,
+    pub balance: i64,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AccountForecast {
+    pub account_id: i64,
+    pub account_name: String,
+    pub currency: String,
+    pub starting_balance: i64,
+    pub recurring: Vec<RecurringPattern>,
+    pub scheduled: Vec<ScheduledMovement>,
+    pub variable_spend: Vec<VariableSpend>,
+    pub daily: Vec<DailyBalance>,
+    pub lowest_balance: i64,
+    pub lowest_balance_date: String,
+    pub warnings: Vec<ForecastWarning>,
+}
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+pub(crate) fn median(values: &mut [i64]) -> Option<i64> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_unstable();
+
+    let middle = values.len() / 2;
+    Some(if values.len().is_multiple_of(2) {
+        (values[middle - 1] + values[middle]) / 2
+    } else {
+        values[middle]
+    })
+}
+
+/// Período de una serie de fechas ordenadas, si al menos dos tercios de los
+/// intervalos caen dentro de la tolerancia de alguno de los períodos.
+pub(crate) fn detect_period(dates: &[NaiveDate]) -> Option<Period> {
+    if dates.len() < MIN_RECURRING_OCCURRENCES {
+        return None;
+    }
+
+    let gaps: Vec<i64> = dates
+        .windows(2)
+        .map(|pair| (pair[1] - pair[0]).num_days())
+        .collect();
+    let typical = median(&mut gaps.clone())?;
+
+    Period::ALL.into_iter().find(|period| {
+        let (days, tolerance) = period.days();
+        let matching = gaps
+            .iter()
+            .filter(|gap| (**gap - days).abs() <= tolerance)
+            .count();
+        (typical - days).abs() <= tolerance && matching * 3 >= gaps.len() * 2
+    })
+}
+
+/// Ocurrencias de un movimiento programado entre `from` y `to`, ambos incluidos.
+fn scheduled_dates(
+    scheduled: &ScheduledMovement,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Vec<NaiveDate> {
+    let Ok(start) = parse_date(&scheduled.start_date) else {
+        return Vec::new();
+    };
+
+    let mut dates = Vec::new();
+    for n in 0.. {
+        if scheduled.occurrences.is_some_and(|total| n >= total) {
+            break;
+        }
+        // Desde la fecha de inicio, para no arrastrar el recorte de fin de mes
+        let Some(date) =
+            start.checked_add_months(chrono::Months::new((n * scheduled.interval_months) as u32))
+        else {
+            break;
+        };
+        if date > to {
+            break;
+        }
+        if date >= from {
+            dates.push(date);
+        }
+    }
+
+    dates
+}
+
+/// Fechas de un patrón recurrente entre mañana y `to`.
+///
+/// Se cuentan desde la última ocurrencia real: `next_date` ya puede venir
+/// recortada a fin de mes y arrastraría el recorte a los meses siguientes.
+fn recurring_dates(pattern: &RecurringPattern, today: NaiveDate, to: NaiveDate) -> Vec<NaiveDate> {
+    let Ok(last) = parse_date(&pattern.last_date) else {
+        return Vec::new();
+    };
+
+    (1..)
+        .map_while(|n| pattern.period.nth(last, n))
+        .take_while(|date| *date <= to)
+        .filter(|date| *date > today)
+        .collect()
+}
+
+fn row_to_scheduled(row: &rusqlite::Row) -> rusqlite::Result<ScheduledMovement> {
+    Ok(ScheduledMovement {
+        id: row.get(0)?,
+        details: row.get(1)?,
+        mov_type: row.get(2)?,
+        amount: row.get(3)?,
+        account_id: row.get(4)?,
+        category_id: row.get(5)?,
+        start_date: row.get(6)?,
+        interval_months: row.get(7)?,
+        occurrences: row.get(8)?,
+        created_at: row.get(9)?,
+    })
+}
+
+const SCHEDULED_SELECT: &str = "SELECT id, details, mov_type, amount, account_id, category_id,
+        start_date, interval_months, occurrences, created_at
+     FROM scheduled_movements";
+
+/// Movimientos recurrentes de una cuenta detectados en el historial reciente.
+/// Solo se consideran vigentes los que tuvieron su última ocurrencia hace
+/// menos de dos períodos.
+fn detect_recurring(
+    conn: &rusqlite::Connection,
+    account_id: i64,
+    today: NaiveDate,
+) -> Result<Vec<RecurringPattern>, OrbitError> {
+    let since = today
+        .checked_sub_months(chrono::Months::new(RECURRING_LOOKBACK_MONTHS))
+        .unwrap_or(today);
+
+    let mut stmt = conn.prepare(
+        "SELECT details, mov_type, date, original_amount, category_id
+         FROM movements
+         WHERE account_id = ?1 AND mov_type IN ('income', 'expense')
+           AND date >= ?2 AND date <= ?3
+         ORDER BY date, id",
+    )?;
+
+    // (detalle original, fechas, montos, categoría) por (detalle normalizado, tipo)
+    type Group = (String, Vec<NaiveDate>, Vec<i64>, Option<i64>);
+    let mut groups: HashMap<(String, String), Group> = HashMap::new();
+    let rows = stmt.query_map(
+        params![account_id, format_date(since), format_date(today)],
+        |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, i64>(3)?,
+                row.get::<_, Option<i64>>(4)?,
+            ))
+        },
+    )?;
+    for row in rows {
+        let (details, mov_type, date, amount, category_id) = row?;
+        let Ok(date) = parse_date(&date) else {
+            continue;
+        };
+        let group = groups
+            .entry((normalize_text(&details), mov_type))
+            .or_insert_with(|| (details, Vec::new(), Vec::new(), category_id));
+        // Dos movimientos el mismo día no son dos ocurrencias
+        if group.1.last() == Some(&date) {
+            continue;
+        }
+        group.1.push(date);
+        group.2.push(amount);
+        group.3 = category_id;
+    }
+
+    let mut patterns: Vec<RecurringPattern> = Vec::new();
+    for ((_, mov_type), (details, dates, amounts, category_id)) in groups {
+        let Some(period) = detect_period(&dates) else {
+            continue;
+        };
+        let last = dates[dates.len() - 1];
+        if (today - last).num_days() > period.days().0 * 2 {
+            continue;
+        }
+
+        // El monto de las últimas ocurrencias refleja mejor el actual
+        let mut recent: Vec<i64> = amounts.iter().rev().take(3).copied().collect();
+        let Some(amount) = median(&mut recent) else {
+            continue;
+        };
+
+        let Some(next) = (1..)
+            .map_while(|n| period.nth(last, n))
+            .find(|date| *date > today)
+        else {
+            continue;
+        };
+
+        patterns.push(RecurringPattern {
+            details,
+            mov_type,
+            amount,
+            period,
+            category_id,
+            occurrences: dates.len() as i64,
+            last_date: format_date(last),
+            next_date: format_date(next),
+        });
+    }
+
+    patterns.sort_by(|a, b| {
+        a.next_date
+            .cmp(&b.next_date)
+            .then(a.details.cmp(&b.details))
+    });
+
+    Ok(patterns)
+}
+
+/// Gasto variable promedio por categoría en los últimos meses completos,
+/// sin los movimientos recurrentes ni programados (ya se proyectan aparte).
+fn variable_spend(
+    conn: &rusqlite::Connection,
+    account_id: i64,
+    today: NaiveDate,
+    excluded: &[String],
+) -> Result<Vec<VariableSpend>, OrbitError> {
+    let this_month = today.with_day(1).unwrap_or(today);
+    let since = this_month
+        .checked_sub_months(chrono::Months::new(VARIABLE_SPEND_MONTHS))
+        .unwrap_or(this_month);
+
+    let mut stmt = conn.prepare(
+        "SELECT m.details, m.category_id, COALESCE(c.name, 'Sin categoría'), m.original_amount
+         FROM movements m
+         LEFT JOIN categories c ON c.id = m.category_id
+         WHERE m.account_id = ?1 AND m.mov_type = 'expense'
+           AND m.date >= ?2 AND m.date < ?3",
+    )?;
+
+    let mut totals: HashMap<Option<i64>, (String, i64)> = HashMap::new();
+    let rows = stmt.query_map(
+        params![account_id, format_date(since), format_date(this_month)],
+        |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, Option<i64>>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, i64>(3)?,
+            ))
+        },
+    )?;
+    for row in rows {
+        let (details, category_id, category, amount) = row?;
+        if excluded.contains(&normalize_text(&details)) {
+            continue;
+        }
+        totals.entry(category_id).or_insert((category, 0)).1 += amount;
+    }
+
+    let mut spend: Vec<VariableSpend> = totals
+        .into_iter()
+        .map(|(category_id, (category, total))| VariableSpend {
+            category_id,
+            category,
+            monthly_average: (total as f64 / VARIABLE_SPEND_MONTHS as f64).round() as i64,
+        })
+        .collect();
+    spend.sort_by_key(|v| std::cmp::Reverse(v.monthly_average));
+
+    Ok(spend)
+}
+
+/// Saldo diario proyectado desde mañana hasta `to`. El gasto variable se
+/// reparte en partes iguales por día (un mes = 30 días).
+fn project_balance(
+    starting_balance: i64,
+    today: NaiveDate,
+    to: NaiveDate,
+    events: &HashMap<NaiveDate, (i64, i64)>,
+    variable_monthly: i64,
+) -> Vec<DailyBalance> {
+    let mut daily = Vec::new();
+    let mut balance = starting_balance as f64;
+    let variable_daily = variable_monthly as f64 / 30.0;
+
+    let mut date = today + chrono::Duration::days(1);
+    while date <= to {
+        let (inflow, outflow) = events.get(&date).copied().unwrap_or((0, 0));
+        balance += inflow as f64 - outflow as f64 - variable_daily;
+
+        daily.push(DailyBalance {
+            date: format_date(date),
+            inflow,
+            outflow: outflow + variable_daily.round() as i64,
+            balance: balance.round() as i64,
+        });
+        date += chrono::Duration::days(1);
+    }
+
+    daily
+}
+
+/// Una advertencia por cada tramo en que el saldo queda negativo.
+fn negative_warnings(account_name: &str, daily: &[DailyBalance]) -> Vec<ForecastWarning> {
+    let mut warnings = Vec::new();
+    let mut negative = false;
+
+    for day in daily {
+        if day.balance < 0 && !negative {
+            warnings.push(ForecastWarning {
+                date: day.date.clone(),
+                balance: day.balance,
+                message: format!(
+                    "El saldo de {account_name} quedaría negativo a partir del {}",
+                    day.date
+                ),
+            });
+        }
+        negative = day.balance < 0;
+    }
+
+    warnings
+}
+
+// ---------------------------------------------------------------------------
+// Commands
+// ---------------------------------------------------------------------------
+
+#[tauri::command]
+pub fn get_scheduled_movements(
+    state: tauri::State<crate::AppState>,
+    account_id: Option<i64>,
+) -> Result<Vec<ScheduledMovement>, OrbitError> {
+    let conn = state.conn.lock().unwrap();
+    let mut stmt = conn.prepare(&format!(
+        "{SCHEDULED_SELECT} WHERE ?1 IS NULL OR account_id = ?1 ORDER BY start_date, id"
+    ))?;
+
+    let scheduled = stmt
+        .query_map(params![account_id], row_to_scheduled)?
+        .collect::<Result<Vec<ScheduledMovement>, rusqlite::Error>>()?;
+
+    Ok(scheduled)
+}
+
+#[tauri::command]
+pub fn add_scheduled_movement(
+    state: tauri::State<crate::AppState>,
+    scheduled: AddScheduledMovement,
+) -> Result<ScheduledMovement, OrbitError> {
+    if scheduled.details.trim().is_empty() {
+        return Err(OrbitError::ValidationError(
+            "La descripción no puede estar vacía".into(),
+        ));
+    }
+    if scheduled.mov_type != "income" && scheduled.mov_type != "expense" {
+        return Err(OrbitError::ValidationError(format!(
+            "Tipo inválido: {:?} (debe ser income o expense)",
+            scheduled.mov_type
+        )));
+    }
+    if scheduled.amount <= 0 {
+        return Err(OrbitError::ValidationError(
+            "El monto debe ser mayor a cero".into(),
+        ));
+    }
+    if scheduled.interval_months < 1 || scheduled.occurrences.is_some_and(|n| n < 1) {
+        return Err(OrbitError::ValidationError(
+            "El intervalo y la cantidad de ocurrencias deben ser al menos 1".into(),
+        ));
+    }
+    parse_date(&scheduled.start_date)?;
+
+    let conn = state.conn.lock().unwrap();
+    let op = history::begin(&conn, "Agregar movimiento programado")?;
+
+    conn.execute(
+        "INSERT INTO scheduled_movements
+            (details, mov_type, amount, account_id, category_id, start_date, interval_months, occurrences)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![
+            scheduled.details.trim(),
+            scheduled.mov_type,
+            scheduled.amount,
+            scheduled.account_id,
+            scheduled.category_id,
+            scheduled.start_date,
+            scheduled.interval_months,
+            scheduled.occurrences
+        ],
+    )?;
+
+    let scheduled = conn.query_row(
+        &format!("{SCHEDULED_SELECT} WHERE id = ?1"),
+        params![conn.last_insert_rowid()],
+        row_to_scheduled,
+    )?;
+
+    op.finish();
+
+    Ok(scheduled)
+}
+
+#[tauri::command]
+pub fn delete_scheduled_movement(
+    state: tauri::State<crate::AppState>,
+    id: i64,
+) -> Result<(), OrbitError> {
+    let conn = state.conn.lock().unwrap();
+    let op = history::begin(&conn, "Eliminar movimiento programado")?;
+
+    let rows_affected =
+        conn.execute("DELETE FROM scheduled_movements WHERE id = ?1", params![id])?;
+
+    if rows_affected == 0 {
+        return Err(OrbitError::NotFound(format!(
+            "No se encontró el movimiento programado con ID {}",
+            id
+        )));
+    }
+
+    op.finish();
+
+    Ok(())
+}
+
+/// Proyecta el saldo de cada cuenta (o de una sola) día por día durante los
+/// próximos `months` meses (1 a 12), en la moneda de la cuenta.
+///
+/// Suma los movimientos recurrentes detectados en el historial, los
+/// movimientos programados y el gasto variable promedio por categoría. Los
+/// recurrentes con la misma descripción que un programado no se cuentan dos
+/// veces. Las transferencias no se proyectan, igual que en los saldos.
+#[tauri::command]
+pub fn get_cash_flow_forecast(
+    state: tauri::State<crate::AppState>,
+    months: i64,
+    account_id: Option<i64>,
+) -> Result<Vec<AccountForecast>, OrbitError> {
+    if !(1..=12).contains(&months) {
+        return Err(OrbitError::ValidationError(
+            "El pronóstico debe abarcar entre 1 y 12 meses".into(),
+        ));
+    }
+
+    let today = chrono::Local::now().date_naive();
+    let to = today
+        .checked_add_months(chrono::Months::new(months as u32))
+        .unwrap_or(today);
+    let tomorrow = today + chrono::Duration::days(1);
+
+    let conn = state.conn.lock().unwrap();
+
+    let mut stmt = conn.prepare(
+        "SELECT a.id, a.name, a.currency, COALESCE(b.current_balance_original, 0)
+         FROM accounts a
+         LEFT JOIN v_account_balance_original b ON b.account_id = a.id
+         WHERE ?1 IS NULL OR a.id = ?1
+         ORDER BY a.id",
+    )?;
+    let accounts = stmt
+        .query_map(params![account_id], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, f64>(3)?.round() as i64,
+            ))
+        })?
+        .collect::<Result<Vec<(i64, String, String, i64)>, rusqlite::Error>>()?;
+
+    if let (Some(id), true) = (account_id, accounts.is_empty()) {
+        return Err(OrbitError::NotFound(format!(
+            "No se encontró la cuenta con ID {}",
+            id
+        )));
+    }
+
+    let mut scheduled_stmt = conn.prepare(&format!(
+        "{SCHEDULED_SELECT} WHERE account_id = ?1 ORDER BY start_date, id"
+    ))?;
+
+    let mut forecasts: Vec<AccountForecast> = Vec::with_capacity(accounts.len());
+    for (account_id, account_name, currency, starting_balance) in accounts {
+        let scheduled: Vec<ScheduledMovement> = scheduled_stmt
+            .query_map(params![account_id], row_to_scheduled)?
+            .collect::<Result<Vec<ScheduledMovement>, rusqlite::Error>>()?
+            .into_iter()
+            .filter(|s| !scheduled_dates(s, tomorrow, to).is_empty())
+            .collect();
+        let scheduled_names: Vec<String> = scheduled
+            .iter()
+            .map(|s| normalize_text(&s.details))
+            .collect();
+
+        let recurring: Vec<RecurringPattern> = detect_recurring(&conn, account_id, today)?
+            .into_iter()
+            .filter(|r| !scheduled_names.contains(&normalize_text(&r.details)))
+            .collect();
+
+        let excluded: Vec<String> = scheduled_names
+            .iter()
+            .cloned()
+            .chain(recurring.iter().map(|r| normalize_text(&r.details)))
+            .collect();
+        let variable = variable_spend(&conn, account_id, today, &excluded)?;
+
+        // (entradas, salidas) conocidas por día
+        let mut events: HashMap<NaiveDate, (i64, i64)> = HashMap::new();
+        let mut add_event = |date: NaiveDate, mov_type: &str, amount: i64| {
+            let day = events.entry(date).or_insert((0, 0));
+            if mov_type == "income" {
+                day.0 += amount;
+            } else {
+                day.1 += amount;
+            }
+        };
+
+        for pattern in &recurring {
+            for date in recurring_dates(pattern, today, to) {
+                add_event(date, &pattern.mov_type, pattern.amount);
+            }
+        }
+        for item in &scheduled {
+            for date in scheduled_dates(item, tomorrow, to) {
+                add_event(date, &item.mov_type, item.amount);
+            }
+        }
+
+        let variable_monthly: i64 = variable.iter().map(|v| v.monthly_average).sum();
+        let daily = project_balance(starting_balance, today, to, &events, variable_monthly);
+        let warnings = negative_warnings(&account_name, &daily);
+
+        let (lowest_balance, lowest_balance_date) = daily
+            .iter()
+            .min_by_key(|d| d.balance)
+            .map(|d| (d.balance, d.date.clone()))
+            .unwrap_or((starting_balance, format_date(today)));
+
+        forecasts.push(AccountForecast {
+            account_id,
+            account_name,
+            currency,
+            starting_balance,
+            recurring,
+            scheduled,
+            variable_spend: variable,
+            daily,
+            lowest_balance,
+            lowest_balance_date,
+            warnings,
+        });
+    }
+
+    Ok(forecasts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(value: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_detect_period() {
+        let monthly = [
+            date("2025-01-05"),
+            date("2025-02-04"),
+            date("2025-03-05"),
+            date("2025-04-07"),
+        ];
+        assert_eq!(detect_period(&monthly), Some(Period::Monthly));
+
+        let weekly = [date("2025-01-01"), date("2025-01-08"), date("2025-01-15")];
+        assert_eq!(detect_period(&weekly), Some(Period::Weekly));
+
+        let irregular = [date("2025-01-01"), date("2025-01-20"), date("2025-03-15")];
+        assert_eq!(detect_period(&irregular), None);
+
+        assert_eq!(detect_period(&monthly[..2]), None);
+    }
+
+    #[test]
+    fn test_scheduled_dates_respects_occurrences_and_month_end() {
+        let scheduled = ScheduledMovement {
+            id: 1,
+            details: "Heladera 3 cuotas".to_string(),
+            mov_type: "expense".to_string(),
+            amount: 100_000,
+            account_id: 1,
+            category_id: None,
+            start_date: "2025-01-31".to_string(),
+            interval_months: 1,
+            occurrences: Some(3),
+            created_at: String::new(),
+        };
+
+        let dates = scheduled_dates(&scheduled, date("2025-02-01"), date("2025-12-31"));
+
+        // Febrero se recorta al 28, marzo vuelve al 31; la primera ya pasó
+        assert_eq!(dates, vec![date("2025-02-28"), date("2025-03-31")]);
+    }
+
+    #[test]
+    fn test_recurring_dates_keep_day_31() {
+        let pattern = RecurringPattern {
+            details: "Alquiler".to_string(),
+            mov_type: "expense".to_string(),
+            amount: 500_000,
+            period: Period::Monthly,
+            category_id: None,
+            occurrences: 4,
+            last_date: "2025-01-31".to_string(),
+            next_date: "2025-02-28".to_string(),
+        };
+
+        let dates = recurring_dates(&pattern, date("2025-02-10"), date("2025-05-31"));
+
+        // Después del recorte de febrero vuelve al 31
+        assert_eq!(
+            dates,
+            vec![
+                date("2025-02-28"),
+                date("2025-03-31"),
+                date("2025-04-30"),
+                date("2025-05-31"),
+            ]
+        );
+    }
+}
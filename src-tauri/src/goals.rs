@@ -0,0 +1,591 @@
+use chrono::NaiveDate;
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    errors::OrbitError,
+    history,
+    movements::{Currency, RateType},
+    rates::usd_to_ars,
+    utils::{format_date, parse_date},
+};
+
+/// Months of contribution history averaged to estimate the saving pace.
+const PACE_MONTHS: u32 = 6;
+
+/// Average days per month, to turn monthly amounts into dates.
+const DAYS_PER_MONTH: f64 = 30.44;
+
+/// A savings goal linked to an account.
+#[derive(Debug, Clone, Serialize)]
+pub struct Goal {
+    pub id: i64,
+    pub name: String,
+    /// In cents of `currency`
+    pub target_amount: i64,
+    pub currency: String,
+    pub target_date: Option<String>,
+    pub account_id: i64,
+    /// Only a portion of the account is reserved for the goal
+    pub earmarked: bool,
+    /// Already saved when the goal was created (earmarked goals only)
+    pub initial_amount: i64,
+    pub created_at: String,
+}
+
+/// A movement counted towards a goal. Withdrawals are negative.
+#[derive(Debug, Clone, Serialize)]
+pub struct GoalContribution {
+    pub mov_id: i64,
+    pub date: String,
+    pub details: String,
+    /// In cents of the goal currency
+    pub amount: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GoalProgress {
+    pub goal: Goal,
+    pub saved: i64,
+    /// Zero once the target is reached
+    pub remaining: i64,
+    pub percent: f64,
+    pub completed: bool,
+    /// Needed per month from now on to reach the target by `target_date`;
+    /// `None` without a target date
+    pub required_monthly: Option<i64>,
+    /// Average net contribution per month over the last months: the linked
+    /// contributions for earmarked goals, the account net flow otherwise
+    pub monthly_pace: i64,
+    /// Date the target is reached at `monthly_pace`; `None` if the pace
+    /// isn't positive
+    pub projected_date: Option<String>,
+    /// Whether `projected_date` is on or before `target_date`
+    pub on_track: Option<bool>,
+    /// Newest first
+    pub contributions: Vec<GoalContribution>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AddGoal {
+    pub name: String,
+    pub target_amount: i64,
+    #[serde(default)]
+    pub currency: Currency,
+    #[serde(default)]
+    pub target_date: Option<String>,
+    pub account_id: i64,
+    #[serde(default)]
+    pub earmarked: bool,
+    #[serde(default)]
+    pub initial_amount: i64,
+}
+
+pub type UpdateGoal = AddGoal;
+
+/// A contribution recorded as a new `transfer` movement in the goal account:
+/// it reserves money without changing the account balance.
+#[derive(Debug, Deserialize)]
+pub struct AddGoalContribution {
+    /// In cents of the goal currency
+    pub amount: i64,
+    pub date: String,
+    #[serde(default)]
+    pub details: Option<String>,
+    /// Required for USD goals, as in `AddMovement`
+    #[serde(default)]
+    pub exchange_rate: Option<i64>,
+    #[serde(default)]
+    pub rate_type: Option<RateType>,
+}
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// (aporte mensual necesario para llegar a `target_date`, fecha proyectada al
+/// ritmo `pace`). Con la fecha límite vencida, lo que falta se necesita ya.
+fn project(
+    remaining: i64,
+    pace: i64,
+    today: NaiveDate,
+    target_date: Option<NaiveDate>,
+) -> (Option<i64>, Option<NaiveDate>) {
+    if remaining <= 0 {
+        return (target_date.map(|_| 0), Some(today));
+    }
+
+    let required = target_date.map(|target| {
+        let months = ((target - today).num_days() as f64 / DAYS_PER_MONTH)
+            .ceil()
+            .max(1.0);
+        (remaining as f64 / months).ceil() as i64
+    });
+
+    let projected = (pace > 0).then(|| {
+        let days = (remaining as f64 / pace as f64 * DAYS_PER_MONTH).ceil() as i64;
+        today + chrono::Duration::days(days)
+    });
+
+    (required, projected)
+}
+
+const GOAL_SELECT: &str = "SELECT id, name, target_amount, currency, target_date, account_id,
+        earmarked, initial_amount, created_at
+ FROM goals";
+
+fn row_to_goal(row: &rusqlite::Row) -> rusqlite::Result<Goal> {
+    Ok(Goal {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        target_amount: row.get(2)?,
+        currency: row.get(3)?,
+        target_date: row.get(4)?,
+        account_id: row.get(5)?,
+        earmarked: row.get(6)?,
+        initial_amount: row.get(7)?,
+        created_at: row.get(8)?,
+    })
+}
+
+fn fetch_goal(conn: &rusqlite::Connection, id: i64) -> Result<Goal, OrbitError> {
+    conn.query_row(
+        &format!("{GOAL_SELECT} WHERE id = ?1"),
+        params![id],
+        row_to_goal,
+    )
+    .map_err(|e| match e {
+        rusqlite::Error::QueryReturnedNoRows => {
+            OrbitError::NotFound(format!("No se encontró el objetivo con ID {id}"))
+        }
+        e => OrbitError::Database(e),
+    })
+}
+
+fn validate_goal(conn: &rusqlite::Connection, goal: &AddGoal) -> Result<(), OrbitError> {
+    if goal.name.trim().is_empty() {
+        return Err(OrbitError::ValidationError(
+            "El nombre no puede estar vacío".into(),
+        ));
+    }
+    if goal.target_amount <= 0 {
+        return Err(OrbitError::ValidationError(
+            "El monto objetivo debe ser mayor a cero".into(),
+        ));
+    }
+    if goal.initial_amount < 0 {
+        return Err(OrbitError::ValidationError(
+            "El monto inicial no puede ser negativo".into(),
+        ));
+    }
+    if let Some(date) = &goal.target_date {
+        parse_date(date)?;
+    }
+
+    let account_currency: String = conn
+        .query_row(
+            "SELECT currency FROM accounts WHERE id = ?1",
+            params![goal.account_id],
+            |row| row.get(0),
+        )
+        .map_err(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => OrbitError::NotFound(format!(
+                "No se encontró la cuenta con ID {}",
+                goal.account_id
+            )),
+            e => OrbitError::Database(e),
+        })?;
+    // Sin reserva, lo ahorrado es el saldo de la cuenta: tiene que estar en la misma moneda
+    if !goal.earmarked && account_currency != goal.currency.as_db_str() {
+        return Err(OrbitError::ValidationError(format!(
+            "La cuenta está en {account_currency}: el objetivo debe estar en la misma moneda o \
+             reservar solo una parte"
+        )));
+    }
+
+    Ok(())
+}
+
+/// Monto de un movimiento expresado en la moneda del objetivo, con signo
+/// (los gastos restan). Un objetivo en USD no puede sumar movimientos en ARS.
+const CONTRIBUTION_AMOUNT: &str = "CASE WHEN m.mov_type = 'expense' THEN -1 ELSE 1 END *
+        CASE WHEN m.currency = g.currency THEN m.original_amount ELSE m.ars_amount END";
+
+fn fetch_contributions(
+    conn: &rusqlite::Connection,
+    goal_id: i64,
+) -> Result<Vec<GoalContribution>, OrbitError> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT m.id, m.date, m.details, {CONTRIBUTION_AMOUNT}
+         FROM goal_contributions gc
+         JOIN goals     g ON g.id = gc.goal_id
+         JOIN movements m ON m.id = gc.mov_id
+         WHERE gc.goal_id = ?1
+         ORDER BY m.date DESC, m.id DESC"
+    ))?;
+    let contributions = stmt
+        .query_map(params![goal_id], |row| {
+            Ok(GoalContribution {
+                mov_id: row.get(0)?,
+                date: row.get(1)?,
+                details: row.get(2)?,
+                amount: row.get(3)?,
+            })
+        })?
+        .collect::<Result<Vec<GoalContribution>, rusqlite::Error>>()?;
+
+    Ok(contributions)
+}
+
+// ---------------------------------------------------------------------------
+// Commands
+// ---------------------------------------------------------------------------
+
+#[tauri::command]
+pub fn get_goals(state: tauri::State<crate::AppState>) -> Result<Vec<Goal>, OrbitError> {
+    let conn = state.conn.lock().unwrap();
+    let mut stmt = conn.prepare(&format!(
+        "{GOAL_SELECT} ORDER BY target_date IS NULL, target_date ASC, name COLLATE NOCASE ASC"
+    ))?;
+    let goals = stmt
+        .query_map([], row_to_goal)?
+        .collect::<Result<Vec<Goal>, rusqlite::Error>>()?;
+
+    Ok(goals)
+}
+
+#[tauri::command]
+pub fn add_goal(state: tauri::State<crate::AppState>, goal: AddGoal) -> Result<Goal, OrbitError> {
+    let conn = state.conn.lock().unwrap();
+    validate_goal(&conn, &goal)?;
+
+    let op = history::begin(&conn, "Agregar objetivo")?;
+    conn.execute(
+        "INSERT INTO goals
+            (name, target_amount, currency, target_date, account_id, earmarked, initial_amount)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            goal.name.trim(),
+            goal.target_amount,
+            goal.currency,
+            goal.target_date,
+            goal.account_id,
+            goal.earmarked,
+            goal.initial_amount
+        ],
+    )?;
+
+    let new_id = conn.last_insert_rowid();
+    op.finish();
+    fetch_goal(&conn, new_id)
+}
+
+#[tauri::command]
+pub fn update_goal(
+    state: tauri::State<crate::AppState>,
+    id: i64,
+    goal: UpdateGoal,
+) -> Result<Goal, OrbitError> {
+    let conn = state.conn.lock().unwrap();
+    fetch_goal(&conn, id)?;
+    validate_goal(&conn, &goal)?;
+
+    let op = history::begin(&conn, "Editar objetivo")?;
+    conn.execute(
+        "UPDATE goals
+         SET name = ?1, target_amount = ?2, currency = ?3, target_date = ?4,
+             account_id = ?5, earmarked = ?6, initial_amount = ?7
+         WHERE id = ?8",
+        params![
+            goal.name.trim(),
+            goal.target_amount,
+            goal.currency,
+            goal.target_date,
+            goal.account_id,
+            goal.earmarked,
+            goal.initial_amount,
+            id
+        ],
+    )?;
+
+    op.finish();
+
+    fetch_goal(&conn, id)
+}
+
+/// Elimina el objetivo. Los movimientos de sus aportes se conservan.
+#[tauri::command]
+pub fn delete_goal(state: tauri::State<crate::AppState>, id: i64) -> Result<(), OrbitError> {
+    let conn = state.conn.lock().unwrap();
+    let op = history::begin(&conn, "Eliminar objetivo")?;
+
+    let deleted = conn.execute("DELETE FROM goals WHERE id = ?1", params![id])?;
+    if deleted == 0 {
+        return Err(OrbitError::NotFound(format!(
+            "No se encontró el objetivo con ID {id}"
+        )));
+    }
+
+    op.finish();
+
+    Ok(())
+}
+
+/// Vincula un movimiento existente de la cuenta del objetivo como aporte
+/// (o retiro, si es un gasto).
+#[tauri::command]
+pub fn link_goal_contribution(
+    state: tauri::State<crate::AppState>,
+    goal_id: i64,
+    mov_id: i64,
+) -> Result<GoalContribution, OrbitError> {
+    let conn = state.conn.lock().unwrap();
+    let goal = fetch_goal(&conn, goal_id)?;
+
+    let (account_id, currency): (i64, String) = conn
+        .query_row(
+            "SELECT account_id, currency FROM movements WHERE id = ?1",
+            params![mov_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .map_err(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => {
+                OrbitError::NotFound(format!("No se encontró el movimiento con ID {mov_id}"))
+            }
+            e => OrbitError::Database(e),
+        })?;
+    if account_id != goal.account_id {
+        return Err(OrbitError::ValidationError(
+            "El movimiento no es de la cuenta del objetivo".into(),
+        ));
+    }
+    if goal.currency == "USD" && currency != "USD" {
+        return Err(OrbitError::ValidationError(
+            "Un objetivo en USD solo admite aportes en USD".into(),
+        ));
+    }
+
+    let op = history::begin(&conn, "Vincular aporte")?;
+    let inserted = conn.execute(
+        "INSERT OR IGNORE INTO goal_contributions (goal_id, mov_id) VALUES (?1, ?2)",
+        params![goal_id, mov_id],
+    )?;
+    if inserted == 0 {
+        return Err(OrbitError::ValidationError(
+            "El movimiento ya es un aporte de este objetivo".into(),
+        ));
+    }
+
+    op.finish();
+
+    fetch_contributions(&conn, goal_id)?
+        .into_iter()
+        .find(|c| c.mov_id == mov_id)
+        .ok_or_else(|| {
+            OrbitError::NotFound(format!("No se encontró el movimiento con ID {mov_id}"))
+        })
+}
+
+/// Desvincula el aporte. El movimiento se conserva.
+#[tauri::command]
+pub fn unlink_goal_contribution(
+    state: tauri::State<crate::AppState>,
+    goal_id: i64,
+    mov_id: i64,
+) -> Result<(), OrbitError> {
+    let conn = state.conn.lock().unwrap();
+    let op = history::begin(&conn, "Desvincular aporte")?;
+
+    let deleted = conn.execute(
+        "DELETE FROM goal_contributions WHERE goal_id = ?1 AND mov_id = ?2",
+        params![goal_id, mov_id],
+    )?;
+    if deleted == 0 {
+        return Err(OrbitError::NotFound(format!(
+            "No se encontró el aporte del movimiento con ID {mov_id} en el objetivo con ID {goal_id}"
+        )));
+    }
+
+    op.finish();
+
+    Ok(())
+}
+
+/// Registra un aporte nuevo: crea la transferencia en la cuenta del objetivo
+/// y la vincula, en una sola operación.
+#[tauri::command]
+pub fn add_goal_contribution(
+    state: tauri::State<crate::AppState>,
+    goal_id: i64,
+    contribution: AddGoalContribution,
+) -> Result<GoalContribution, OrbitError> {
+    if contribution.amount <= 0 {
+        return Err(OrbitError::ValidationError(
+            "El aporte debe ser mayor a cero".into(),
+        ));
+    }
+    parse_date(&contribution.date)?;
+
+    let mut conn = state.conn.lock().unwrap();
+    let goal = fetch_goal(&conn, goal_id)?;
+
+    let (currency, ars_amount, exchange_rate, rate_type) = if goal.currency == "USD" {
+        let (Some(rate), Some(rate_type)) = (contribution.exchange_rate, contribution.rate_type)
+        else {
+            return Err(OrbitError::ValidationError(
+                "Un aporte en USD necesita la cotización y su tipo".into(),
+            ));
+        };
+        if rate <= 0 {
+            return Err(OrbitError::ValidationError(
+                "La cotización debe ser mayor a cero".into(),
+            ));
+        }
+        (
+            Currency::USD,
+            usd_to_ars(contribution.amount, rate),
+            Some(rate),
+            Some(rate_type.as_db_str()),
+        )
+    } else {
+        (Currency::ARS, contribution.amount, None, None)
+    };
+    let details = contribution
+        .details
+        .as_deref()
+        .map(str::trim)
+        .filter(|d| !d.is_empty())
+        .map_or_else(|| format!("Aporte a {}", goal.name), str::to_string);
+
+    let tx = conn.transaction()?;
+    let op = history::begin(&tx, "Agregar aporte")?;
+
+    tx.execute(
+        "INSERT INTO movements
+            (details, date, mov_type, currency, original_amount, ars_amount, exchange_rate, rate_type, account_id)
+         VALUES (?1, ?2, 'transfer', ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![
+            details,
+            contribution.date,
+            currency,
+            contribution.amount,
+            ars_amount,
+            exchange_rate,
+            rate_type,
+            goal.account_id
+        ],
+    )?;
+    let mov_id = tx.last_insert_rowid();
+    tx.execute(
+        "INSERT INTO goal_contributions (goal_id, mov_id) VALUES (?1, ?2)",
+        params![goal_id, mov_id],
+    )?;
+
+    op.finish();
+    tx.commit()?;
+
+    Ok(GoalContribution {
+        mov_id,
+        date: contribution.date,
+        details,
+        amount: contribution.amount,
+    })
+}
+
+/// Avance del objetivo: cuánto se ahorró, cuánto falta, el aporte mensual
+/// necesario para llegar a la fecha y la fecha proyectada al ritmo actual.
+#[tauri::command]
+pub fn get_goal_progress(
+    state: tauri::State<crate::AppState>,
+    id: i64,
+) -> Result<GoalProgress, OrbitError> {
+    let today = chrono::Local::now().date_naive();
+    let pace_since = today
+        .checked_sub_months(chrono::Months::new(PACE_MONTHS))
+        .unwrap_or(today);
+
+    let conn = state.conn.lock().unwrap();
+    let goal = fetch_goal(&conn, id)?;
+    let contributions = fetch_contributions(&conn, id)?;
+
+    let (saved, paced): (i64, i64) = if goal.earmarked {
+        let saved = goal.initial_amount + contributions.iter().map(|c| c.amount).sum::<i64>();
+        let since = format_date(pace_since);
+        let paced = contributions
+            .iter()
+            .filter(|c| c.date.as_str() > since.as_str())
+            .map(|c| c.amount)
+            .sum();
+        (saved, paced)
+    } else {
+        let saved: i64 = conn
+            .query_row(
+                "SELECT current_balance_original FROM v_account_balance_original
+                 WHERE account_id = ?1",
+                params![goal.account_id],
+                |row| row.get(0),
+            )
+            .or_else(|e| match e {
+                // Cuenta sin snapshot de saldo
+                rusqlite::Error::QueryReturnedNoRows => Ok(0),
+                e => Err(e),
+            })?;
+        // Mismo criterio que los saldos: las transferencias no mueven la cuenta
+        let paced: i64 = conn.query_row(
+            "SELECT COALESCE(SUM(CASE mov_type
+                                     WHEN 'income'  THEN  original_amount
+                                     WHEN 'expense' THEN -original_amount
+                                     ELSE 0 END), 0)
+             FROM movements
+             WHERE account_id = ?1 AND date > ?2 AND date <= ?3",
+            params![goal.account_id, format_date(pace_since), format_date(today)],
+            |row| row.get(0),
+        )?;
+        (saved, paced)
+    };
+
+    let monthly_pace = (paced as f64 / PACE_MONTHS as f64).round() as i64;
+    let remaining = (goal.target_amount - saved).max(0);
+    let target_date = match &goal.target_date {
+        Some(date) => Some(parse_date(date)?),
+        None => None,
+    };
+    let (required_monthly, projected) = project(remaining, monthly_pace, today, target_date);
+
+    Ok(GoalProgress {
+        saved,
+        remaining,
+        percent: (saved as f64 / goal.target_amount as f64 * 10_000.0).round() / 100.0,
+        completed: remaining == 0,
+        required_monthly,
+        monthly_pace,
+        projected_date: projected.map(format_date),
+        on_track: target_date.map(|target| projected.is_some_and(|p| p <= target)),
+        contributions,
+        goal,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_project_required_and_projected() {
+        let date = |d: &str| NaiveDate::parse_from_str(d, "%Y-%m-%d").unwrap();
+        let today = date("2024-01-01");
+
+        // 10 meses hasta la fecha: 1000 por mes; a 500 por mes tarda ~20 meses
+        let (required, projected) = project(10_000, 500, today, Some(date("2024-10-29")));
+        assert_eq!(required, Some(1000));
+        assert_eq!(projected, Some(date("2025-09-01")));
+
+        // Fecha vencida: todo lo que falta, ya. Sin ritmo, no hay proyección
+        assert_eq!(
+            project(3_000, 0, today, Some(date("2023-06-01"))),
+            (Some(3_000), None)
+        );
+
+        assert_eq!(project(0, 0, today, None), (None, Some(today)));
+    }
+}
@@ -1,7 +1,11 @@
 use rusqlite::params;
 use serde::{Deserialize, Serialize};
 
-use crate::movements::{Currency, Movement, MovementType, RateType};
+use crate::{
+    errors::OrbitError,
+    history,
+    movements::{Currency, Movement, MovementType, RateType},
+};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Group {
@@ -142,14 +146,17 @@ pub fn get_groups(state: tauri::State<crate::AppState>) -> Vec<GroupWithMovement
 }
 
 #[tauri::command]
-pub fn add_group(state: tauri::State<crate::AppState>, group: AddGroup) -> GroupWithMovements {
+pub fn add_group(
+    state: tauri::State<crate::AppState>,
+    group: AddGroup,
+) -> Result<GroupWithMovements, OrbitError> {
     let conn = state.conn.lock().unwrap();
+    let op = history::begin(&conn, "Agregar grupo")?;
 
     conn.execute(
         "INSERT INTO groups (name, description) VALUES (?1, ?2)",
         params![group.name, group.description],
-    )
-    .unwrap();
+    )?;
 
     let group_id = conn.last_insert_rowid();
 
@@ -158,27 +165,26 @@ pub fn add_group(state: tauri::State<crate::AppState>, group: AddGroup) -> Group
         conn.execute(
             "INSERT INTO movements_groups (mov_id, group_id) VALUES (?1, ?2)",
             params![mov_id, group_id],
-        )
-        .unwrap();
+        )?;
     }
 
-    let created_at = conn
-        .query_row(
-            "SELECT created_at FROM groups WHERE id = ?1",
-            params![group_id],
-            |row| row.get::<_, String>(0),
-        )
-        .unwrap();
+    let created_at = conn.query_row(
+        "SELECT created_at FROM groups WHERE id = ?1",
+        params![group_id],
+        |row| row.get::<_, String>(0),
+    )?;
 
     let movements = fetch_movements_for_group(&conn, group_id);
 
-    GroupWithMovements {
+    op.finish();
+
+    Ok(GroupWithMovements {
         id: group_id,
         name: group.name,
         description: group.description,
         created_at,
         movements,
-    }
+    })
 }
 
 #[tauri::command]
@@ -186,54 +192,56 @@ pub fn update_group(
     state: tauri::State<crate::AppState>,
     id: i64,
     group: UpdateGroup,
-) -> GroupWithMovements {
+) -> Result<GroupWithMovements, OrbitError> {
     let conn = state.conn.lock().unwrap();
+    let op = history::begin(&conn, "Editar grupo")?;
 
     conn.execute(
         "UPDATE groups SET name = ?1, description = ?2 WHERE id = ?3",
         params![group.name, group.description, id],
-    )
-    .unwrap();
+    )?;
 
     // Delete + re-insert de relaciones
     conn.execute(
         "DELETE FROM movements_groups WHERE group_id = ?1",
         params![id],
-    )
-    .unwrap();
+    )?;
 
     for mov_id in &group.movement_ids {
         conn.execute(
             "INSERT INTO movements_groups (mov_id, group_id) VALUES (?1, ?2)",
             params![mov_id, id],
-        )
-        .unwrap();
+        )?;
     }
 
-    let created_at = conn
-        .query_row(
-            "SELECT created_at FROM groups WHERE id = ?1",
-            params![id],
-            |row| row.get::<_, String>(0),
-        )
-        .unwrap();
+    let created_at = conn.query_row(
+        "SELECT created_at FROM groups WHERE id = ?1",
+        params![id],
+        |row| row.get::<_, String>(0),
+    )?;
 
     let movements = fetch_movements_for_group(&conn, id);
 
-    GroupWithMovements {
+    op.finish();
+
+    Ok(GroupWithMovements {
         id,
         name: group.name,
         description: group.description,
         created_at,
         movements,
-    }
+    })
 }
 
 #[tauri::command]
-pub fn delete_group(state: tauri::State<crate::AppState>, id: i64) {
+pub fn delete_group(state: tauri::State<crate::AppState>, id: i64) -> Result<(), OrbitError> {
     let conn = state.conn.lock().unwrap();
+    let op = history::begin(&conn, "Eliminar grupo")?;
 
     // movements_groups se limpia automáticamente por ON DELETE CASCADE
-    conn.execute("DELETE FROM groups WHERE id = ?1", params![id])
-        .unwrap();
+    conn.execute("DELETE FROM groups WHERE id = ?1", params![id])?;
+
+    op.finish();
+
+    Ok(())
 }
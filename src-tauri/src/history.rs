@@ -0,0 +1,392 @@
+use rusqlite::{params, Connection};
+use serde::Serialize;
+
+use crate::{
+    attachments,
+    errors::OrbitError,
+    utils::{json_row_expr, table_columns},
+};
+
+/// Tables whose changes are recorded and can be undone.
+const JOURNALED_TABLES: &[&str] = &[
+    "accounts",
+    "balance_snapshots",
+    "categories",
+    "movements",
+    "movement_splits",
+    "groups",
+    "movements_groups",
+    "tags",
+    "movements_tags",
+    "scheduled_movements",
+    "subscriptions",
+    "subscription_prices",
+    "goals",
+    "goal_contributions",
+    "cpi_index",
+    "exchange_rates",
+    "items",
+    "stores",
+    "store_aliases",
+    "purchases",
+    "attachment_links",
+    "shopping_lists",
+    "shopping_list_items",
+];
+
+/// Maximum number of operations kept in the history. Older ones are discarded.
+const MAX_HISTORY: i64 = 100;
+
+/// A data-changing operation that can be undone or redone.
+#[derive(Debug, Clone, Serialize)]
+pub struct HistoryEntry {
+    pub id: i64,
+    /// Human readable description, e.g. "Eliminar movimiento"
+    pub label: String,
+    /// "done" (can be undone) or "undone" (can be redone)
+    pub status: String,
+    pub created_at: String,
+}
+
+/// An open operation: every change made while it's alive is recorded under it.
+///
+/// Created with [`begin`] at the start of a mutating command, which opens a
+/// savepoint. [`Operation::finish`] keeps the changes (an empty operation is
+/// discarded); dropping it without finishing, e.g. when the command returns
+/// early with an error, rolls back everything written since [`begin`].
+pub struct Operation<'c> {
+    conn: &'c Connection,
+    id: i64,
+    finished: bool,
+}
+
+impl Operation<'_> {
+    /// Confirma la operación. Se llama al final del camino exitoso del
+    /// comando, antes del `commit()` si la operación se abrió sobre una
+    /// transacción; sin esto los cambios se descartan.
+    pub fn finish(mut self) {
+        self.finished = true;
+    }
+
+    fn close(&self) -> rusqlite::Result<()> {
+        self.conn.execute(
+            "UPDATE journal_state SET current_op = NULL WHERE id = 1",
+            [],
+        )?;
+
+        let has_changes: bool = self.conn.query_row(
+            "SELECT EXISTS (SELECT 1 FROM journal_changes WHERE op_id = ?1)",
+            params![self.id],
+            |row| row.get(0),
+        )?;
+
+        if !has_changes {
+            self.conn
+                .execute("DELETE FROM journal_ops WHERE id = ?1", params![self.id])?;
+            return Ok(());
+        }
+
+        // Una operación nueva invalida todo lo que se podía rehacer
+        self.conn.execute(
+            "DELETE FROM journal_ops WHERE status = 'undone' AND id <> ?1",
+            params![self.id],
+        )?;
+
+        // Historial acotado: descartamos lo más viejo
+        self.conn.execute(
+            "DELETE FROM journal_ops WHERE id NOT IN
+                 (SELECT id FROM journal_ops ORDER BY id DESC LIMIT ?1)",
+            params![MAX_HISTORY],
+        )?;
+
+        Ok(())
+    }
+}
+
+impl Drop for Operation<'_> {
+    fn drop(&mut self) {
+        let result = if self.finished {
+            self.close()
+                .and_then(|_| self.conn.execute_batch("RELEASE history_op"))
+        } else {
+            // El comando falló a mitad de camino: no quedan escrituras
+            // parciales ni una operación deshacible a medias
+            self.conn
+                .execute_batch("ROLLBACK TO history_op; RELEASE history_op")
+        };
+
+        if let Err(e) = result {
+            println!("Error al cerrar la operación {}: {:?}", self.id, e);
+        }
+    }
+}
+
+/// Abre una operación deshacible con la descripción indicada.
+pub fn begin<'c>(conn: &'c Connection, label: &str) -> Result<Operation<'c>, OrbitError> {
+    conn.execute_batch("SAVEPOINT history_op")?;
+
+    // Desde acá el Drop deshace el savepoint si algo falla
+    let mut op = Operation {
+        conn,
+        id: 0,
+        finished: false,
+    };
+
+    conn.execute(
+        "INSERT INTO journal_ops (label) VALUES (?1)",
+        params![label],
+    )?;
+    op.id = conn.last_insert_rowid();
+
+    conn.execute(
+        "UPDATE journal_state SET current_op = ?1 WHERE id = 1",
+        params![op.id],
+    )?;
+
+    Ok(op)
+}
+
+/// Crea los triggers que registran los cambios de cada tabla en journal_changes.
+///
+/// Se generan a partir de `PRAGMA table_info` para que no haya que mantener a
+/// mano la lista de columnas cada vez que cambia el esquema.
+pub fn install_triggers(conn: &Connection) -> Result<(), OrbitError> {
+    for table in JOURNALED_TABLES {
+        let columns = table_columns(conn, table)?;
+
+        let row_json = |alias: &str| json_row_expr(&columns, alias);
+
+        let triggers = [
+            (
+                "insert",
+                "INSERT",
+                "NEW.id",
+                "NULL".to_string(),
+                row_json("NEW"),
+            ),
+            (
+                "update",
+                "UPDATE",
+                "NEW.id",
+                row_json("OLD"),
+                row_json("NEW"),
+            ),
+            (
+                "delete",
+                "DELETE",
+                "OLD.id",
+                row_json("OLD"),
+                "NULL".to_string(),
+            ),
+        ];
+
+        for (suffix, event, row_id, old_row, new_row) in triggers {
+            conn.execute_batch(&format!(
+                "CREATE TRIGGER IF NOT EXISTS trg_journal_{table}_{suffix}
+                 AFTER {event} ON {table}
+                 WHEN (SELECT current_op FROM journal_state WHERE id = 1) IS NOT NULL
+                 BEGIN
+                     INSERT INTO journal_changes (op_id, table_name, row_id, old_row, new_row)
+                     VALUES ((SELECT current_op FROM journal_state WHERE id = 1),
+                             '{table}', {row_id}, {old_row}, {new_row});
+                 END;"
+            ))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Un cambio registrado, listo para reaplicarse en cualquier sentido.
+struct Change {
+    table_name: String,
+    row_id: i64,
+    old_row: Option<String>,
+    new_row: Option<String>,
+}
+
+/// Lleva la fila `row_id` de `table` al estado `target` (JSON), o la borra si es `None`.
+fn apply_row_state(
+    conn: &Connection,
+    table: &str,
+    row_id: i64,
+    target: Option<&str>,
+) -> Result<(), OrbitError> {
+    // table_name viene de journal_changes, que solo escriben nuestros triggers;
+    // igual lo validamos contra la whitelist antes de interpolarlo.
+    if !JOURNALED_TABLES.contains(&table) {
+        return Err(OrbitError::ValidationError(format!(
+            "Tabla no registrada en el historial: {table}"
+        )));
+    }
+
+    let Some(json) = target else {
+        conn.execute(
+            &format!("DELETE FROM {table} WHERE id = ?1"),
+            params![row_id],
+        )?;
+        return Ok(());
+    };
+
+    let columns = table_columns(conn, table)?;
+    let value = |c: &String| format!("json_extract(?1, '$.\"{c}\"')");
+
+    // UPDATE si la fila existe (un DELETE + INSERT dispararía los ON DELETE
+    // CASCADE y se llevaría puestas las filas hijas), INSERT si no.
+    let exists: bool = conn.query_row(
+        &format!("SELECT EXISTS (SELECT 1 FROM {table} WHERE id = ?1)"),
+        params![row_id],
+        |row| row.get(0),
+    )?;
+
+    if exists {
+        let assignments: Vec<String> = columns
+            .iter()
+            .map(|c| format!("\"{c}\" = {}", value(c)))
+            .collect();
+        conn.execute(
+            &format!(
+                "UPDATE {table} SET {} WHERE id = ?2",
+                assignments.join(", ")
+            ),
+            params![json, row_id],
+        )?;
+    } else {
+        let names: Vec<String> = columns.iter().map(|c| format!("\"{c}\"")).collect();
+        let values: Vec<String> = columns.iter().map(value).collect();
+        conn.execute(
+            &format!(
+                "INSERT INTO {table} ({}) SELECT {}",
+                names.join(", "),
+                values.join(", ")
+            ),
+            params![json],
+        )?;
+    }
+
+    Ok(())
+}
+
+fn fetch_changes(conn: &Connection, op_id: i64) -> Result<Vec<Change>, OrbitError> {
+    let mut stmt = conn.prepare(
+        "SELECT table_name, row_id, old_row, new_row
+         FROM journal_changes
+         WHERE op_id = ?1
+         ORDER BY id ASC",
+    )?;
+
+    let changes = stmt
+        .query_map(params![op_id], |row| {
+            Ok(Change {
+                table_name: row.get(0)?,
+                row_id: row.get(1)?,
+                old_row: row.get(2)?,
+                new_row: row.get(3)?,
+            })
+        })?
+        .collect::<Result<Vec<Change>, rusqlite::Error>>()?;
+
+    Ok(changes)
+}
+
+fn row_to_entry(row: &rusqlite::Row) -> rusqlite::Result<HistoryEntry> {
+    Ok(HistoryEntry {
+        id: row.get(0)?,
+        label: row.get(1)?,
+        status: row.get(2)?,
+        created_at: row.get(3)?,
+    })
+}
+
+/// Deshace (`undo = true`) o rehace la operación más reciente que corresponda.
+fn replay(conn: &mut Connection, undo: bool) -> Result<Option<HistoryEntry>, OrbitError> {
+    let sql = if undo {
+        "SELECT id, label, status, created_at FROM journal_ops
+         WHERE status = 'done' ORDER BY id DESC LIMIT 1"
+    } else {
+        "SELECT id, label, status, created_at FROM journal_ops
+         WHERE status = 'undone' ORDER BY id ASC LIMIT 1"
+    };
+
+    let entry = match conn.query_row(sql, [], row_to_entry) {
+        Ok(entry) => entry,
+        Err(rusqlite::Error::QueryReturnedNoRows) => return Ok(None),
+        Err(e) => return Err(OrbitError::Database(e)),
+    };
+
+    let mut changes = fetch_changes(conn, entry.id)?;
+    if undo {
+        changes.reverse();
+    }
+
+    let tx = conn.transaction()?;
+
+    // Las filas se restauran en orden inverso al original, así que las FK
+    // pueden quedar momentáneamente rotas: se validan recién en el commit.
+    tx.execute_batch("PRAGMA defer_foreign_keys = ON;")?;
+    // Nada de lo que hagamos acá debe registrarse como operación nueva
+    tx.execute(
+        "UPDATE journal_state SET current_op = NULL WHERE id = 1",
+        [],
+    )?;
+
+    for change in &changes {
+        let target = if undo {
+            change.old_row.as_deref()
+        } else {
+            change.new_row.as_deref()
+        };
+        apply_row_state(&tx, &change.table_name, change.row_id, target)?;
+    }
+
+    let status = if undo { "undone" } else { "done" };
+    tx.execute(
+        "UPDATE journal_ops SET status = ?1 WHERE id = ?2",
+        params![status, entry.id],
+    )?;
+
+    tx.commit()?;
+
+    Ok(Some(HistoryEntry {
+        status: status.to_string(),
+        ..entry
+    }))
+}
+
+// ---------------------------------------------------------------------------
+// Commands
+// ---------------------------------------------------------------------------
+
+/// Lista el historial, lo más reciente primero.
+#[tauri::command]
+pub fn get_history(state: tauri::State<crate::AppState>) -> Result<Vec<HistoryEntry>, OrbitError> {
+    let conn = state.conn.lock().unwrap();
+
+    let mut stmt =
+        conn.prepare("SELECT id, label, status, created_at FROM journal_ops ORDER BY id DESC")?;
+
+    let entries = stmt
+        .query_map([], row_to_entry)?
+        .collect::<Result<Vec<HistoryEntry>, rusqlite::Error>>()?;
+
+    Ok(entries)
+}
+
+/// Deshace la última operación. Devuelve `None` si no hay nada para deshacer.
+#[tauri::command]
+pub fn undo(state: tauri::State<crate::AppState>) -> Result<Option<HistoryEntry>, OrbitError> {
+    let mut conn = state.conn.lock().unwrap();
+    let entry = replay(&mut conn, true)?;
+    // Deshacer un alta borra filas: sus adjuntos pueden quedar huérfanos
+    attachments::remove_orphans(&conn, &state.attachments_dir)?;
+    Ok(entry)
+}
+
+/// Rehace la última operación deshecha. Devuelve `None` si no hay nada para rehacer.
+#[tauri::command]
+pub fn redo(state: tauri::State<crate::AppState>) -> Result<Option<HistoryEntry>, OrbitError> {
+    let mut conn = state.conn.lock().unwrap();
+    let entry = replay(&mut conn, false)?;
+    attachments::remove_orphans(&conn, &state.attachments_dir)?;
+    Ok(entry)
+}
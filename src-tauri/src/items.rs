@@ -1,39 +1,96 @@
-use rusqlite::params;
+use rusqlite::{
+    params,
+    types::{FromSql, FromSqlError, FromSqlResult, ToSqlOutput, ValueRef},
+    ToSql,
+};
 use serde::{Deserialize, Serialize};
 
-use crate::errors::OrbitError;
+use crate::{errors::OrbitError, history};
+
+/// Unit an item is measured in. Purchases record how many of these units
+/// each package contains.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ItemUnit {
+    #[default]
+    Unit,
+    Kg,
+    G,
+    L,
+    Ml,
+}
+
+impl ToSql for ItemUnit {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        let s: &str = match self {
+            ItemUnit::Unit => "unit",
+            ItemUnit::Kg => "kg",
+            ItemUnit::G => "g",
+            ItemUnit::L => "l",
+            ItemUnit::Ml => "ml",
+        };
+        Ok(ToSqlOutput::from(s))
+    }
+}
+
+impl FromSql for ItemUnit {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        value.as_str().and_then(|s| match s {
+            "unit" => Ok(ItemUnit::Unit),
+            "kg" => Ok(ItemUnit::Kg),
+            "g" => Ok(ItemUnit::G),
+            "l" => Ok(ItemUnit::L),
+            "ml" => Ok(ItemUnit::Ml),
+            other => Err(FromSqlError::Other(
+                format!("unit inválida en la base de datos: {other:?}").into(),
+            )),
+        })
+    }
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Item {
     pub id: i64,
     pub name: String,
     pub brand: Option<String>,
+    pub unit: ItemUnit,
+    /// EAN/UPC code, digits only
+    pub barcode: Option<String>,
+    pub is_archived: bool,
     pub created_at: String,
     pub purchase_count: i64,
     pub last_purchased_at: Option<String>, // should be a string like "2 days ago" | "4 weeks ago"
     pub last_price_registered: Option<i64>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Store {
-    pub id: i64,
-    pub name: String,
-    pub color: Option<String>,
-    pub created_at: String,
-    pub total_purchases: i64,
-    pub total_expenses: i64,
-}
-
 #[derive(Debug, Deserialize)]
 pub struct AddItem {
     pub name: String,
     pub brand: Option<String>,
+    #[serde(default)]
+    pub unit: ItemUnit,
+    #[serde(default)]
+    pub barcode: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct UpdateItem {
     pub name: String,
     pub brand: Option<String>,
+    #[serde(default)]
+    pub unit: ItemUnit,
+    #[serde(default)]
+    pub barcode: Option<String>,
+}
+
+/// Which items `get_items` returns.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ArchivedFilter {
+    #[default]
+    Active,
+    Archived,
+    All,
 }
 
 fn row_to_item(row: &rusqlite::Row) -> rusqlite::Result<Item> {
@@ -45,18 +102,15 @@ fn row_to_item(row: &rusqlite::Row) -> rusqlite::Result<Item> {
         purchase_count: row.get(4)?,
         last_purchased_at: row.get(5)?,
         last_price_registered: row.get(6)?,
+        unit: row.get(7)?,
+        barcode: row.get(8)?,
+        is_archived: row.get(9)?,
     })
 }
 
-// ---------------------------------------------------------------------------
-// Item commands
-// ---------------------------------------------------------------------------
-
-#[tauri::command]
-pub fn get_items(state: tauri::State<crate::AppState>) -> Result<Vec<Item>, OrbitError> {
-    let conn = state.conn.lock().unwrap();
-    let mut stmt = conn.prepare(
-        "SELECT
+/// SELECT de ítems con sus estadísticas de compra. Se completa con WHERE y
+/// `ITEM_GROUP_BY`.
+const ITEM_SELECT: &str = "SELECT
             i.id,
             i.name,
             i.brand,
@@ -76,7 +130,10 @@ pub fn get_items(state: tauri::State<crate::AppState>) -> Result<Vec<Item>, Orbi
                     END
                 ELSE NULL
             END AS last_purchased_at,
-            last_mov.price AS last_price_registered
+            last_mov.price AS last_price_registered,
+            i.unit,
+            i.barcode,
+            i.is_archived
         FROM items i
         LEFT JOIN purchases p ON p.item_id = i.id
         LEFT JOIN (
@@ -96,10 +153,106 @@ pub fn get_items(state: tauri::State<crate::AppState>) -> Result<Vec<Item>, Orbi
                 WHERE p4.item_id = p2.item_id
                 AND m4.date = m2.date
             )
-        ) last_mov ON last_mov.item_id = i.id
-        GROUP BY i.id, i.name, i.brand, i.created_at
-        ORDER BY i.name ASC"
-    )?;
+        ) last_mov ON last_mov.item_id = i.id";
+
+const ITEM_GROUP_BY: &str = "GROUP BY i.id, i.name, i.brand, i.created_at";
+
+fn fetch_item(conn: &rusqlite::Connection, id: i64) -> Result<Item, OrbitError> {
+    match conn.query_row(
+        &format!("{ITEM_SELECT} WHERE i.id = ?1 {ITEM_GROUP_BY}"),
+        params![id],
+        row_to_item,
+    ) {
+        Ok(item) => Ok(item),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Err(OrbitError::NotFound(format!(
+            "No se encontró el ítem con ID {}",
+            id
+        ))),
+        Err(e) => Err(OrbitError::Database(e)),
+    }
+}
+
+/// Normaliza un código EAN/UPC: quita espacios y guiones, y valida largo y
+/// dígito verificador (EAN-8, UPC-A, EAN-13 o GTIN-14).
+fn normalize_barcode(barcode: &str) -> Result<String, OrbitError> {
+    let digits: String = barcode
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '-')
+        .collect();
+
+    let invalid = || OrbitError::ValidationError(format!("Código de barras inválido: {barcode:?}"));
+
+    if !matches!(digits.len(), 8 | 12 | 13 | 14) || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return Err(invalid());
+    }
+
+    // Desde la derecha (sin el verificador) los dígitos pesan 3, 1, 3, 1...
+    let values: Vec<u32> = digits.chars().filter_map(|c| c.to_digit(10)).collect();
+    let (check, body) = values.split_last().ok_or_else(invalid)?;
+    let sum: u32 = body
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, d)| if i % 2 == 0 { d * 3 } else { *d })
+        .sum();
+
+    if (10 - sum % 10) % 10 != *check {
+        return Err(invalid());
+    }
+
+    Ok(digits)
+}
+
+/// Normaliza el código (si hay) y verifica que no lo tenga otro ítem.
+fn validate_barcode(
+    conn: &rusqlite::Connection,
+    item_id: Option<i64>,
+    barcode: Option<&str>,
+) -> Result<Option<String>, OrbitError> {
+    let Some(barcode) = barcode.filter(|b| !b.trim().is_empty()) else {
+        return Ok(None);
+    };
+    let barcode = normalize_barcode(barcode)?;
+
+    let taken_by: Option<String> = match conn.query_row(
+        "SELECT name FROM items WHERE barcode = ?1 AND id IS NOT ?2",
+        params![barcode, item_id],
+        |row| row.get(0),
+    ) {
+        Ok(name) => Some(name),
+        Err(rusqlite::Error::QueryReturnedNoRows) => None,
+        Err(e) => return Err(OrbitError::Database(e)),
+    };
+
+    if let Some(name) = taken_by {
+        return Err(OrbitError::ValidationError(format!(
+            "El código {barcode} ya está asignado al ítem {name:?}"
+        )));
+    }
+
+    Ok(Some(barcode))
+}
+
+// ---------------------------------------------------------------------------
+// Item commands
+// ---------------------------------------------------------------------------
+
+/// Ítems del catálogo; por defecto solo los activos (no archivados).
+#[tauri::command]
+pub fn get_items(
+    state: tauri::State<crate::AppState>,
+    archived: Option<ArchivedFilter>,
+) -> Result<Vec<Item>, OrbitError> {
+    let condition = match archived.unwrap_or_default() {
+        ArchivedFilter::Active => "WHERE i.is_archived = 0",
+        ArchivedFilter::Archived => "WHERE i.is_archived = 1",
+        ArchivedFilter::All => "",
+    };
+
+    let conn = state.conn.lock().unwrap();
+    let mut stmt = conn.prepare(&format!(
+        "{ITEM_SELECT} {condition} {ITEM_GROUP_BY} ORDER BY i.name ASC"
+    ))?;
 
     // Eliminamos los .unwrap() encadenados usando la inversión del collect
     let items = stmt
@@ -123,31 +276,17 @@ pub fn add_item(state: tauri::State<crate::AppState>, item: AddItem) -> Result<I
     }
 
     let conn = state.conn.lock().unwrap();
+    let barcode = validate_barcode(&conn, None, item.barcode.as_deref())?;
+    let op = history::begin(&conn, "Agregar ítem")?;
 
     conn.execute(
-        "INSERT INTO items (name, brand) VALUES (?1, ?2)",
-        params![item.name, item.brand],
-    )?;
-
-    let item_id = conn.last_insert_rowid();
-
-    let item = conn.query_row(
-        "SELECT id, name, brand, created_at FROM items WHERE id = ?1",
-        params![item_id],
-        |row| {
-            Ok(Item {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                brand: row.get(2)?,
-                created_at: row.get(3)?,
-                purchase_count: 0,
-                last_purchased_at: None,
-                last_price_registered: None,
-            })
-        },
+        "INSERT INTO items (name, brand, unit, barcode) VALUES (?1, ?2, ?3, ?4)",
+        params![item.name, item.brand, item.unit, barcode],
     )?;
 
-    Ok(item)
+    let new_id = conn.last_insert_rowid();
+    op.finish();
+    fetch_item(&conn, new_id)
 }
 
 #[tauri::command]
@@ -164,9 +303,29 @@ pub fn update_item(
 
     let conn = state.conn.lock().unwrap();
 
+    // El package_size de cada compra está expresado en la unidad del ítem:
+    // cambiarla cambiaría en silencio el significado de los precios por unidad
+    let current = fetch_item(&conn, id)?;
+    if current.unit != item.unit {
+        let has_purchases: bool = conn.query_row(
+            "SELECT EXISTS (SELECT 1 FROM purchases WHERE item_id = ?1)",
+            params![id],
+            |row| row.get(0),
+        )?;
+        if has_purchases {
+            return Err(OrbitError::ValidationError(format!(
+                "No se puede cambiar la unidad de {:?}: ya tiene compras registradas",
+                current.name
+            )));
+        }
+    }
+
+    let barcode = validate_barcode(&conn, Some(id), item.barcode.as_deref())?;
+    let op = history::begin(&conn, "Editar ítem")?;
+
     let rows_affected = conn.execute(
-        "UPDATE items SET name = ?1, brand = ?2 WHERE id = ?3",
-        params![item.name, item.brand, id],
+        "UPDATE items SET name = ?1, brand = ?2, unit = ?3, barcode = ?4 WHERE id = ?5",
+        params![item.name, item.brand, item.unit, barcode, id],
     )?;
 
     // Si afectó 0 filas significa que el ID enviado desde el frontend no existe
@@ -177,60 +336,15 @@ pub fn update_item(
         )));
     }
 
-    let updated_item = conn.query_row(
-        "SELECT
-            i.id,
-            i.name,
-            i.brand,
-            i.created_at,
-            COUNT(p.id) AS purchase_count,
-            CASE
-                WHEN last_mov.date IS NOT NULL THEN
-                    CASE
-                        WHEN CAST(julianday('now') - julianday(last_mov.date) AS INTEGER) < 1 THEN 'today'
-                        WHEN CAST(julianday('now') - julianday(last_mov.date) AS INTEGER) < 2 THEN '1 day ago'
-                        WHEN CAST(julianday('now') - julianday(last_mov.date) AS INTEGER) < 7 THEN
-                            CAST(CAST(julianday('now') - julianday(last_mov.date) AS INTEGER) AS TEXT) || ' days ago'
-                        WHEN CAST(julianday('now') - julianday(last_mov.date) AS INTEGER) < 30 THEN
-                            CAST(CAST((julianday('now') - julianday(last_mov.date)) / 7 AS INTEGER) AS TEXT) || ' weeks ago'
-                        ELSE
-                            CAST(CAST((julianday('now') - julianday(last_mov.date)) / 30 AS INTEGER) AS TEXT) || ' months ago'
-                    END
-                ELSE NULL
-            END AS last_purchased_at,
-            last_mov.price AS last_price_registered
-        FROM items i
-        LEFT JOIN purchases p ON p.item_id = i.id
-        LEFT JOIN (
-            SELECT p2.item_id, p2.price, m2.date
-            FROM purchases p2
-            JOIN movements m2 ON m2.id = p2.mov_id
-            WHERE m2.date = (
-                SELECT MAX(m3.date)
-                FROM purchases p3
-                JOIN movements m3 ON m3.id = p3.mov_id
-                WHERE p3.item_id = p2.item_id
-            )
-            AND p2.id = (
-                SELECT MAX(p4.id)
-                FROM purchases p4
-                JOIN movements m4 ON m4.id = p4.mov_id
-                WHERE p4.item_id = p2.item_id
-                AND m4.date = m2.date
-            )
-        ) last_mov ON last_mov.item_id = i.id
-        WHERE i.id = ?1
-        GROUP BY i.id, i.name, i.brand, i.created_at",
-        params![id],
-        |row| row_to_item(row),
-    )?;
+    op.finish();
 
-    Ok(updated_item)
+    fetch_item(&conn, id)
 }
 
 #[tauri::command]
 pub fn delete_item(state: tauri::State<crate::AppState>, id: i64) -> Result<(), OrbitError> {
     let conn = state.conn.lock().unwrap();
+    let op = history::begin(&conn, "Archivar ítem")?;
 
     // Hacemos un borrado lógico (Soft Delete)
     let rows_affected = conn.execute(
@@ -245,39 +359,143 @@ pub fn delete_item(state: tauri::State<crate::AppState>, id: i64) -> Result<(),
         )));
     }
 
+    op.finish();
+
     Ok(())
 }
 
+/// Vuelve a activar un ítem archivado.
 #[tauri::command]
-pub fn get_stores(state: tauri::State<crate::AppState>) -> Result<Vec<Store>, OrbitError> {
+pub fn unarchive_item(state: tauri::State<crate::AppState>, id: i64) -> Result<Item, OrbitError> {
     let conn = state.conn.lock().unwrap();
-    let mut stmt = conn.prepare(
-        "
-        SELECT
-            s.id,
-            s.name,
-            s.color,
-            s.created_at,
-            COUNT(DISTINCT p.mov_id) AS total_purchases,
-            COALESCE(SUM(p.price * p.quantity), 0) AS total_expenses
-        FROM stores s
-        LEFT JOIN purchases p ON p.store_id = s.id
-        GROUP BY s.id
-        ORDER BY s.name ASC",
+    let op = history::begin(&conn, "Desarchivar ítem")?;
+
+    let rows_affected = conn.execute(
+        "UPDATE items SET is_archived = 0 WHERE id = ?1 AND is_archived = 1",
+        params![id],
     )?;
 
-    let stores = stmt
-        .query_map([], |row| {
-            Ok(Store {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                color: row.get(2)?,
-                created_at: row.get(3)?,
-                total_purchases: row.get(4)?,
-                total_expenses: row.get(5)?,
-            })
-        })?
-        .collect::<Result<Vec<Store>, rusqlite::Error>>()?;
-
-    Ok(stores)
+    if rows_affected == 0 {
+        return Err(OrbitError::NotFound(format!(
+            "No se encontró el ítem archivado con ID {}",
+            id
+        )));
+    }
+
+    op.finish();
+
+    fetch_item(&conn, id)
+}
+
+/// Fusiona ítems repetidos en `keep_id`: sus compras y lugares en listas de
+/// compras pasan a ese ítem, y después se eliminan.
+///
+/// Todos deben tener la misma unidad, porque el tamaño de paquete de cada
+/// compra está expresado en la unidad del ítem.
+#[tauri::command]
+pub fn merge_items(
+    state: tauri::State<crate::AppState>,
+    keep_id: i64,
+    merge_ids: Vec<i64>,
+) -> Result<Item, OrbitError> {
+    if merge_ids.is_empty() {
+        return Err(OrbitError::ValidationError(
+            "Debe indicar al menos un ítem a fusionar".into(),
+        ));
+    }
+    if merge_ids.contains(&keep_id) {
+        return Err(OrbitError::ValidationError(
+            "El ítem a conservar no puede estar entre los fusionados".into(),
+        ));
+    }
+
+    let mut conn = state.conn.lock().unwrap();
+
+    let keep = fetch_item(&conn, keep_id)?;
+    let mut barcode = keep.barcode;
+    for id in &merge_ids {
+        let merged = fetch_item(&conn, *id)?;
+        if merged.unit != keep.unit {
+            return Err(OrbitError::ValidationError(format!(
+                "No se puede fusionar {:?} con {:?}: tienen unidades distintas",
+                merged.name, keep.name
+            )));
+        }
+        // Si el conservado no tiene código, hereda el del primero que tenga
+        barcode = barcode.or(merged.barcode);
+    }
+
+    let tx = conn.transaction()?;
+    let op = history::begin(&tx, "Fusionar ítems")?;
+
+    for id in &merge_ids {
+        tx.execute(
+            "UPDATE purchases SET item_id = ?1 WHERE item_id = ?2",
+            params![keep_id, id],
+        )?;
+        // Si ambos estaban en la misma lista, se suman las cantidades
+        tx.execute(
+            "INSERT INTO shopping_list_items (list_id, item_id, quantity, checked)
+             SELECT list_id, ?1, quantity, checked FROM shopping_list_items WHERE item_id = ?2
+             ON CONFLICT (list_id, item_id) DO UPDATE SET quantity = quantity + excluded.quantity",
+            params![keep_id, id],
+        )?;
+        // Las filas de listas restantes caen por ON DELETE CASCADE
+        tx.execute("DELETE FROM items WHERE id = ?1", params![id])?;
+    }
+
+    tx.execute(
+        "UPDATE items SET barcode = ?1 WHERE id = ?2",
+        params![barcode, keep_id],
+    )?;
+
+    op.finish();
+    tx.commit()?;
+
+    fetch_item(&conn, keep_id)
+}
+
+/// Busca un ítem (activo o archivado) por su código de barras, para cargar
+/// compras escaneando el producto.
+#[tauri::command]
+pub fn get_item_by_barcode(
+    state: tauri::State<crate::AppState>,
+    barcode: String,
+) -> Result<Option<Item>, OrbitError> {
+    let barcode = normalize_barcode(&barcode)?;
+    let conn = state.conn.lock().unwrap();
+
+    match conn.query_row(
+        &format!("{ITEM_SELECT} WHERE i.barcode = ?1 {ITEM_GROUP_BY}"),
+        params![barcode],
+        row_to_item,
+    ) {
+        Ok(item) => Ok(Some(item)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(OrbitError::Database(e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_barcode_validates_check_digit() {
+        assert_eq!(
+            normalize_barcode("0 36000-29145 2").ok().as_deref(),
+            Some("036000291452")
+        );
+        assert_eq!(
+            normalize_barcode("4006381333931").ok().as_deref(),
+            Some("4006381333931")
+        );
+        assert_eq!(
+            normalize_barcode("96385074").ok().as_deref(),
+            Some("96385074")
+        );
+        assert!(normalize_barcode("4006381333932").is_err());
+        assert!(normalize_barcode("12345").is_err());
+        assert!(normalize_barcode("40063813339AB").is_err());
+    }
 }
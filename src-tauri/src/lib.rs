@@ -1,19 +1,53 @@
 use rusqlite::Connection;
+use std::collections::HashSet;
+use std::path::PathBuf;
 use std::sync::Mutex;
 
 use tauri::Manager;
 
 pub mod accounts;
+pub mod anomalies;
+pub mod attachments;
+pub mod audit;
+pub mod bulk;
 pub mod categories;
+pub mod cpi;
+pub mod db;
+pub mod duplicates;
 pub mod errors;
+pub mod forecast;
+pub mod goals;
 pub mod groups;
+pub mod history;
 pub mod items;
 pub mod movements;
+pub mod prices;
 pub mod purchases;
+pub mod rates;
+pub mod receipts;
+pub mod reports;
+pub mod search;
+pub mod shopping;
+pub mod splits;
+pub mod stores;
+pub mod subscriptions;
+pub mod tags;
 pub mod utils;
 
 pub struct AppState {
     conn: Mutex<Connection>,
+    /// Último COUNT(*) de get_movements, ver movements::cached_movement_count.
+    movement_count_cache: Mutex<Option<CountCache>>,
+    /// Directorio donde se guardan los adjuntos, ver attachments.rs.
+    attachments_dir: PathBuf,
+    /// Claves de las anomalías ya notificadas, ver anomalies::notify_new_insights.
+    notified_insights: Mutex<HashSet<String>>,
+}
+
+/// Resultado de un conteo cacheado junto con la clave que lo invalida.
+pub(crate) struct CountCache {
+    key: String,
+    count: i64,
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -25,10 +59,15 @@ pub fn run() {
             accounts::add_account,
             accounts::delete_account,
             accounts::update_account,
+            anomalies::get_insights,
             categories::get_categories,
             categories::add_category,
             categories::delete_category,
             categories::update_category,
+            cpi::get_cpi,
+            cpi::set_cpi_value,
+            cpi::delete_cpi_value,
+            cpi::import_cpi_csv,
             movements::get_movements,
             movements::get_movements_stats,
             movements::get_movements_by_account_id,
@@ -36,46 +75,133 @@ pub fn run() {
             movements::update_movement,
             movements::delete_movement,
             movements::items_by_movement,
+            attachments::add_attachment,
+            attachments::get_attachments,
+            attachments::open_attachment,
+            attachments::remove_attachment,
+            bulk::bulk_update_movements,
+            duplicates::find_duplicate_movements,
+            duplicates::merge_movements,
             groups::get_groups,
             groups::add_group,
             groups::delete_group,
             groups::update_group,
+            forecast::get_scheduled_movements,
+            forecast::add_scheduled_movement,
+            forecast::delete_scheduled_movement,
+            forecast::get_cash_flow_forecast,
+            goals::get_goals,
+            goals::add_goal,
+            goals::update_goal,
+            goals::delete_goal,
+            goals::link_goal_contribution,
+            goals::unlink_goal_contribution,
+            goals::add_goal_contribution,
+            goals::get_goal_progress,
             items::get_items,
             items::add_item,
             items::delete_item,
+            items::unarchive_item,
+            items::merge_items,
+            items::get_item_by_barcode,
             items::update_item,
-            items::get_stores,
+            shopping::get_shopping_lists,
+            shopping::get_shopping_list,
+            shopping::add_shopping_list,
+            shopping::rename_shopping_list,
+            shopping::delete_shopping_list,
+            shopping::add_shopping_list_item,
+            shopping::update_shopping_list_item,
+            shopping::remove_shopping_list_item,
+            shopping::get_shopping_suggestions,
+            shopping::complete_shopping_list,
+            stores::get_stores,
+            stores::add_store,
+            stores::update_store,
+            stores::delete_store,
+            stores::merge_stores,
+            prices::get_item_price_history,
+            prices::get_personal_inflation,
+            prices::compare_stores,
             purchases::get_purchases,
             purchases::purchases_by_item,
             purchases::add_purchase,
+            purchases::update_purchase,
+            purchases::delete_purchase,
+            purchases::set_movement_purchases,
+            rates::get_exchange_rates,
+            rates::set_exchange_rate,
+            rates::delete_exchange_rate,
+            receipts::parse_receipt,
+            receipts::parse_receipt_pdf,
+            receipts::confirm_receipt,
+            reports::get_monthly_report,
+            reports::get_net_worth,
+            search::search,
+            splits::get_movement_splits,
+            splits::set_movement_splits,
+            subscriptions::get_subscriptions,
+            subscriptions::get_subscription_candidates,
+            subscriptions::add_subscription,
+            subscriptions::update_subscription,
+            subscriptions::delete_subscription,
+            subscriptions::get_subscription_summary,
+            tags::get_tags,
+            tags::tags_by_movement,
+            tags::add_tags_to_movements,
+            tags::remove_tags_from_movements,
+            tags::rename_tag,
+            tags::delete_tag,
+            history::get_history,
+            history::undo,
+            history::redo,
+            audit::get_audit_history,
+            audit::get_audit_feed,
         ])
         .setup(|app| {
-            // let db_path = app.path().app_data_dir()?.join("app.db");
+            let data_dir = app.path().app_data_dir()?;
+            std::fs::create_dir_all(&data_dir)?;
 
-            let conn = Connection::open_in_memory()?;
+            // La base vive en disco para que el historial de undo/redo
+            // (journal_ops) sobreviva entre ejecuciones.
+            let mut conn = Connection::open(data_dir.join("app.db"))?;
+            conn.execute_batch("PRAGMA foreign_keys = ON;")?;
 
-            // conn.execute_batch("PRAGMA foreign_keys = ON;")?;
+            let version = db::migrate(&mut conn)?;
 
-            // MIGRATIONS.to_latest(&mut conn).unwrap();
+            // WARNING: this is only used in development to populate with initial data. Do not use in production.
+            // populate with initial data, before the triggers so it isn't journaled or audited
+            #[cfg(dev)]
+            if version.is_new() {
+                let data = include_str!("../data.sql");
+                match conn.execute_batch(data) {
+                    Ok(_) => println!("Datos iniciales cargados correctamente"),
+                    Err(e) => println!("Error al cargar datos iniciales: {:?}", e),
+                }
+            }
 
-            let schema = include_str!("../schema.sql");
+            // triggers del historial de undo/redo y de auditoría (dependen de las tablas ya creadas)
+            db::install_triggers(&conn, version)?;
 
-            // initialize database with schema.sql
-            match conn.execute_batch(schema) {
-                Ok(_) => println!("Base de datos inicializada correctamente"),
-                Err(e) => println!("Error al inicializar la base de datos: {:?}", e),
-            }
+            // Si la app se cerró con una operación abierta, no hay que seguir
+            // registrando cambios bajo ella
+            conn.execute(
+                "UPDATE journal_state SET current_op = NULL WHERE id = 1",
+                [],
+            )?;
 
-            // WARNING: this is only used in development to populate with initial data. Do not use in production.
-            // populate with initial data
-            let data = include_str!("../data.sql");
-            match conn.execute_batch(data) {
-                Ok(_) => println!("Datos iniciales cargados correctamente"),
-                Err(e) => println!("Error al cargar datos iniciales: {:?}", e),
+            let attachments_dir = data_dir.join("attachments");
+
+            // Archivos cuyas operaciones ya salieron del historial
+            if let Err(e) = attachments::remove_orphans(&conn, &attachments_dir) {
+                println!("Error al limpiar adjuntos huérfanos: {:?}", e);
             }
 
             app.manage(AppState {
                 conn: Mutex::new(conn),
+                movement_count_cache: Mutex::new(None),
+                attachments_dir,
+                notified_insights: Mutex::new(HashSet::new()),
             });
 
             Ok(())
@@ -5,7 +5,15 @@ use rusqlite::{
 };
 use serde::{Deserialize, Serialize};
 
-use crate::errors::OrbitError;
+use crate::{
+    anomalies, attachments,
+    cpi::CpiSeries,
+    errors::OrbitError,
+    history,
+    rates::UsdConversion,
+    search::fts_match_query,
+    utils::{format_date, parse_date, parse_month},
+};
 
 /// Represents a financial movement in the personal finance application.
 ///
@@ -70,6 +78,16 @@ pub enum Currency {
     USD,
 }
 
+impl Currency {
+    /// Representación tal cual se guarda en la columna `currency`.
+    pub fn as_db_str(self) -> &'static str {
+        match self {
+            Currency::ARS => "ARS",
+            Currency::USD => "USD",
+        }
+    }
+}
+
 impl Default for Currency {
     fn default() -> Self {
         Currency::ARS
@@ -87,6 +105,19 @@ pub enum RateType {
     Cripto,
 }
 
+impl RateType {
+    /// Representación tal cual se guarda en la columna `rate_type`.
+    pub fn as_db_str(self) -> &'static str {
+        match self {
+            RateType::Blue => "blue",
+            RateType::Oficial => "oficial",
+            RateType::Mep => "mep",
+            RateType::Ccl => "ccl",
+            RateType::Cripto => "cripto",
+        }
+    }
+}
+
 impl ToSql for Currency {
     fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
         let s: &str = match self {
@@ -265,6 +296,16 @@ impl MovementTypeFilter {
     }
 }
 
+/// `TagMatch = "any" | "all"`
+/// Cómo combinar varias etiquetas en el filtro: alcanza con una o deben estar todas.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum TagMatch {
+    #[default]
+    Any,
+    All,
+}
+
 /// `MovementFilters` — matchea con el objeto `filters` del front.
 #[derive(Debug, Clone, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -276,19 +317,245 @@ pub struct MovementFilters {
     pub sort: Option<SortCriteria>,
 
     pub limit: i64,
+    /// Ignorado en modo cursor.
+    #[serde(default)]
     pub offset: i64,
 
+    /// Cursor opaco devuelto por una llamada anterior (`next_cursor` /
+    /// `prev_cursor`). Si viene, se pagina por keyset en lugar de OFFSET:
+    /// la primera página se pide sin cursor y se sigue con los devueltos.
+    #[serde(default)]
+    pub cursor: Option<String>,
+
     /// `number | null` y además puede venir ausente => doble default.
     #[serde(default)]
     pub category_id: Option<i64>,
     #[serde(default)]
     pub group_id: Option<i64>,
 
+    /// Etiquetas a filtrar; vacío o ausente => sin filtro.
+    #[serde(default)]
+    pub tag_ids: Vec<i64>,
+    #[serde(default)]
+    pub tag_match: TagMatch,
+
     #[serde(default)]
     pub query: Option<String>,
 
     /// Requerido en el front (siempre manda al menos "all").
     pub r#type: MovementTypeFilter,
+
+    /// Rango absoluto de fechas (YYYY-MM-DD, ambos inclusive).
+    /// Se combina con `period` si vienen los dos.
+    #[serde(default)]
+    pub from: Option<String>,
+    #[serde(default)]
+    pub to: Option<String>,
+
+    /// Cuentas a incluir; vacío o ausente => todas.
+    #[serde(default)]
+    pub account_ids: Vec<i64>,
+
+    /// Rango de montos en centavos (ambos inclusive), sobre la moneda
+    /// indicada por `amount_basis`.
+    #[serde(default)]
+    pub min_amount: Option<i64>,
+    #[serde(default)]
+    pub max_amount: Option<i64>,
+    #[serde(default)]
+    pub amount_basis: AmountBasis,
+
+    #[serde(default)]
+    pub currency: Option<Currency>,
+    #[serde(default)]
+    pub rate_type: Option<RateType>,
+
+    /// Solo movimientos sin categoría (ni propia ni en su desglose).
+    #[serde(default)]
+    pub uncategorized_only: bool,
+
+    /// `true` => solo con compras registradas, `false` => solo sin compras.
+    #[serde(default)]
+    pub has_purchases: Option<bool>,
+}
+
+/// `AmountBasis = "original" | "base"`
+/// Sobre qué columna se aplica el rango de montos.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum AmountBasis {
+    /// `original_amount`, en la moneda del movimiento
+    Original,
+    /// `ars_amount`, el monto normalizado usado en reportes
+    #[default]
+    Base,
+}
+
+impl AmountBasis {
+    /// Condiciones (mínimo, máximo) sobre la columna correspondiente (whitelist).
+    fn bounds(self) -> (&'static str, &'static str) {
+        match self {
+            AmountBasis::Original => ("original_amount >= ?", "original_amount <= ?"),
+            AmountBasis::Base => ("ars_amount >= ?", "ars_amount <= ?"),
+        }
+    }
+}
+
+/// Cláusula WHERE parametrizada sobre la tabla `movements`.
+pub(crate) struct MovementWhere {
+    /// Vacía si no hay condiciones; si no, empieza con " WHERE ".
+    pub clause: String,
+    pub params: Vec<Value>,
+}
+
+impl MovementFilters {
+    /// Arma el WHERE compartido por el listado y las estadísticas. No hay
+    /// exportación de movimientos; si se agrega, debería reutilizarlo.
+    ///
+    /// Las condiciones referencian columnas de `movements` sin calificar, así
+    /// que el resultado se puede usar directo en `FROM movements` o dentro de
+    /// un `id IN (SELECT id FROM movements ...)`. Todo valor va como parámetro (?).
+    pub(crate) fn build_where(&self) -> Result<MovementWhere, OrbitError> {
+        let mut conditions: Vec<&str> = Vec::new();
+        let mut params: Vec<Value> = Vec::new();
+
+        // ── type ("all" no filtra) ──
+        if let Some(t) = self.r#type.as_movement_type() {
+            conditions.push("mov_type = ?");
+            params.push(Value::Text(t.as_db_str().to_string()));
+        }
+
+        // ── period (date >= now - intervalo) ──
+        if let Some(period) = self.period {
+            conditions.push("date >= date('now', ?)");
+            params.push(Value::Text(period.sqlite_modifier().to_string()));
+        }
+
+        // ── from / to (rango absoluto) ──
+        if let Some(from) = non_empty(&self.from) {
+            conditions.push("date >= ?");
+            params.push(Value::Text(format_date(parse_date(from)?)));
+        }
+        if let Some(to) = non_empty(&self.to) {
+            conditions.push("date <= ?");
+            params.push(Value::Text(format_date(parse_date(to)?)));
+        }
+
+        // ── account_ids ──
+        if !self.account_ids.is_empty() {
+            conditions.push("account_id IN (SELECT value FROM json_each(?))");
+            params.push(Value::Text(json_id_list(&self.account_ids)));
+        }
+
+        // ── min / max amount ──
+        if let (Some(min), Some(max)) = (self.min_amount, self.max_amount) {
+            if min > max {
+                return Err(OrbitError::ValidationError(
+                    "El monto mínimo no puede ser mayor al máximo".into(),
+                ));
+            }
+        }
+        let (min_condition, max_condition) = self.amount_basis.bounds();
+        if let Some(min) = self.min_amount {
+            conditions.push(min_condition);
+            params.push(Value::Integer(min));
+        }
+        if let Some(max) = self.max_amount {
+            conditions.push(max_condition);
+            params.push(Value::Integer(max));
+        }
+
+        // ── currency / rate_type ──
+        if let Some(currency) = self.currency {
+            conditions.push("currency = ?");
+            params.push(Value::Text(currency.as_db_str().to_string()));
+        }
+        if let Some(rate_type) = self.rate_type {
+            conditions.push("rate_type = ?");
+            params.push(Value::Text(rate_type.as_db_str().to_string()));
+        }
+
+        // ── category_id (propia o en alguna línea de desglose) ──
+        if let Some(cat) = self.category_id {
+            conditions.push(
+                "(category_id = ? OR id IN (SELECT mov_id FROM movement_splits WHERE category_id = ?))",
+            );
+            params.push(Value::Integer(cat));
+            params.push(Value::Integer(cat));
+        }
+
+        // ── uncategorized_only ──
+        if self.uncategorized_only {
+            conditions.push(
+                "(category_id IS NULL AND NOT EXISTS \
+                 (SELECT 1 FROM movement_splits ms WHERE ms.mov_id = movements.id \
+                  AND ms.category_id IS NOT NULL))",
+            );
+        }
+
+        // ── group_id (relación N:M vía movements_groups) ──
+        if let Some(group) = self.group_id {
+            conditions.push("id IN (SELECT mov_id FROM movements_groups WHERE group_id = ?)");
+            params.push(Value::Integer(group));
+        }
+
+        // ── tags (any/all vía movements_tags, ids como array JSON) ──
+        if !self.tag_ids.is_empty() {
+            let tag_ids = json_id_list(&self.tag_ids);
+            match self.tag_match {
+                TagMatch::Any => {
+                    conditions.push(
+                        "id IN (SELECT mov_id FROM movements_tags \
+                         WHERE tag_id IN (SELECT value FROM json_each(?)))",
+                    );
+                    params.push(Value::Text(tag_ids));
+                }
+                TagMatch::All => {
+                    conditions.push(
+                        "id IN (SELECT mov_id FROM movements_tags \
+                         WHERE tag_id IN (SELECT value FROM json_each(?)) \
+                         GROUP BY mov_id HAVING COUNT(DISTINCT tag_id) = \
+                         (SELECT COUNT(DISTINCT value) FROM json_each(?)))",
+                    );
+                    params.push(Value::Text(tag_ids.clone()));
+                    params.push(Value::Text(tag_ids));
+                }
+            }
+        }
+
+        // ── has_purchases ──
+        match self.has_purchases {
+            Some(true) => conditions.push("id IN (SELECT mov_id FROM purchases)"),
+            Some(false) => conditions.push("id NOT IN (SELECT mov_id FROM purchases)"),
+            None => {}
+        }
+
+        // ── query (búsqueda full-text en details y notas, sin acentos) ──
+        if let Some(q) = non_empty(&self.query).and_then(fts_match_query) {
+            conditions.push("id IN (SELECT rowid FROM movements_fts WHERE movements_fts MATCH ?)");
+            params.push(Value::Text(q));
+        }
+
+        // Cláusula WHERE reutilizable (vacía si no hay condiciones).
+        let clause = if conditions.is_empty() {
+            String::new()
+        } else {
+            format!(" WHERE {}", conditions.join(" AND "))
+        };
+
+        Ok(MovementWhere { clause, params })
+    }
+}
+
+/// `Some(texto recortado)` si el campo viene y no está vacío.
+fn non_empty(value: &Option<String>) -> Option<&str> {
+    value.as_deref().map(str::trim).filter(|v| !v.is_empty())
+}
+
+/// Lista de IDs como array JSON, para usar con `json_each(?)`.
+fn json_id_list(ids: &[i64]) -> String {
+    let ids: Vec<String> = ids.iter().map(i64::to_string).collect();
+    format!("[{}]", ids.join(","))
 }
 
 #[derive(Debug, Deserialize)]
@@ -322,6 +589,145 @@ pub struct UpdateMovement {
 pub struct MovementList {
     pub movements: Vec<Movement>,
     pub total: i64,
+    /// Cursor para la página siguiente (`None` si no hay más).
+    pub next_cursor: Option<String>,
+    /// Cursor para la página anterior (`None` si es la primera).
+    pub prev_cursor: Option<String>,
+}
+
+// ───────────────────────── Paginación por keyset ─────────────────────────
+// OFFSET obliga a SQLite a recorrer y descartar todas las filas previas, y
+// si entran movimientos nuevos entre página y página se repiten o saltean
+// filas. Con keyset continuamos desde el último (valor de orden, id) visto.
+
+/// Sentido en el que se pide la página relativa al cursor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum PageDirection {
+    Next,
+    Prev,
+}
+
+/// Contenido del cursor. Se serializa a JSON y luego a hex para que el
+/// front lo trate como un string opaco.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct MovementCursor {
+    /// Orden con el que se generó: un cursor no sirve para otro orden.
+    sort: SortCriteria,
+    /// Valor de la columna de orden en la fila frontera
+    value: serde_json::Value,
+    /// Desempate: id de la fila frontera
+    id: i64,
+    direction: PageDirection,
+}
+
+impl MovementCursor {
+    fn from_movement(sort: SortCriteria, movement: &Movement, direction: PageDirection) -> Self {
+        let value = match sort.field {
+            SortField::Date => serde_json::Value::from(movement.date.clone()),
+            SortField::Details => serde_json::Value::from(movement.details.clone()),
+            SortField::Amount => serde_json::Value::from(movement.ars_amount),
+        };
+
+        MovementCursor {
+            sort,
+            value,
+            id: movement.id,
+            direction,
+        }
+    }
+
+    fn encode(&self) -> String {
+        let json = serde_json::to_string(self).unwrap_or_default();
+        json.bytes().map(|b| format!("{b:02x}")).collect()
+    }
+
+    fn decode(cursor: &str) -> Result<Self, OrbitError> {
+        let invalid = || OrbitError::ValidationError("Cursor de paginación inválido".into());
+
+        if !cursor.len().is_multiple_of(2) {
+            return Err(invalid());
+        }
+
+        let bytes = (0..cursor.len())
+            .step_by(2)
+            .map(|i| {
+                cursor
+                    .get(i..i + 2)
+                    .and_then(|h| u8::from_str_radix(h, 16).ok())
+            })
+            .collect::<Option<Vec<u8>>>()
+            .ok_or_else(invalid)?;
+
+        serde_json::from_slice(&bytes).map_err(|_| invalid())
+    }
+
+    /// Valor de orden como parámetro SQL.
+    fn sql_value(&self) -> Result<Value, OrbitError> {
+        match (&self.value, self.sort.field) {
+            (serde_json::Value::String(s), SortField::Date | SortField::Details) => {
+                Ok(Value::Text(s.clone()))
+            }
+            (serde_json::Value::Number(n), SortField::Amount) => n
+                .as_i64()
+                .map(Value::Integer)
+                .ok_or_else(|| OrbitError::ValidationError("Cursor de paginación inválido".into())),
+            _ => Err(OrbitError::ValidationError(
+                "Cursor de paginación inválido".into(),
+            )),
+        }
+    }
+}
+
+/// Total de movimientos para un WHERE dado, cacheado hasta la próxima escritura.
+///
+/// La clave incluye `total_changes()` de la conexión, que aumenta con cada
+/// INSERT/UPDATE/DELETE: cualquier cambio invalida el cache sin tener que
+/// tocar los comandos que escriben.
+fn cached_movement_count(
+    conn: &rusqlite::Connection,
+    cache: &std::sync::Mutex<Option<crate::CountCache>>,
+    where_clause: &str,
+    params: &[Value],
+) -> Result<i64, OrbitError> {
+    let key = format!("{where_clause}|{params:?}|{}", conn.total_changes());
+
+    let mut cache = cache.lock().unwrap();
+    if let Some(entry) = cache.as_ref().filter(|c| c.key == key) {
+        return Ok(entry.count);
+    }
+
+    let count_sql = format!("SELECT COUNT(*) FROM movements{where_clause}");
+    let count: i64 = conn.query_row(&count_sql, params_from_iter(params.iter()), |row| {
+        row.get(0)
+    })?;
+
+    *cache = Some(crate::CountCache { key, count });
+
+    Ok(count)
+}
+
+/// SELECT base de get_movements; el WHERE se agrega a continuación.
+pub(crate) const MOVEMENT_SELECT: &str =
+    "SELECT id, details, date, created_at, mov_type, currency, \
+     original_amount, ars_amount, exchange_rate, rate_type, account_id, category_id \
+     FROM movements";
+
+pub(crate) fn row_to_movement(row: &rusqlite::Row) -> rusqlite::Result<Movement> {
+    Ok(Movement {
+        id: row.get(0)?,
+        details: row.get(1)?,
+        date: row.get(2)?,
+        created_at: row.get(3)?,
+        mov_type: row.get(4)?, // usa FromSql<MovementType>
+        currency: row.get(5)?, // usa FromSql<Currency>
+        original_amount: row.get(6)?,
+        ars_amount: row.get(7)?,
+        exchange_rate: row.get(8)?,
+        rate_type: row.get(9)?, // Option<RateType>, maneja NULL
+        account_id: row.get(10)?,
+        category_id: row.get(11)?,
+    })
 }
 
 #[tauri::command]
@@ -333,109 +739,190 @@ pub fn get_movements(
 
     // Construimos SOLO el WHERE primero: lo compartimos entre la query
     // de conteo y la de datos. Todo valor va como parámetro (?).
-    let mut conditions: Vec<&str> = Vec::new();
-    let mut params: Vec<Value> = Vec::new();
+    let MovementWhere {
+        clause: where_clause,
+        mut params,
+    } = filters.build_where().map_err(|e| e.to_string())?;
 
-    // ── type ("all" no filtra) ──
-    if let Some(t) = filters.r#type.as_movement_type() {
-        conditions.push("mov_type = ?");
-        params.push(Value::Text(t.as_db_str().to_string()));
-    }
+    // ── 1) TOTAL: cuenta TODO lo filtrado, sin LIMIT/OFFSET ──
+    // Se cachea: con muchos movimientos el COUNT(*) en cada página pesa.
+    let total = cached_movement_count(&conn, &state.movement_count_cache, &where_clause, &params)
+        .map_err(|e| e.to_string())?;
 
-    // ── period (date >= now - intervalo) ──
-    if let Some(period) = filters.period {
-        conditions.push("date >= date('now', ?)");
-        params.push(Value::Text(period.sqlite_modifier().to_string()));
-    }
+    let sort = filters.sort.unwrap_or_default();
 
-    // ── category_id ──
-    if let Some(cat) = filters.category_id {
-        conditions.push("category_id = ?");
-        params.push(Value::Integer(cat));
-    }
+    // ── 2) DATOS: modo cursor (keyset) o modo clásico LIMIT/OFFSET ──
+    let cursor = filters
+        .cursor
+        .as_deref()
+        .filter(|c| !c.is_empty())
+        .map(MovementCursor::decode)
+        .transpose()
+        .map_err(|e| e.to_string())?;
+
+    let Some(cursor) = cursor else {
+        let mut sql = format!("{MOVEMENT_SELECT}{where_clause}");
+
+        // ── ORDER BY (whitelist, seguro) + desempate estable por id ──
+        sql.push_str(&format!(
+            " ORDER BY {} {}, id {}",
+            sort.field.column(),
+            sort.order.keyword(),
+            sort.order.keyword(),
+        ));
+
+        // ── LIMIT / OFFSET (agregamos al final, solo para esta query) ──
+        sql.push_str(" LIMIT ? OFFSET ?");
+        params.push(Value::Integer(filters.limit));
+        params.push(Value::Integer(filters.offset));
+
+        let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+
+        let movements = stmt
+            .query_map(params_from_iter(params), row_to_movement)
+            .map_err(|e| e.to_string())?
+            .collect::<Result<Vec<Movement>, _>>()
+            .map_err(|e| e.to_string())?;
+
+        #[cfg(dev)]
+        {
+            println!("retrieving {} of {} movements", movements.len(), total);
+        }
 
-    // ── group_id (relación N:M vía movements_groups) ──
-    if let Some(group) = filters.group_id {
-        conditions.push("id IN (SELECT mov_id FROM movements_groups WHERE group_id = ?)");
-        params.push(Value::Integer(group));
+        // También devolvemos cursores: el front puede pedir la primera página
+        // con OFFSET 0 y seguir por keyset desde ahí.
+        let next_cursor = movements
+            .last()
+            .filter(|_| filters.offset + (movements.len() as i64) < total)
+            .map(|m| MovementCursor::from_movement(sort, m, PageDirection::Next).encode());
+        let prev_cursor = movements
+            .first()
+            .filter(|_| filters.offset > 0)
+            .map(|m| MovementCursor::from_movement(sort, m, PageDirection::Prev).encode());
+
+        return Ok(MovementList {
+            movements,
+            total,
+            next_cursor,
+            prev_cursor,
+        });
+    };
+
+    if cursor.sort.field != sort.field || cursor.sort.order != sort.order {
+        return Err(OrbitError::ValidationError(
+            "El cursor no corresponde al orden solicitado".into(),
+        )
+        .to_string());
     }
 
-    // ── query (búsqueda en details) ──
-    if let Some(q) = filters.query.as_deref().map(str::trim) {
-        if !q.is_empty() {
-            conditions.push("details LIKE ?");
-            params.push(Value::Text(format!("%{q}%")));
-        }
+    let base = MovementWhere {
+        clause: where_clause,
+        params,
+    };
+    let (movements, next_cursor, prev_cursor) =
+        fetch_movements_page(&conn, base, sort, filters.limit, cursor)?;
+
+    #[cfg(dev)]
+    {
+        println!(
+            "retrieving {} of {} movements (keyset)",
+            movements.len(),
+            total
+        );
     }
 
-    // Cláusula WHERE reutilizable (vacía si no hay condiciones).
-    let where_clause = if conditions.is_empty() {
-        String::new()
-    } else {
-        format!(" WHERE {}", conditions.join(" AND "))
+    Ok(MovementList {
+        movements,
+        total,
+        next_cursor,
+        prev_cursor,
+    })
+}
+
+/// Página de movimientos junto con sus cursores (siguiente, anterior).
+type MovementPage = (Vec<Movement>, Option<String>, Option<String>);
+
+/// Trae una página por keyset a partir de `cursor`.
+///
+/// Pide `limit + 1` filas para saber si hay más sin un COUNT extra. Para ir
+/// hacia atrás invierte comparación y orden, y después da vuelta el resultado.
+fn fetch_movements_page(
+    conn: &rusqlite::Connection,
+    base: MovementWhere,
+    sort: SortCriteria,
+    limit: i64,
+    cursor: MovementCursor,
+) -> Result<MovementPage, String> {
+    let MovementWhere {
+        clause: where_clause,
+        mut params,
+    } = base;
+    let backwards = cursor.direction == PageDirection::Prev;
+
+    // En qué sentido se recorre la tabla para esta consulta
+    let scan_order = match (sort.order, backwards) {
+        (SortOrder::Asc, false) | (SortOrder::Desc, true) => SortOrder::Asc,
+        (SortOrder::Desc, false) | (SortOrder::Asc, true) => SortOrder::Desc,
+    };
+    let cmp = match scan_order {
+        SortOrder::Asc => ">",
+        SortOrder::Desc => "<",
     };
 
-    // ── 1) TOTAL: cuenta TODO lo filtrado, sin LIMIT/OFFSET ──
-    // Clonamos los params del WHERE porque params_from_iter los consume.
-    let count_sql = format!("SELECT COUNT(*) FROM movements{where_clause}");
-    let total: i64 = conn
-        .query_row(&count_sql, params_from_iter(params.clone()), |row| {
-            row.get(0)
-        })
-        .map_err(|e| e.to_string())?;
+    let column = sort.field.column();
+    let value = cursor.sql_value().map_err(|e| e.to_string())?;
 
-    // ── 2) DATOS: misma base + ORDER BY + LIMIT/OFFSET ──
-    let mut sql = format!(
-        "SELECT id, details, date, created_at, mov_type, currency, \
-         original_amount, ars_amount, exchange_rate, rate_type, account_id, category_id \
-         FROM movements{where_clause}",
-    );
+    let keyset = format!("({column} {cmp} ? OR ({column} = ? AND id {cmp} ?))");
+    let mut sql = if where_clause.is_empty() {
+        format!("{MOVEMENT_SELECT} WHERE {keyset}")
+    } else {
+        format!("{MOVEMENT_SELECT}{where_clause} AND {keyset}")
+    };
+    params.push(value.clone());
+    params.push(value);
+    params.push(Value::Integer(cursor.id));
 
-    // ── ORDER BY (whitelist, seguro) + desempate estable por id ──
-    let sort = filters.sort.unwrap_or_default();
     sql.push_str(&format!(
-        " ORDER BY {} {}, id {}",
+        " ORDER BY {} {}, id {} LIMIT ?",
         sort.field.column(),
-        sort.order.keyword(),
-        sort.order.keyword(),
+        scan_order.keyword(),
+        scan_order.keyword(),
     ));
-
-    // ── LIMIT / OFFSET (agregamos al final, solo para esta query) ──
-    sql.push_str(" LIMIT ? OFFSET ?");
-    params.push(Value::Integer(filters.limit));
-    params.push(Value::Integer(filters.offset));
+    params.push(Value::Integer(limit + 1));
 
     let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
 
-    let rows = stmt
-        .query_map(params_from_iter(params), |row| {
-            Ok(Movement {
-                id: row.get(0)?,
-                details: row.get(1)?,
-                date: row.get(2)?,
-                created_at: row.get(3)?,
-                mov_type: row.get(4)?, // usa FromSql<MovementType>
-                currency: row.get(5)?, // usa FromSql<Currency>
-                original_amount: row.get(6)?,
-                ars_amount: row.get(7)?,
-                exchange_rate: row.get(8)?,
-                rate_type: row.get(9)?, // Option<RateType>, maneja NULL
-                account_id: row.get(10)?,
-                category_id: row.get(11)?,
-            })
-        })
-        .map_err(|e| e.to_string())?;
-
-    let movements = rows
+    let mut movements = stmt
+        .query_map(params_from_iter(params), row_to_movement)
+        .map_err(|e| e.to_string())?
         .collect::<Result<Vec<Movement>, _>>()
         .map_err(|e| e.to_string())?;
 
-    #[cfg(dev)]
-    {
-        println!("retrieving {} of {} movements", movements.len(), total);
+    let has_more = movements.len() as i64 > limit;
+    movements.truncate(limit.max(0) as usize);
+
+    if backwards {
+        movements.reverse();
     }
 
-    Ok(MovementList { movements, total })
+    // Hacia adelante: hay siguiente si sobró una fila, y siempre hay anterior
+    // porque vinimos de un cursor. Hacia atrás: al revés.
+    let (has_next, has_prev) = if backwards {
+        (true, has_more)
+    } else {
+        (has_more, true)
+    };
+
+    let next_cursor = movements
+        .last()
+        .filter(|_| has_next)
+        .map(|m| MovementCursor::from_movement(sort, m, PageDirection::Next).encode());
+    let prev_cursor = movements
+        .first()
+        .filter(|_| has_prev)
+        .map(|m| MovementCursor::from_movement(sort, m, PageDirection::Prev).encode());
+
+    Ok((movements, next_cursor, prev_cursor))
 }
 
 #[tauri::command]
@@ -495,8 +982,13 @@ pub fn get_movements_by_account_id(
 }
 
 #[tauri::command]
-pub fn add_movement(state: tauri::State<crate::AppState>, movement: AddMovement) -> Movement {
+pub fn add_movement(
+    app: tauri::AppHandle,
+    state: tauri::State<crate::AppState>,
+    movement: AddMovement,
+) -> Result<Movement, OrbitError> {
     let conn = state.conn.lock().unwrap();
+    let op = history::begin(&conn, "Agregar movimiento")?;
 
     conn.execute(
         "INSERT INTO movements (details, date, mov_type, currency, original_amount, ars_amount, exchange_rate, rate_type, account_id, category_id) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
@@ -512,12 +1004,11 @@ pub fn add_movement(state: tauri::State<crate::AppState>, movement: AddMovement)
             movement.account_id,
             movement.category_id
         ],
-    )
-    .unwrap();
+    )?;
 
     let movement_id = conn.last_insert_rowid();
 
-    conn.query_row(
+    let movement = conn.query_row(
         "SELECT id, details, date, created_at, mov_type, currency, original_amount, ars_amount, exchange_rate, rate_type, account_id, category_id FROM movements WHERE id = ?1",
         params![movement_id],
         |row| {
@@ -554,8 +1045,46 @@ pub fn add_movement(state: tauri::State<crate::AppState>, movement: AddMovement)
                 category_id: row.get::<_, Option<i64>>(11)?,
             })
         },
-    )
-    .unwrap()
+    )?;
+
+    op.finish();
+    drop(conn);
+
+    // Avisa al front si el movimiento nuevo dispara alguna anomalía
+    anomalies::spawn_insight_check(app, movement_id);
+
+    Ok(movement)
+}
+
+/// Un movimiento dividido no puede cambiar de monto: las divisiones dejarían
+/// de sumar el total (ver splits::set_movement_splits).
+fn ensure_splits_match(
+    conn: &rusqlite::Connection,
+    id: i64,
+    original_amount: i64,
+) -> Result<(), OrbitError> {
+    let (current_amount, has_splits): (i64, bool) = conn
+        .query_row(
+            "SELECT original_amount,
+                    EXISTS (SELECT 1 FROM movement_splits WHERE mov_id = ?1)
+             FROM movements WHERE id = ?1",
+            params![id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .map_err(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => {
+                OrbitError::NotFound(format!("No se encontró el movimiento con ID {}", id))
+            }
+            e => OrbitError::Database(e),
+        })?;
+
+    if has_splits && current_amount != original_amount {
+        return Err(OrbitError::ValidationError(
+            "El movimiento está dividido en categorías: quitá o ajustá el desglose antes de cambiar el monto".into(),
+        ));
+    }
+
+    Ok(())
 }
 
 #[tauri::command]
@@ -563,8 +1092,11 @@ pub fn update_movement(
     state: tauri::State<crate::AppState>,
     id: i64,
     movement: UpdateMovement,
-) -> Movement {
+) -> Result<Movement, OrbitError> {
     let conn = state.conn.lock().unwrap();
+    ensure_splits_match(&conn, id, movement.original_amount)?;
+
+    let op = history::begin(&conn, "Editar movimiento")?;
 
     conn.execute(
         "UPDATE movements SET details = ?1, date = ?2, mov_type = ?3, currency = ?4, original_amount = ?5, ars_amount = ?6, exchange_rate = ?7, rate_type = ?8, category_id = ?9 WHERE id = ?10",
@@ -580,10 +1112,9 @@ pub fn update_movement(
             movement.category_id,
             id
         ],
-    )
-    .unwrap();
+    )?;
 
-    conn.query_row(
+    let movement = conn.query_row(
         "SELECT id, details, date, created_at, mov_type, currency, original_amount, ars_amount, exchange_rate, rate_type, account_id, category_id FROM movements WHERE id = ?1",
         params![id],
         |row| {
@@ -620,16 +1151,27 @@ pub fn update_movement(
                 category_id: row.get::<_, Option<i64>>(11)?,
             })
         },
-    )
-    .unwrap()
+    )?;
+
+    op.finish();
+
+    Ok(movement)
 }
 
 #[tauri::command]
-pub fn delete_movement(state: tauri::State<crate::AppState>, id: i64) {
+pub fn delete_movement(state: tauri::State<crate::AppState>, id: i64) -> Result<(), OrbitError> {
     let conn = state.conn.lock().unwrap();
+    let op = history::begin(&conn, "Eliminar movimiento")?;
 
-    conn.execute("DELETE FROM movements WHERE id = ?1", params![id])
-        .unwrap();
+    conn.execute("DELETE FROM movements WHERE id = ?1", params![id])?;
+
+    op.finish();
+
+    // Los adjuntos del movimiento se conservan mientras se pueda deshacer;
+    // esto solo borra los que ya salieron del historial
+    attachments::remove_orphans(&conn, &state.attachments_dir)?;
+
+    Ok(())
 }
 
 // ---------------------------------------------------------------------------
@@ -643,7 +1185,7 @@ pub struct ItemWithPurchase {
     /// Purchase fields
     pub purchase_id: i64,
     pub price: i64,
-    pub quantity: i64,
+    pub quantity: f64,
     /// Item fields
     pub item_id: i64,
     pub item_name: String,
@@ -696,75 +1238,170 @@ pub struct MovementStats {
     pub total_income: f64,
     pub total_expense: f64,
     pub total_net: f64,
+    /// Los mismos totales en pesos constantes, si se pidió un mes de referencia.
+    pub constant: Option<ConstantStats>,
+    /// Los mismos totales en USD (centavos), si se pidió una conversión.
+    pub usd: Option<UsdStats>,
+}
+
+/// Totales convertidos a USD según `conversion`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsdStats {
+    pub conversion: UsdConversion,
+    pub total_income: f64,
+    pub total_expense: f64,
+    pub total_net: f64,
+}
+
+/// Totales ajustados por IPC a pesos de `reference_month`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConstantStats {
+    pub reference_month: String,
+    pub total_income: f64,
+    pub total_expense: f64,
+    pub total_net: f64,
 }
 
 #[tauri::command]
 pub fn get_movements_stats(
     state: tauri::State<crate::AppState>,
     filters: MovementFilters,
+    reference_month: Option<String>,
+    usd: Option<UsdConversion>,
 ) -> Result<MovementStats, OrbitError> {
+    let reference_month = reference_month.as_deref().map(parse_month).transpose()?;
     let conn = state.conn.lock().unwrap();
 
-    // Mismo armado de WHERE que get_movements (todo parametrizado).
+    // Mismo WHERE que get_movements (todo parametrizado).
     // NO usamos limit/offset: las stats son sobre TODO lo filtrado.
-    let mut conditions: Vec<&str> = Vec::new();
-    let mut params: Vec<Value> = Vec::new();
+    let MovementWhere {
+        clause: where_clause,
+        mut params,
+    } = filters.build_where()?;
 
-    // ── type ("all" no filtra) ──
-    if let Some(t) = filters.r#type.as_movement_type() {
-        conditions.push("mov_type = ?");
-        params.push(Value::Text(t.as_db_str().to_string()));
-    }
-
-    // ── period ──
-    if let Some(period) = filters.period {
-        conditions.push("date >= date('now', ?)");
-        params.push(Value::Text(period.sqlite_modifier().to_string()));
-    }
+    // Agregamos en una sola pasada con SUM condicional.
+    // COALESCE evita NULL cuando no hay filas que matcheen.
+    // Leemos de v_movement_lines para que un movimiento dividido aporte a
+    // cada categoría solo el monto de sus líneas: los filtros se evalúan a
+    // nivel movimiento y la categoría, además, a nivel línea.
+    // Se agrupa por mes para poder ajustar por IPC; los totales nominales
+    // se suman después.
+    let mut lines = format!(
+        " FROM v_movement_lines \
+         WHERE id IN (SELECT id FROM movements{where_clause})"
+    );
 
-    // ── category_id ──
     if let Some(cat) = filters.category_id {
-        conditions.push("category_id = ?");
+        lines.push_str(" AND category_id = ?");
         params.push(Value::Integer(cat));
     }
-
-    // ── group_id (relación N:M vía movements_groups) ──
-    if let Some(group) = filters.group_id {
-        conditions.push("id IN (SELECT mov_id FROM movements_groups WHERE group_id = ?)");
-        params.push(Value::Integer(group));
+    if filters.uncategorized_only {
+        lines.push_str(" AND category_id IS NULL");
     }
 
-    // ── query (búsqueda en details) ──
-    if let Some(q) = filters.query.as_deref().map(str::trim) {
-        if !q.is_empty() {
-            conditions.push("details LIKE ?");
-            params.push(Value::Text(format!("%{q}%")));
-        }
-    }
-
-    // Agregamos en una sola pasada con SUM condicional.
-    // COALESCE evita NULL cuando no hay filas que matcheen.
-    let mut sql = String::from(
-        "SELECT \
+    let sql = format!(
+        "SELECT strftime('%Y-%m', date) AS month, \
          COALESCE(SUM(CASE WHEN mov_type = 'income'  THEN ars_amount ELSE 0 END), 0) AS total_income, \
-         COALESCE(SUM(CASE WHEN mov_type = 'expense' THEN ars_amount ELSE 0 END), 0) AS total_expense \
-         FROM movements",
+         COALESCE(SUM(CASE WHEN mov_type = 'expense' THEN ars_amount ELSE 0 END), 0) AS total_expense\
+         {lines} GROUP BY month"
     );
 
-    if !conditions.is_empty() {
-        sql.push_str(" WHERE ");
-        sql.push_str(&conditions.join(" AND "));
-    }
+    let mut stmt = conn.prepare(&sql)?;
+    let by_month = stmt
+        .query_map(params_from_iter(params.iter()), |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, f64>(1)?,
+                row.get::<_, f64>(2)?,
+            ))
+        })?
+        .collect::<Result<Vec<(String, f64, f64)>, rusqlite::Error>>()?;
+
+    let total_income: f64 = by_month.iter().map(|(_, income, _)| income).sum();
+    let total_expense: f64 = by_month.iter().map(|(_, _, expense)| expense).sum();
+
+    let constant = match reference_month {
+        Some(reference_month) => {
+            let months: Vec<String> = by_month.iter().map(|(m, _, _)| m.clone()).collect();
+            let factors = CpiSeries::load(&conn)?.factors(&months, &reference_month)?;
+
+            let (mut income, mut expense) = (0.0, 0.0);
+            for ((_, month_income, month_expense), factor) in by_month.iter().zip(&factors) {
+                income += (month_income * factor).round();
+                expense += (month_expense * factor).round();
+            }
+            Some(ConstantStats {
+                reference_month,
+                total_income: income,
+                total_expense: expense,
+                total_net: income - expense,
+            })
+        }
+        None => None,
+    };
 
-    let (total_income, total_expense): (f64, f64) = conn
-        .query_row(&sql, params_from_iter(params), |row| {
-            Ok((row.get(0)?, row.get(1)?))
-        })
-        .map_err(|e| OrbitError::Database(e))?;
+    let usd = match usd {
+        Some(conversion) => {
+            conversion.ensure_rates(&conn)?;
+            let amount = conversion.amount_expr("v_movement_lines");
+            let (income, expense): (f64, f64) = conn.query_row(
+                &format!(
+                    "SELECT \
+                     COALESCE(SUM(CASE WHEN mov_type = 'income'  THEN {amount} ELSE 0 END), 0), \
+                     COALESCE(SUM(CASE WHEN mov_type = 'expense' THEN {amount} ELSE 0 END), 0)\
+                     {lines}"
+                ),
+                params_from_iter(params.iter()),
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )?;
+            Some(UsdStats {
+                conversion,
+                total_income: income.round(),
+                total_expense: expense.round(),
+                total_net: income.round() - expense.round(),
+            })
+        }
+        None => None,
+    };
 
     Ok(MovementStats {
         total_income,
         total_expense,
         total_net: total_income - total_expense,
+        constant,
+        usd,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_movement_keeps_its_amount() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch(include_str!("../schema.sql")).unwrap();
+        conn.execute_batch(
+            "INSERT INTO accounts (id, name, acc_type) VALUES (1, 'Cuenta', 'checking');
+             INSERT INTO movements (id, details, date, mov_type, original_amount, ars_amount, account_id)
+             VALUES (1, 'Super', '2024-01-10', 'expense', 10000, 10000, 1),
+                    (2, 'Cine',  '2024-01-11', 'expense',  5000,  5000, 1);
+             INSERT INTO movement_splits (mov_id, amount) VALUES (1, 6000), (1, 4000);",
+        )
+        .unwrap();
+
+        assert!(ensure_splits_match(&conn, 1, 10000).is_ok());
+        assert!(matches!(
+            ensure_splits_match(&conn, 1, 12000),
+            Err(OrbitError::ValidationError(_))
+        ));
+        // Sin desglose el monto se puede cambiar libremente
+        assert!(ensure_splits_match(&conn, 2, 7000).is_ok());
+        assert!(matches!(
+            ensure_splits_match(&conn, 3, 7000),
+            Err(OrbitError::NotFound(_))
+        ));
+    }
+}
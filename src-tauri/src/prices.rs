@@ -0,0 +1,670 @@
+use std::collections::{BTreeMap, HashMap};
+
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    errors::OrbitError,
+    utils::{format_date, month_range, parse_date},
+};
+
+/// Number of items in the personal inflation basket when not specified.
+const DEFAULT_BASKET_SIZE: i64 = 20;
+
+/// Price statistics of an item for one month, per standard unit, in ARS cents.
+#[derive(Debug, Clone, Serialize)]
+pub struct MonthlyPrice {
+    /// YYYY-MM
+    pub month: String,
+    pub min: i64,
+    pub max: i64,
+    pub avg: i64,
+    pub purchases: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StorePriceSeries {
+    pub store_id: Option<i64>,
+    /// `None` for purchases without a store
+    pub store_name: Option<String>,
+    pub monthly: Vec<MonthlyPrice>,
+}
+
+/// Price history of an item, normalized per kg, liter or unit.
+#[derive(Debug, Clone, Serialize)]
+pub struct ItemPriceHistory {
+    pub item_id: i64,
+    /// "unit", "kg" or "l"
+    pub standard_unit: String,
+    pub monthly: Vec<MonthlyPrice>,
+    pub by_store: Vec<StorePriceSeries>,
+    /// Percentage change of the average price against 1, 3 and 12 months
+    /// before the last month with purchases. `None` without data to compare.
+    pub change_1m: Option<f64>,
+    pub change_3m: Option<f64>,
+    pub change_12m: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BasketItem {
+    pub item_id: i64,
+    pub name: String,
+    /// Share of the basket spend (0.0 - 1.0)
+    pub weight: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct InflationPoint {
+    /// YYYY-MM
+    pub month: String,
+    /// Price level of the basket, 100 = first month
+    pub index: f64,
+    /// Percentage change against the previous month
+    pub monthly_change: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PersonalInflation {
+    pub basket: Vec<BasketItem>,
+    pub series: Vec<InflationPoint>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StoreComparisonFilters {
+    /// Items to compare; empty compares every item bought in the period
+    #[serde(default)]
+    pub item_ids: Vec<i64>,
+    /// Fechas YYYY-MM-DD, ambas inclusive
+    #[serde(default)]
+    pub from: Option<String>,
+    #[serde(default)]
+    pub to: Option<String>,
+}
+
+/// Prices of an item at one store, per standard unit, in ARS cents.
+#[derive(Debug, Clone, Serialize)]
+pub struct StoreItemPrice {
+    pub store_id: i64,
+    pub store_name: String,
+    pub latest_price: i64,
+    pub latest_date: String,
+    pub avg_price: i64,
+    pub purchases: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ItemStoreComparison {
+    pub item_id: i64,
+    pub item_name: String,
+    pub standard_unit: String,
+    /// Ordered from cheapest to most expensive latest price
+    pub stores: Vec<StoreItemPrice>,
+    /// Store with the lowest latest price (the most recent one on ties)
+    pub best_store_id: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StoreRanking {
+    pub store_id: i64,
+    pub store_name: String,
+    /// Sum of the average prices of the items bought at this store
+    pub basket_cost: i64,
+    pub items_covered: i64,
+    /// Average of the store price relative to the cheapest store for each
+    /// covered item; 100 = always the cheapest
+    pub price_index: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StoreComparison {
+    pub items: Vec<ItemStoreComparison>,
+    /// Ordered from cheapest to most expensive
+    pub ranking: Vec<StoreRanking>,
+}
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Precio por unidad estándar convertido a ARS con la cotización del
+/// movimiento (en ARS no cambia nada).
+const ARS_UNIT_PRICE: &str = "u.unit_price * m.ars_amount * 1.0 / NULLIF(m.original_amount, 0)";
+
+fn round2(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+/// Variación porcentual entre el último mes de la serie y el último mes con
+/// datos que esté al menos `months` meses antes. La serie va ordenada por mes.
+fn percent_change(series: &[(String, f64)], months: u32) -> Option<f64> {
+    let (last_month, last_value) = series.last()?;
+    let limit = chrono::NaiveDate::parse_from_str(&format!("{last_month}-01"), "%Y-%m-%d")
+        .ok()?
+        .checked_sub_months(chrono::Months::new(months))?
+        .format("%Y-%m")
+        .to_string();
+
+    let (_, base) = series.iter().rev().find(|(month, _)| *month <= limit)?;
+    if *base == 0.0 {
+        return None;
+    }
+
+    Some(round2((last_value / base - 1.0) * 100.0))
+}
+
+/// Índice encadenado de la canasta, base 100 en el primer mes.
+///
+/// Para cada par de meses consecutivos se promedian las variaciones de precio
+/// de cada ítem, ponderadas por su peso en el gasto. Si un ítem no se compró
+/// en un mes se arrastra su último precio (variación 0); los ítems todavía no
+/// comprados no participan hasta su primera compra.
+fn chain_index(
+    months: &[String],
+    prices: &HashMap<i64, BTreeMap<String, f64>>,
+    weights: &HashMap<i64, f64>,
+) -> Vec<InflationPoint> {
+    let mut last_price: HashMap<i64, f64> = HashMap::new();
+    let mut series: Vec<InflationPoint> = Vec::with_capacity(months.len());
+    let mut index = 100.0;
+
+    for month in months {
+        let mut weighted = 0.0;
+        let mut total_weight = 0.0;
+        let mut current: HashMap<i64, f64> = HashMap::new();
+
+        for (item_id, item_prices) in prices {
+            let Some(price) = item_prices.get(month).or(last_price.get(item_id)) else {
+                continue;
+            };
+            current.insert(*item_id, *price);
+
+            if let Some(previous) = last_price.get(item_id).filter(|p| **p > 0.0) {
+                let weight = weights.get(item_id).copied().unwrap_or(0.0);
+                weighted += weight * (price / previous);
+                total_weight += weight;
+            }
+        }
+
+        let monthly_change = if series.is_empty() || total_weight == 0.0 {
+            None
+        } else {
+            let relative = weighted / total_weight;
+            index *= relative;
+            Some(round2((relative - 1.0) * 100.0))
+        };
+
+        series.push(InflationPoint {
+            month: month.clone(),
+            index: round2(index),
+            monthly_change,
+        });
+        last_price = current;
+    }
+
+    series
+}
+
+/// Arma el ranking de tiendas a partir de la comparación por ítem.
+///
+/// Cada tienda cubre solo algunos ítems, así que sumar precios no alcanza
+/// para compararlas: el índice promedia, por ítem cubierto, cuánto más cara
+/// es la tienda que la más barata para ese ítem.
+fn rank_stores(items: &[ItemStoreComparison]) -> Vec<StoreRanking> {
+    let mut rankings: HashMap<i64, (StoreRanking, f64)> = HashMap::new();
+
+    for item in items {
+        let Some(cheapest) = item.stores.iter().map(|s| s.avg_price).min() else {
+            continue;
+        };
+
+        for store in &item.stores {
+            let (ranking, relative_sum) = rankings.entry(store.store_id).or_insert_with(|| {
+                (
+                    StoreRanking {
+                        store_id: store.store_id,
+                        store_name: store.store_name.clone(),
+                        basket_cost: 0,
+                        items_covered: 0,
+                        price_index: 0.0,
+                    },
+                    0.0,
+                )
+            });
+
+            ranking.basket_cost += store.avg_price;
+            ranking.items_covered += 1;
+            *relative_sum += if cheapest > 0 {
+                store.avg_price as f64 / cheapest as f64
+            } else {
+                1.0
+            };
+        }
+    }
+
+    let mut ranking: Vec<StoreRanking> = rankings
+        .into_values()
+        .map(|(mut ranking, relative_sum)| {
+            ranking.price_index = round2(relative_sum / ranking.items_covered as f64 * 100.0);
+            ranking
+        })
+        .collect();
+
+    // A igual índice, primero la que cubre más ítems
+    ranking.sort_by(|a, b| {
+        a.price_index
+            .total_cmp(&b.price_index)
+            .then(b.items_covered.cmp(&a.items_covered))
+            .then(a.store_name.cmp(&b.store_name))
+    });
+
+    ranking
+}
+
+/// Límite opcional de un rango de fechas (YYYY-MM-DD); vacío es sin límite.
+fn parse_date_bound(value: &Option<String>) -> Result<Option<String>, OrbitError> {
+    value
+        .as_deref()
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .map(|v| parse_date(v).map(format_date))
+        .transpose()
+}
+
+// ---------------------------------------------------------------------------
+// Commands
+// ---------------------------------------------------------------------------
+
+/// Serie de precios de un ítem por mes (mínimo, máximo y promedio por unidad
+/// estándar, en ARS), total y por tienda, con la variación a 1, 3 y 12 meses.
+#[tauri::command]
+pub fn get_item_price_history(
+    state: tauri::State<crate::AppState>,
+    item_id: i64,
+) -> Result<ItemPriceHistory, OrbitError> {
+    let conn = state.conn.lock().unwrap();
+
+    let standard_unit: String = match conn.query_row(
+        "SELECT CASE unit WHEN 'g' THEN 'kg' WHEN 'ml' THEN 'l' ELSE unit END
+         FROM items WHERE id = ?1",
+        params![item_id],
+        |row| row.get(0),
+    ) {
+        Ok(unit) => unit,
+        Err(rusqlite::Error::QueryReturnedNoRows) => {
+            return Err(OrbitError::NotFound(format!(
+                "No se encontró el ítem con ID {}",
+                item_id
+            )))
+        }
+        Err(e) => return Err(OrbitError::Database(e)),
+    };
+
+    let row_to_monthly = |row: &rusqlite::Row, offset: usize| -> rusqlite::Result<MonthlyPrice> {
+        Ok(MonthlyPrice {
+            month: row.get(offset)?,
+            min: row.get(offset + 1)?,
+            max: row.get(offset + 2)?,
+            avg: row.get(offset + 3)?,
+            purchases: row.get(offset + 4)?,
+        })
+    };
+
+    let mut stmt = conn.prepare(&format!(
+        "SELECT strftime('%Y-%m', m.date) AS month,
+                CAST(ROUND(MIN({ARS_UNIT_PRICE})) AS INTEGER),
+                CAST(ROUND(MAX({ARS_UNIT_PRICE})) AS INTEGER),
+                CAST(ROUND(AVG({ARS_UNIT_PRICE})) AS INTEGER),
+                COUNT(*)
+         FROM v_purchase_unit_prices u
+         JOIN movements m ON m.id = u.mov_id
+         WHERE u.item_id = ?1 AND m.original_amount <> 0
+         GROUP BY month
+         ORDER BY month ASC"
+    ))?;
+    let monthly = stmt
+        .query_map(params![item_id], |row| row_to_monthly(row, 0))?
+        .collect::<Result<Vec<MonthlyPrice>, rusqlite::Error>>()?;
+
+    let mut stmt = conn.prepare(&format!(
+        "SELECT u.store_id, s.name,
+                strftime('%Y-%m', m.date) AS month,
+                CAST(ROUND(MIN({ARS_UNIT_PRICE})) AS INTEGER),
+                CAST(ROUND(MAX({ARS_UNIT_PRICE})) AS INTEGER),
+                CAST(ROUND(AVG({ARS_UNIT_PRICE})) AS INTEGER),
+                COUNT(*)
+         FROM v_purchase_unit_prices u
+         JOIN movements m ON m.id = u.mov_id
+         LEFT JOIN stores s ON s.id = u.store_id
+         WHERE u.item_id = ?1 AND m.original_amount <> 0
+         GROUP BY u.store_id, month
+         ORDER BY s.name ASC, month ASC"
+    ))?;
+    let rows = stmt
+        .query_map(params![item_id], |row| {
+            Ok((
+                row.get::<_, Option<i64>>(0)?,
+                row.get::<_, Option<String>>(1)?,
+                row_to_monthly(row, 2)?,
+            ))
+        })?
+        .collect::<Result<Vec<(Option<i64>, Option<String>, MonthlyPrice)>, rusqlite::Error>>()?;
+
+    let mut by_store: Vec<StorePriceSeries> = Vec::new();
+    for (store_id, store_name, month) in rows {
+        match by_store.last_mut().filter(|s| s.store_id == store_id) {
+            Some(series) => series.monthly.push(month),
+            None => by_store.push(StorePriceSeries {
+                store_id,
+                store_name,
+                monthly: vec![month],
+            }),
+        }
+    }
+
+    let averages: Vec<(String, f64)> = monthly
+        .iter()
+        .map(|m| (m.month.clone(), m.avg as f64))
+        .collect();
+
+    Ok(ItemPriceHistory {
+        item_id,
+        standard_unit,
+        change_1m: percent_change(&averages, 1),
+        change_3m: percent_change(&averages, 3),
+        change_12m: percent_change(&averages, 12),
+        monthly,
+        by_store,
+    })
+}
+
+/// Índice de inflación personal: evolución mensual del precio de una canasta
+/// con los ítems en los que más se gastó en el período, ponderados por gasto.
+///
+/// Solo entran a la canasta ítems comprados en al menos dos meses distintos
+/// (con uno solo no hay variación que medir).
+#[tauri::command]
+pub fn get_personal_inflation(
+    state: tauri::State<crate::AppState>,
+    from: Option<String>,
+    to: Option<String>,
+    basket_size: Option<i64>,
+) -> Result<PersonalInflation, OrbitError> {
+    let from = parse_date_bound(&from)?;
+    let to = parse_date_bound(&to)?;
+    let basket_size = basket_size.unwrap_or(DEFAULT_BASKET_SIZE);
+    if basket_size <= 0 {
+        return Err(OrbitError::ValidationError(
+            "La canasta debe tener al menos un ítem".into(),
+        ));
+    }
+
+    let conn = state.conn.lock().unwrap();
+
+    let range = "(?1 IS NULL OR m.date >= ?1) AND (?2 IS NULL OR m.date <= ?2)";
+
+    let mut stmt = conn.prepare(&format!(
+        "SELECT u.item_id, i.name,
+                SUM(u.price * u.quantity * m.ars_amount * 1.0 / NULLIF(m.original_amount, 0)) AS spend
+         FROM v_purchase_unit_prices u
+         JOIN movements m ON m.id = u.mov_id
+         JOIN items i ON i.id = u.item_id
+         WHERE {range}
+         GROUP BY u.item_id
+         HAVING COUNT(DISTINCT strftime('%Y-%m', m.date)) >= 2 AND spend > 0
+         ORDER BY spend DESC
+         LIMIT ?3"
+    ))?;
+    let basket_rows = stmt
+        .query_map(params![from, to, basket_size], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, f64>(2)?,
+            ))
+        })?
+        .collect::<Result<Vec<(i64, String, f64)>, rusqlite::Error>>()?;
+
+    let total_spend: f64 = basket_rows.iter().map(|(_, _, spend)| spend).sum();
+    let basket: Vec<BasketItem> = basket_rows
+        .into_iter()
+        .map(|(item_id, name, spend)| BasketItem {
+            item_id,
+            name,
+            weight: spend / total_spend,
+        })
+        .collect();
+
+    if basket.is_empty() {
+        return Ok(PersonalInflation {
+            basket,
+            series: Vec::new(),
+        });
+    }
+
+    let weights: HashMap<i64, f64> = basket.iter().map(|b| (b.item_id, b.weight)).collect();
+    let basket_ids = format!(
+        "[{}]",
+        basket
+            .iter()
+            .map(|b| b.item_id.to_string())
+            .collect::<Vec<String>>()
+            .join(",")
+    );
+
+    let mut stmt = conn.prepare(&format!(
+        "SELECT u.item_id, strftime('%Y-%m', m.date) AS month, AVG({ARS_UNIT_PRICE})
+         FROM v_purchase_unit_prices u
+         JOIN movements m ON m.id = u.mov_id
+         WHERE {range} AND u.item_id IN (SELECT value FROM json_each(?3))
+         GROUP BY u.item_id, month"
+    ))?;
+    let rows = stmt
+        .query_map(params![from, to, basket_ids], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, Option<f64>>(2)?,
+            ))
+        })?
+        .collect::<Result<Vec<(i64, String, Option<f64>)>, rusqlite::Error>>()?;
+
+    let mut prices: HashMap<i64, BTreeMap<String, f64>> = HashMap::new();
+    for (item_id, month, price) in rows {
+        if let Some(price) = price {
+            prices.entry(item_id).or_default().insert(month, price);
+        }
+    }
+
+    let all_months = prices.values().flat_map(|p| p.keys());
+    let first = all_months.clone().min().cloned().unwrap_or_default();
+    let last = all_months.max().cloned().unwrap_or_default();
+    let months = month_range(&first, &last);
+
+    Ok(PersonalInflation {
+        basket,
+        series: chain_index(&months, &prices, &weights),
+    })
+}
+
+/// Compara los precios de los ítems entre tiendas en un período: último precio
+/// y promedio por tienda, la tienda con el mejor último precio de cada ítem y
+/// un ranking de tiendas para esa canasta.
+///
+/// Solo se consideran compras con tienda asignada.
+#[tauri::command]
+pub fn compare_stores(
+    state: tauri::State<crate::AppState>,
+    filters: StoreComparisonFilters,
+) -> Result<StoreComparison, OrbitError> {
+    let from = parse_date_bound(&filters.from)?;
+    let to = parse_date_bound(&filters.to)?;
+    let item_ids = (!filters.item_ids.is_empty()).then(|| {
+        format!(
+            "[{}]",
+            filters
+                .item_ids
+                .iter()
+                .map(|id| id.to_string())
+                .collect::<Vec<String>>()
+                .join(",")
+        )
+    });
+
+    let conn = state.conn.lock().unwrap();
+
+    let mut stmt = conn.prepare(&format!(
+        "WITH prices AS (
+             SELECT u.item_id, u.store_id, m.date, {ARS_UNIT_PRICE} AS price,
+                    ROW_NUMBER() OVER (
+                        PARTITION BY u.item_id, u.store_id
+                        ORDER BY m.date DESC, u.purchase_id DESC
+                    ) AS recency
+             FROM v_purchase_unit_prices u
+             JOIN movements m ON m.id = u.mov_id
+             WHERE u.store_id IS NOT NULL AND m.original_amount <> 0
+               AND (?1 IS NULL OR m.date >= ?1) AND (?2 IS NULL OR m.date <= ?2)
+               AND (?3 IS NULL OR u.item_id IN (SELECT value FROM json_each(?3)))
+         )
+         SELECT p.item_id, i.name,
+                CASE i.unit WHEN 'g' THEN 'kg' WHEN 'ml' THEN 'l' ELSE i.unit END,
+                p.store_id, s.name,
+                CAST(ROUND(MAX(CASE WHEN p.recency = 1 THEN p.price END)) AS INTEGER),
+                MAX(p.date),
+                CAST(ROUND(AVG(p.price)) AS INTEGER),
+                COUNT(*)
+         FROM prices p
+         JOIN items i ON i.id = p.item_id
+         JOIN stores s ON s.id = p.store_id
+         GROUP BY p.item_id, p.store_id
+         ORDER BY i.name ASC, p.item_id ASC"
+    ))?;
+
+    let rows = stmt
+        .query_map(params![from, to, item_ids], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                StoreItemPrice {
+                    store_id: row.get(3)?,
+                    store_name: row.get(4)?,
+                    latest_price: row.get(5)?,
+                    latest_date: row.get(6)?,
+                    avg_price: row.get(7)?,
+                    purchases: row.get(8)?,
+                },
+            ))
+        })?
+        .collect::<Result<Vec<(i64, String, String, StoreItemPrice)>, rusqlite::Error>>()?;
+
+    let mut items: Vec<ItemStoreComparison> = Vec::new();
+    for (item_id, item_name, standard_unit, store) in rows {
+        match items.last_mut().filter(|i| i.item_id == item_id) {
+            Some(item) => item.stores.push(store),
+            None => items.push(ItemStoreComparison {
+                item_id,
+                item_name,
+                standard_unit,
+                best_store_id: store.store_id,
+                stores: vec![store],
+            }),
+        }
+    }
+
+    for item in &mut items {
+        item.stores.sort_by(|a, b| {
+            a.latest_price
+                .cmp(&b.latest_price)
+                .then(b.latest_date.cmp(&a.latest_date))
+        });
+        item.best_store_id = item.stores[0].store_id;
+    }
+
+    let ranking = rank_stores(&items);
+
+    Ok(StoreComparison { items, ranking })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percent_change_uses_closest_earlier_month() {
+        let series = vec![
+            ("2025-01".to_string(), 100.0),
+            ("2025-03".to_string(), 110.0),
+            ("2025-04".to_string(), 121.0),
+        ];
+
+        assert_eq!(percent_change(&series, 1), Some(10.0));
+        assert_eq!(percent_change(&series[..2], 1), Some(10.0));
+        // 2025-02 no tiene datos: se compara contra 2025-01
+        assert_eq!(percent_change(&series, 3), Some(21.0));
+        assert_eq!(percent_change(&series, 12), None);
+    }
+
+    #[test]
+    fn test_chain_index_weights_and_carries_prices() {
+        let months = month_range("2025-01", "2025-03");
+        let prices: HashMap<i64, BTreeMap<String, f64>> = HashMap::from([
+            (
+                1,
+                BTreeMap::from([
+                    ("2025-01".to_string(), 100.0),
+                    ("2025-02".to_string(), 110.0),
+                ]),
+            ),
+            (
+                2,
+                BTreeMap::from([("2025-01".to_string(), 50.0), ("2025-03".to_string(), 60.0)]),
+            ),
+        ]);
+        let weights = HashMap::from([(1, 0.5), (2, 0.5)]);
+
+        let series = chain_index(&months, &prices, &weights);
+
+        assert_eq!(series.len(), 3);
+        assert_eq!(series[0].index, 100.0);
+        assert_eq!(series[0].monthly_change, None);
+        // Feb: ítem 1 +10%, ítem 2 sin compra (arrastra precio) => +5%
+        assert_eq!(series[1].index, 105.0);
+        // Mar: ítem 1 arrastrado, ítem 2 +20% => +10%
+        assert_eq!(series[2].index, 115.5);
+        assert_eq!(series[2].monthly_change, Some(10.0));
+    }
+
+    #[test]
+    fn test_rank_stores_normalizes_by_cheapest() {
+        let price = |store_id: i64, avg_price: i64| StoreItemPrice {
+            store_id,
+            store_name: format!("Tienda {store_id}"),
+            latest_price: avg_price,
+            latest_date: "2025-01-01".to_string(),
+            avg_price,
+            purchases: 1,
+        };
+        let item = |item_id: i64, stores: Vec<StoreItemPrice>| ItemStoreComparison {
+            item_id,
+            item_name: format!("Ítem {item_id}"),
+            standard_unit: "unit".to_string(),
+            best_store_id: stores[0].store_id,
+            stores,
+        };
+
+        let items = vec![
+            item(1, vec![price(1, 100), price(2, 120)]),
+            item(2, vec![price(2, 1000), price(1, 1500)]),
+            item(3, vec![price(2, 50)]),
+        ];
+
+        let ranking = rank_stores(&items);
+
+        // Tienda 2: (1.2 + 1.0 + 1.0) / 3; tienda 1: (1.0 + 1.5) / 2
+        assert_eq!(ranking[0].store_id, 2);
+        assert_eq!(ranking[0].price_index, 106.67);
+        assert_eq!(ranking[0].items_covered, 3);
+        assert_eq!(ranking[0].basket_cost, 1170);
+        assert_eq!(ranking[1].price_index, 125.0);
+    }
+}
@@ -1,4 +1,7 @@
-use crate::{errors::OrbitError, utils::random_hex_color};
+use crate::{
+    attachments, errors::OrbitError, history, items::ItemUnit, stores::find_store_by_name,
+    utils::random_hex_color,
+};
 use rusqlite::params;
 use serde::{Deserialize, Serialize};
 
@@ -7,8 +10,17 @@ use serde::{Deserialize, Serialize};
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PurchaseWithDetails {
     pub id: i64,
+    /// Price of each package
     pub price: i64,
-    pub quantity: i64,
+    /// Number of packages; fractional for items sold by weight or volume
+    pub quantity: f64,
+    /// Content of each package in the item's unit (`None` = 1)
+    pub package_size: Option<f64>,
+    pub unit: ItemUnit,
+    /// Unit `unit_price` refers to: "unit", "kg" or "l"
+    pub standard_unit: String,
+    /// Price per standard unit (per kg, liter or unit), in cents
+    pub unit_price: i64,
     pub created_at: String,
     /// Movement this purchase belongs to
     pub mov_id: i64,
@@ -22,78 +34,165 @@ pub struct PurchaseWithDetails {
 #[derive(Debug, Deserialize)]
 pub struct AddPurchase {
     pub price: i64,
-    pub quantity: i64,
+    pub quantity: f64,
+    #[serde(default)]
+    pub package_size: Option<f64>,
     pub mov_id: i64,
     pub item_id: i64,
     /// Store name — auto-created if it doesn't exist, NULL if not provided.
     pub store_name: Option<String>,
 }
 
-/// Busca una tienda por nombre. Si no existe, la crea de forma segura.
-fn find_or_create_store(conn: &rusqlite::Connection, name: &str) -> Result<i64, OrbitError> {
-    // Validación básica antes de operar
-    if name.trim().is_empty() {
+#[derive(Debug, Deserialize)]
+pub struct UpdatePurchase {
+    pub price: i64,
+    pub quantity: f64,
+    #[serde(default)]
+    pub package_size: Option<f64>,
+    pub item_id: i64,
+    pub store_name: Option<String>,
+}
+
+/// A line of the full purchase breakdown sent to set_movement_purchases.
+#[derive(Debug, Deserialize)]
+pub struct MovementPurchaseLine {
+    pub price: i64,
+    pub quantity: f64,
+    #[serde(default)]
+    pub package_size: Option<f64>,
+    pub item_id: i64,
+    pub store_name: Option<String>,
+}
+
+/// Breakdown of a movement after set_movement_purchases.
+#[derive(Debug, Clone, Serialize)]
+pub struct MovementPurchases {
+    pub purchases: Vec<PurchaseWithDetails>,
+    /// Part of the movement not covered by the purchases ("otros"), in cents
+    pub other_amount: i64,
+}
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Validaciones defensivas de negocio, comunes a alta y edición.
+fn validate_purchase(
+    price: i64,
+    quantity: f64,
+    package_size: Option<f64>,
+) -> Result<(), OrbitError> {
+    if quantity.is_nan() || quantity <= 0.0 {
         return Err(OrbitError::ValidationError(
-            "El nombre de la tienda no puede estar vacío".into(),
+            "La cantidad comprada debe ser mayor a cero".into(),
+        ));
+    }
+    if package_size.is_some_and(|size| size.is_nan() || size <= 0.0) {
+        return Err(OrbitError::ValidationError(
+            "El tamaño del paquete debe ser mayor a cero".into(),
+        ));
+    }
+    if price < 0 {
+        return Err(OrbitError::ValidationError(
+            "El precio del ítem no puede ser negativo".into(),
         ));
     }
 
-    let query_result = conn.query_row(
-        "SELECT id FROM stores WHERE name = ?1",
-        params![name],
-        |row| row.get(0),
-    );
+    Ok(())
+}
 
-    match query_result {
-        // Caso 1: La tienda ya existe, devolvemos el ID mapeado a Ok
-        Ok(id) => Ok(id),
+/// Total de una línea (`price * quantity`) redondeado a centavos.
+pub(crate) fn line_total(price: i64, quantity: f64) -> i64 {
+    (price as f64 * quantity).round() as i64
+}
 
-        // Caso 2: No se encontró (Error esperado), la insertamos
-        Err(rusqlite::Error::QueryReturnedNoRows) => {
-            let color = random_hex_color();
-            conn.execute(
-                "INSERT INTO stores (name, color) VALUES (?1, ?2)",
-                params![name, color],
-            )?;
-            Ok(conn.last_insert_rowid())
-        }
+fn row_to_purchase(row: &rusqlite::Row) -> rusqlite::Result<PurchaseWithDetails> {
+    Ok(PurchaseWithDetails {
+        id: row.get(0)?,
+        price: row.get(1)?,
+        quantity: row.get(2)?,
+        created_at: row.get(3)?,
+        mov_id: row.get(4)?,
+        mov_date: row.get(5)?,
+        mov_currency: row.get(6)?,
+        store_id: row.get(7)?,
+        store_name: row.get(8)?,
+        package_size: row.get(9)?,
+        unit: row.get(10)?,
+        standard_unit: row.get(11)?,
+        unit_price: row.get(12)?,
+    })
+}
 
-        // Caso 3: Cualquier otro error de SQLite (DB bloqueada, disco lleno, etc.) se propaga
+const PURCHASE_SELECT: &str = "SELECT p.id, p.price, p.quantity, p.created_at, \
+     m.id, m.date, m.currency, \
+     s.id, s.name, \
+     p.package_size, u.unit, u.standard_unit, u.unit_price \
+     FROM purchases p \
+     JOIN movements m ON m.id = p.mov_id \
+     JOIN v_purchase_unit_prices u ON u.purchase_id = p.id \
+     LEFT JOIN stores s ON s.id = p.store_id";
+
+fn fetch_purchase(conn: &rusqlite::Connection, id: i64) -> Result<PurchaseWithDetails, OrbitError> {
+    match conn.query_row(
+        &format!("{PURCHASE_SELECT} WHERE p.id = ?1"),
+        params![id],
+        row_to_purchase,
+    ) {
+        Ok(purchase) => Ok(purchase),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Err(OrbitError::NotFound(format!(
+            "No se encontró la compra con ID {}",
+            id
+        ))),
         Err(e) => Err(OrbitError::Database(e)),
     }
 }
 
+fn resolve_store(
+    conn: &rusqlite::Connection,
+    store_name: Option<&str>,
+) -> Result<Option<i64>, OrbitError> {
+    store_name
+        .filter(|s| !s.trim().is_empty())
+        .map(|name| find_or_create_store(conn, name))
+        .transpose()
+}
+
+/// Busca una tienda por nombre o alias. Si no existe, la crea.
+pub(crate) fn find_or_create_store(
+    conn: &rusqlite::Connection,
+    name: &str,
+) -> Result<i64, OrbitError> {
+    // Validación básica antes de operar
+    if name.trim().is_empty() {
+        return Err(OrbitError::ValidationError(
+            "El nombre de la tienda no puede estar vacío".into(),
+        ));
+    }
+
+    // "Coto", "COTO " y "coto" son la misma tienda; también se buscan los alias
+    if let Some(id) = find_store_by_name(conn, name)? {
+        return Ok(id);
+    }
+
+    conn.execute(
+        "INSERT INTO stores (name, color) VALUES (?1, ?2)",
+        params![name.trim(), random_hex_color()],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
 #[tauri::command]
 pub fn get_purchases(
     state: tauri::State<crate::AppState>,
 ) -> Result<Vec<PurchaseWithDetails>, OrbitError> {
     let conn = state.conn.lock().unwrap();
 
-    let mut stmt = conn.prepare(
-        "SELECT p.id, p.price, p.quantity, p.created_at,
-                m.id, m.date, m.currency,
-                s.id, s.name
-         FROM purchases p
-         JOIN movements m ON m.id = p.mov_id
-         LEFT JOIN stores s ON s.id = p.store_id
-         ORDER BY m.date DESC, p.id DESC
-        ",
-    )?;
+    let mut stmt = conn.prepare(&format!(
+        "{PURCHASE_SELECT} ORDER BY m.date DESC, p.id DESC"
+    ))?;
 
-    let rows = stmt.query_map([], |row| {
-        Ok(PurchaseWithDetails {
-            id: row.get(0)?,
-            price: row.get(1)?,
-            quantity: row.get(2)?,
-            created_at: row.get(3)?,
-            mov_id: row.get(4)?,
-            mov_date: row.get(5)?,
-            mov_currency: row.get(6)?,
-            // Estos dos absorben el NULL del LEFT JOIN de forma segura
-            store_id: row.get(7)?,
-            store_name: row.get(8)?,
-        })
-    })?;
+    let rows = stmt.query_map([], row_to_purchase)?;
 
     // El operador ? final convierte limpiamente el rusqlite::Error a OrbitError
     let purchases = rows.collect::<Result<Vec<PurchaseWithDetails>, rusqlite::Error>>()?;
@@ -102,37 +201,20 @@ pub fn get_purchases(
 }
 
 /// Devuelve todas las compras de un ítem, ordenadas por fecha de movimiento descendente.
+/// Cada compra incluye su precio por unidad estándar (`unit_price`), para comparar
+/// tiendas y tamaños de paquete.
 #[tauri::command]
 pub fn purchases_by_item(
     state: tauri::State<crate::AppState>,
     item_id: i64,
 ) -> Result<Vec<PurchaseWithDetails>, OrbitError> {
     let conn = state.conn.lock().unwrap();
-    let mut stmt = conn.prepare(
-        "SELECT p.id, p.price, p.quantity, p.created_at,
-                m.id, m.date, m.currency,
-                s.id, s.name
-         FROM purchases p
-         JOIN  movements m ON m.id = p.mov_id
-         LEFT JOIN stores s ON s.id = p.store_id
-         WHERE p.item_id = ?1
-         ORDER BY m.date DESC",
-    )?;
+    let mut stmt = conn.prepare(&format!(
+        "{PURCHASE_SELECT} WHERE p.item_id = ?1 ORDER BY m.date DESC"
+    ))?;
 
     let purchases = stmt
-        .query_map(params![item_id], |row| {
-            Ok(PurchaseWithDetails {
-                id: row.get(0)?,
-                price: row.get(1)?,
-                quantity: row.get(2)?,
-                created_at: row.get(3)?,
-                mov_id: row.get(4)?,
-                mov_date: row.get(5)?,
-                mov_currency: row.get(6)?,
-                store_id: row.get(7)?,
-                store_name: row.get(8)?,
-            })
-        })?
+        .query_map(params![item_id], row_to_purchase)?
         .collect::<Result<Vec<PurchaseWithDetails>, rusqlite::Error>>()?;
 
     Ok(purchases)
@@ -144,33 +226,19 @@ pub fn add_purchase(
     state: tauri::State<crate::AppState>,
     purchase: AddPurchase,
 ) -> Result<PurchaseWithDetails, OrbitError> {
-    // Validaciones defensivas de negocio
-    if purchase.quantity <= 0 {
-        return Err(OrbitError::ValidationError(
-            "La cantidad comprada debe ser mayor a cero".into(),
-        ));
-    }
-    if purchase.price < 0 {
-        return Err(OrbitError::ValidationError(
-            "El precio del ítem no puede ser negativo".into(),
-        ));
-    }
+    validate_purchase(purchase.price, purchase.quantity, purchase.package_size)?;
 
     let conn = state.conn.lock().unwrap();
+    let op = history::begin(&conn, "Agregar compra")?;
 
-    // El truco del .transpose() para resolver la firma de find_or_create_store
-    let store_id: Option<i64> = purchase
-        .store_name
-        .as_deref()
-        .filter(|s| !s.trim().is_empty())
-        .map(|name| find_or_create_store(&conn, name))
-        .transpose()?; // Si falla la DB al crear la tienda, intercepta y sale acá con Err
+    let store_id = resolve_store(&conn, purchase.store_name.as_deref())?;
 
     conn.execute(
-        "INSERT INTO purchases (price, quantity, mov_id, item_id, store_id) VALUES (?1, ?2, ?3, ?4, ?5)",
+        "INSERT INTO purchases (price, quantity, package_size, mov_id, item_id, store_id) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
         params![
             purchase.price,
             purchase.quantity,
+            purchase.package_size,
             purchase.mov_id,
             purchase.item_id,
             store_id
@@ -179,29 +247,152 @@ pub fn add_purchase(
 
     let purchase_id = conn.last_insert_rowid();
 
-    let purchase_details = conn.query_row(
-        "SELECT p.id, p.price, p.quantity, p.created_at,
-                m.id, m.date, m.currency,
-                s.id, s.name
-         FROM purchases p
-         JOIN  movements m ON m.id = p.mov_id
-         LEFT JOIN stores s ON s.id = p.store_id
-         WHERE p.id = ?1",
-        params![purchase_id],
-        |row| {
-            Ok(PurchaseWithDetails {
-                id: row.get(0)?,
-                price: row.get(1)?,
-                quantity: row.get(2)?,
-                created_at: row.get(3)?,
-                mov_id: row.get(4)?,
-                mov_date: row.get(5)?,
-                mov_currency: row.get(6)?,
-                store_id: row.get(7)?,
-                store_name: row.get(8)?,
-            })
-        },
+    op.finish();
+
+    fetch_purchase(&conn, purchase_id)
+}
+
+/// Corrige una compra existente (precio, cantidad, ítem o tienda).
+#[tauri::command]
+pub fn update_purchase(
+    state: tauri::State<crate::AppState>,
+    id: i64,
+    purchase: UpdatePurchase,
+) -> Result<PurchaseWithDetails, OrbitError> {
+    validate_purchase(purchase.price, purchase.quantity, purchase.package_size)?;
+
+    let conn = state.conn.lock().unwrap();
+    let op = history::begin(&conn, "Editar compra")?;
+
+    let store_id = resolve_store(&conn, purchase.store_name.as_deref())?;
+
+    let rows_affected = conn.execute(
+        "UPDATE purchases SET price = ?1, quantity = ?2, package_size = ?3, item_id = ?4, store_id = ?5 WHERE id = ?6",
+        params![
+            purchase.price,
+            purchase.quantity,
+            purchase.package_size,
+            purchase.item_id,
+            store_id,
+            id
+        ],
     )?;
 
-    Ok(purchase_details)
+    if rows_affected == 0 {
+        return Err(OrbitError::NotFound(format!(
+            "No se encontró la compra con ID {}",
+            id
+        )));
+    }
+
+    op.finish();
+
+    fetch_purchase(&conn, id)
+}
+
+#[tauri::command]
+pub fn delete_purchase(state: tauri::State<crate::AppState>, id: i64) -> Result<(), OrbitError> {
+    let conn = state.conn.lock().unwrap();
+    let op = history::begin(&conn, "Eliminar compra")?;
+
+    let rows_affected = conn.execute("DELETE FROM purchases WHERE id = ?1", params![id])?;
+
+    if rows_affected == 0 {
+        return Err(OrbitError::NotFound(format!(
+            "No se encontró la compra con ID {}",
+            id
+        )));
+    }
+
+    op.finish();
+
+    // Los adjuntos de la compra quedan sin vínculo
+    attachments::remove_orphans(&conn, &state.attachments_dir)?;
+
+    Ok(())
+}
+
+/// Reemplaza todas las compras de un movimiento en una sola transacción.
+///
+/// La suma de `price * quantity` debe coincidir con el monto original del
+/// movimiento. Con `allow_other`, puede quedar por debajo y la diferencia se
+/// informa como `other_amount` (lo pagado que no está detallado).
+#[tauri::command]
+pub fn set_movement_purchases(
+    state: tauri::State<crate::AppState>,
+    mov_id: i64,
+    purchases: Vec<MovementPurchaseLine>,
+    allow_other: bool,
+) -> Result<MovementPurchases, OrbitError> {
+    for purchase in &purchases {
+        validate_purchase(purchase.price, purchase.quantity, purchase.package_size)?;
+    }
+
+    let mut conn = state.conn.lock().unwrap();
+
+    let original_amount: i64 = match conn.query_row(
+        "SELECT original_amount FROM movements WHERE id = ?1",
+        params![mov_id],
+        |row| row.get(0),
+    ) {
+        Ok(amount) => amount,
+        Err(rusqlite::Error::QueryReturnedNoRows) => {
+            return Err(OrbitError::NotFound(format!(
+                "No se encontró el movimiento con ID {}",
+                mov_id
+            )))
+        }
+        Err(e) => return Err(OrbitError::Database(e)),
+    };
+
+    let total: i64 = purchases
+        .iter()
+        .map(|p| line_total(p.price, p.quantity))
+        .sum();
+    let other_amount = original_amount - total;
+
+    if other_amount < 0 || (other_amount > 0 && !allow_other) {
+        return Err(OrbitError::ValidationError(format!(
+            "La suma de las compras ({}) no coincide con el monto del movimiento ({})",
+            total, original_amount
+        )));
+    }
+
+    let tx = conn.transaction()?;
+    let op = history::begin(&tx, "Editar compras del movimiento")?;
+
+    tx.execute("DELETE FROM purchases WHERE mov_id = ?1", params![mov_id])?;
+
+    for purchase in &purchases {
+        let store_id = resolve_store(&tx, purchase.store_name.as_deref())?;
+        tx.execute(
+            "INSERT INTO purchases (price, quantity, package_size, mov_id, item_id, store_id) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                purchase.price,
+                purchase.quantity,
+                purchase.package_size,
+                mov_id,
+                purchase.item_id,
+                store_id
+            ],
+        )?;
+    }
+
+    op.finish();
+    tx.commit()?;
+
+    // Las compras reemplazadas pueden haber dejado adjuntos sin vínculo
+    attachments::remove_orphans(&conn, &state.attachments_dir)?;
+
+    let mut stmt = conn.prepare(&format!(
+        "{PURCHASE_SELECT} WHERE p.mov_id = ?1 ORDER BY p.id ASC"
+    ))?;
+    let purchases = stmt
+        .query_map(params![mov_id], row_to_purchase)?
+        .collect::<Result<Vec<PurchaseWithDetails>, rusqlite::Error>>()?;
+
+    Ok(MovementPurchases {
+        purchases,
+        other_amount,
+    })
 }
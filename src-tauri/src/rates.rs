@@ -0,0 +1,197 @@
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    errors::OrbitError,
+    history,
+    movements::RateType,
+    utils::{format_date, parse_date},
+};
+
+/// Historical USD quote of one rate type on one date.
+#[derive(Debug, Clone, Serialize)]
+pub struct ExchangeRate {
+    pub id: i64,
+    pub date: String,
+    pub rate_type: String,
+    /// ARS cents per USD, same unit as `movements.exchange_rate`
+    pub rate: i64,
+}
+
+/// Which rate converts each movement to USD.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum UsdRateSource {
+    /// USD movements keep their original amount (the rate recorded when they
+    /// were registered); ARS movements use the historical rate of their date
+    #[default]
+    Recorded,
+    /// Every movement is converted at the historical rate of its date
+    Historical,
+}
+
+/// Options to express aggregates in USD alongside ARS.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsdConversion {
+    pub rate_type: RateType,
+    #[serde(default)]
+    pub source: UsdRateSource,
+}
+
+impl UsdConversion {
+    /// Cotización vigente en la fecha de la fila `alias`: la última cargada
+    /// hasta ese día o, si la fecha es anterior a la serie, la primera.
+    fn rate_expr(&self, alias: &str) -> String {
+        // rate_type sale de un enum (whitelist), nunca de texto del usuario.
+        let rate_type = self.rate_type.as_db_str();
+        format!(
+            "COALESCE(\
+             (SELECT r.rate FROM exchange_rates r \
+              WHERE r.rate_type = '{rate_type}' AND r.date <= {alias}.date \
+              ORDER BY r.date DESC LIMIT 1), \
+             (SELECT r.rate FROM exchange_rates r \
+              WHERE r.rate_type = '{rate_type}' \
+              ORDER BY r.date ASC LIMIT 1))"
+        )
+    }
+
+    /// Expresión SQL con el monto en centavos de USD de la fila `alias`, que
+    /// debe tener las columnas de `v_movement_lines` (date, currency,
+    /// original_amount, ars_amount).
+    pub(crate) fn amount_expr(&self, alias: &str) -> String {
+        let converted = format!("{alias}.ars_amount * 100.0 / {}", self.rate_expr(alias));
+        match self.source {
+            UsdRateSource::Recorded => format!(
+                "CASE WHEN {alias}.currency = 'USD' THEN {alias}.original_amount ELSE {converted} END"
+            ),
+            UsdRateSource::Historical => converted,
+        }
+    }
+
+    /// Falla si no hay ninguna cotización cargada del tipo elegido: sin ella
+    /// las conversiones darían NULL y los totales quedarían incompletos.
+    pub(crate) fn ensure_rates(&self, conn: &rusqlite::Connection) -> Result<(), OrbitError> {
+        latest_rate(conn, self.rate_type).map(|_| ())
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Última cotización cargada del tipo indicado.
+pub(crate) fn latest_rate(
+    conn: &rusqlite::Connection,
+    rate_type: RateType,
+) -> Result<i64, OrbitError> {
+    conn.query_row(
+        "SELECT rate FROM exchange_rates WHERE rate_type = ?1 ORDER BY date DESC LIMIT 1",
+        params![rate_type.as_db_str()],
+        |row| row.get(0),
+    )
+    .map_err(|e| match e {
+        rusqlite::Error::QueryReturnedNoRows => OrbitError::ValidationError(format!(
+            "No hay cotizaciones {} cargadas",
+            rate_type.as_db_str()
+        )),
+        e => OrbitError::Database(e),
+    })
+}
+
+/// Centavos de ARS a centavos de USD con una cotización en centavos por USD.
+pub(crate) fn ars_to_usd(amount: i64, rate: i64) -> i64 {
+    (amount as f64 * 100.0 / rate as f64).round() as i64
+}
+
+/// Centavos de USD a centavos de ARS.
+pub(crate) fn usd_to_ars(amount: i64, rate: i64) -> i64 {
+    (amount as f64 * rate as f64 / 100.0).round() as i64
+}
+
+const RATE_SELECT: &str = "SELECT id, date, rate_type, rate FROM exchange_rates";
+
+fn row_to_rate(row: &rusqlite::Row) -> rusqlite::Result<ExchangeRate> {
+    Ok(ExchangeRate {
+        id: row.get(0)?,
+        date: row.get(1)?,
+        rate_type: row.get(2)?,
+        rate: row.get(3)?,
+    })
+}
+
+// ---------------------------------------------------------------------------
+// Commands
+// ---------------------------------------------------------------------------
+
+/// Cotizaciones cargadas, de la más reciente a la más vieja.
+#[tauri::command]
+pub fn get_exchange_rates(
+    state: tauri::State<crate::AppState>,
+    rate_type: Option<RateType>,
+) -> Result<Vec<ExchangeRate>, OrbitError> {
+    let conn = state.conn.lock().unwrap();
+    let mut stmt = conn.prepare(&format!(
+        "{RATE_SELECT} WHERE ?1 IS NULL OR rate_type = ?1 ORDER BY date DESC, rate_type ASC"
+    ))?;
+    let rates = stmt
+        .query_map(params![rate_type.map(RateType::as_db_str)], row_to_rate)?
+        .collect::<Result<Vec<ExchangeRate>, rusqlite::Error>>()?;
+
+    Ok(rates)
+}
+
+/// Carga la cotización de un día; si ya había una del mismo tipo, la reemplaza.
+#[tauri::command]
+pub fn set_exchange_rate(
+    state: tauri::State<crate::AppState>,
+    date: String,
+    rate_type: RateType,
+    rate: i64,
+) -> Result<ExchangeRate, OrbitError> {
+    let date = format_date(parse_date(&date)?);
+    if rate <= 0 {
+        return Err(OrbitError::ValidationError(
+            "La cotización debe ser mayor a cero".into(),
+        ));
+    }
+
+    let conn = state.conn.lock().unwrap();
+    let op = history::begin(&conn, "Cargar cotización")?;
+
+    conn.execute(
+        "INSERT INTO exchange_rates (date, rate_type, rate) VALUES (?1, ?2, ?3)
+         ON CONFLICT (rate_type, date) DO UPDATE SET rate = excluded.rate",
+        params![date, rate_type.as_db_str(), rate],
+    )?;
+
+    let rate = conn.query_row(
+        &format!("{RATE_SELECT} WHERE rate_type = ?1 AND date = ?2"),
+        params![rate_type.as_db_str(), date],
+        row_to_rate,
+    )?;
+
+    op.finish();
+
+    Ok(rate)
+}
+
+#[tauri::command]
+pub fn delete_exchange_rate(
+    state: tauri::State<crate::AppState>,
+    id: i64,
+) -> Result<(), OrbitError> {
+    let conn = state.conn.lock().unwrap();
+    let op = history::begin(&conn, "Eliminar cotización")?;
+
+    let deleted = conn.execute("DELETE FROM exchange_rates WHERE id = ?1", params![id])?;
+    if deleted == 0 {
+        return Err(OrbitError::NotFound(format!(
+            "No se encontró la cotización con ID {id}"
+        )));
+    }
+
+    op.finish();
+
+    Ok(())
+}
@@ -0,0 +1,699 @@
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    errors::OrbitError,
+    history,
+    movements::{row_to_movement, Movement, MOVEMENT_SELECT},
+    purchases::{find_or_create_store, line_total},
+    utils::{format_date, normalize_text, parse_date, text_similarity},
+};
+
+/// Minimum similarity to suggest an existing item for a receipt line.
+const ITEM_MATCH_THRESHOLD: f64 = 0.6;
+/// Minimum similarity to reuse an existing store for the receipt header.
+const STORE_MATCH_THRESHOLD: f64 = 0.8;
+
+/// Movement proposed from a receipt, to be reviewed by the user.
+#[derive(Debug, Clone, Serialize)]
+pub struct DraftMovement {
+    pub details: String,
+    /// Date found on the receipt (YYYY-MM-DD), if any
+    pub date: Option<String>,
+    /// Total printed on the receipt, or the sum of the lines if none was found
+    pub amount: i64,
+    pub store_id: Option<i64>,
+    pub store_name: Option<String>,
+}
+
+/// A receipt line converted into a purchase proposal.
+#[derive(Debug, Clone, Serialize)]
+pub struct DraftPurchase {
+    /// Line description as printed on the receipt
+    pub description: String,
+    /// Fractional for items sold by weight (0.350 kg)
+    pub quantity: f64,
+    /// Unit price in cents
+    pub price: i64,
+    /// `price * quantity`, as printed on the receipt
+    pub line_total: i64,
+    /// Best matching existing item, if similar enough
+    pub item_id: Option<i64>,
+    pub item_name: Option<String>,
+    /// Similarity (0.0 - 1.0) between the description and the suggested item
+    pub match_score: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReceiptDraft {
+    pub movement: DraftMovement,
+    pub purchases: Vec<DraftPurchase>,
+    /// Total printed on the receipt, if found
+    pub total: Option<i64>,
+    /// Sum of the parsed lines; differs from `total` when a line was missed
+    pub lines_total: i64,
+    /// Lines with an amount that could not be interpreted (discounts, taxes...)
+    pub unparsed_lines: Vec<String>,
+}
+
+/// A purchase as confirmed by the user. If `item_id` is `None`, a new item
+/// named `item_name` is created.
+#[derive(Debug, Deserialize)]
+pub struct ConfirmReceiptPurchase {
+    pub item_id: Option<i64>,
+    pub item_name: Option<String>,
+    pub price: i64,
+    pub quantity: f64,
+    #[serde(default)]
+    pub package_size: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ConfirmReceipt {
+    pub details: String,
+    pub date: String,
+    pub account_id: i64,
+    pub category_id: Option<i64>,
+    pub store_name: Option<String>,
+    pub purchases: Vec<ConfirmReceiptPurchase>,
+    /// Total printed on the receipt (`ReceiptDraft::total`); when present the
+    /// purchases must add up to it
+    #[serde(default)]
+    pub total: Option<i64>,
+}
+
+// ---------------------------------------------------------------------------
+// Parsing
+// ---------------------------------------------------------------------------
+
+/// Resultado del parseo del texto, antes de cruzarlo con la base.
+#[derive(Debug, Default)]
+struct ParsedReceipt {
+    store_name: Option<String>,
+    date: Option<String>,
+    total: Option<i64>,
+    lines: Vec<ParsedLine>,
+    unparsed: Vec<String>,
+}
+
+#[derive(Debug, PartialEq)]
+struct ParsedLine {
+    description: String,
+    quantity: f64,
+    price: i64,
+    line_total: i64,
+}
+
+/// Líneas que tienen monto pero no son ítems.
+const NON_ITEM_KEYWORDS: &[&str] = &[
+    "subtotal",
+    "iva",
+    "cuit",
+    "vuelto",
+    "cambio",
+    "efectivo",
+    "tarjeta",
+    "debito",
+    "credito",
+    "recibido",
+    "pago",
+    "descuento",
+    "desc",
+    "bonif",
+    "redondeo",
+    "percep",
+    "impuesto",
+];
+
+/// Líneas del encabezado que no son el nombre del comercio.
+const HEADER_KEYWORDS: &[&str] = &[
+    "cuit",
+    "ticket",
+    "factura",
+    "fecha",
+    "hora",
+    "iva",
+    "consumidor",
+    "caja",
+    "cajero",
+    "nro",
+    "tel",
+    "ingresos brutos",
+    "inicio de actividades",
+    "punto de venta",
+    "original",
+];
+
+/// Convierte un monto impreso a centavos. Acepta formato argentino
+/// ("1.234,56"), con punto decimal ("1234.56") y enteros ("$1.500").
+fn parse_amount(token: &str) -> Option<i64> {
+    let token = token.trim().trim_start_matches('$').trim();
+    let (negative, token) = match token.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, token),
+    };
+
+    if token.is_empty()
+        || !token
+            .chars()
+            .all(|c| c.is_ascii_digit() || c == '.' || c == ',')
+    {
+        return None;
+    }
+    if !token.starts_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+
+    // El separador decimal es el último '.' o ',' seguido de 1 o 2 dígitos;
+    // cualquier otro separador es de miles.
+    let decimal_pos = token
+        .rfind(['.', ','])
+        .filter(|&pos| (1..=2).contains(&(token.len() - pos - 1)));
+
+    let (integer, decimals) = match decimal_pos {
+        Some(pos) => (&token[..pos], &token[pos + 1..]),
+        None => (token, ""),
+    };
+
+    let integer: String = integer.chars().filter(|c| c.is_ascii_digit()).collect();
+    let integer: i64 = if integer.is_empty() {
+        0
+    } else {
+        integer.parse().ok()?
+    };
+    let cents: i64 = match decimals.len() {
+        0 => 0,
+        1 => decimals.parse::<i64>().ok()? * 10,
+        _ => decimals.parse().ok()?,
+    };
+
+    let amount = integer.checked_mul(100)?.checked_add(cents)?;
+    Some(if negative { -amount } else { amount })
+}
+
+/// Cantidad impresa ("2", "0,750", "1.5") como número.
+fn parse_quantity(token: &str) -> Option<f64> {
+    let token = token.trim().to_lowercase();
+    let token = token
+        .trim_end_matches(['x', 'u'])
+        .trim_end_matches("un")
+        .replace(',', ".");
+    token.parse::<f64>().ok().filter(|q| *q > 0.0)
+}
+
+/// Busca una fecha dd/mm/aaaa, dd-mm-aa, dd.mm.aaaa o aaaa-mm-dd en la línea.
+fn find_date(line: &str) -> Option<String> {
+    for token in line.split(|c: char| c.is_whitespace() || c == ':') {
+        let parts: Vec<&str> = token.split(['/', '-', '.']).collect();
+        if parts.len() != 3 || parts.iter().any(|p| !p.chars().all(|c| c.is_ascii_digit())) {
+            continue;
+        }
+
+        let numbers: Vec<i32> = match parts.iter().map(|p| p.parse::<i32>()).collect() {
+            Ok(numbers) => numbers,
+            Err(_) => continue,
+        };
+
+        let (year, month, day) = if parts[0].len() == 4 {
+            (numbers[0], numbers[1], numbers[2])
+        } else {
+            let year = if parts[2].len() == 2 {
+                2000 + numbers[2]
+            } else {
+                numbers[2]
+            };
+            (year, numbers[1], numbers[0])
+        };
+
+        if let Some(date) = chrono::NaiveDate::from_ymd_opt(year, month as u32, day as u32) {
+            return Some(format_date(date));
+        }
+    }
+
+    None
+}
+
+/// Separa el monto final de la línea: "LECHE 1L   1.250,00" => ("LECHE 1L", 125000).
+fn split_trailing_amount(line: &str) -> Option<(String, i64)> {
+    let mut tokens: Vec<&str> = line.split_whitespace().collect();
+    let last = tokens.pop()?;
+    let amount = parse_amount(last)?;
+
+    // "$ 1.250,00": el signo quedó como token aparte
+    if tokens.last() == Some(&"$") {
+        tokens.pop();
+    }
+
+    Some((tokens.join(" "), amount))
+}
+
+/// Busca "cantidad x precio" en el texto ("2 x 150,00", "2x $150").
+/// Devuelve la cantidad, el precio unitario y el texto sin ese fragmento.
+fn split_quantity(text: &str) -> Option<(f64, i64, String)> {
+    let tokens: Vec<&str> = text.split_whitespace().collect();
+
+    for i in 0..tokens.len() {
+        let lower = tokens[i].to_lowercase();
+
+        // "2x" seguido del precio, o "2" "x" precio
+        let (quantity, price_idx) = if lower.ends_with('x') && lower.len() > 1 {
+            (parse_quantity(&lower), i + 1)
+        } else if lower == "x" && i > 0 {
+            (parse_quantity(tokens[i - 1]), i + 1)
+        } else {
+            continue;
+        };
+        let Some(quantity) = quantity else { continue };
+
+        // El precio puede venir con el '$' separado
+        let price_idx = if tokens.get(price_idx) == Some(&"$") {
+            price_idx + 1
+        } else {
+            price_idx
+        };
+        let Some(price) = tokens.get(price_idx).and_then(|t| parse_amount(t)) else {
+            continue;
+        };
+
+        let start = if lower == "x" { i - 1 } else { i };
+        let rest: Vec<&str> = tokens[..start]
+            .iter()
+            .chain(&tokens[price_idx + 1..])
+            .copied()
+            .collect();
+
+        return Some((quantity, price, rest.join(" ")));
+    }
+
+    None
+}
+
+fn has_letters(text: &str) -> bool {
+    text.chars().filter(|c| c.is_alphabetic()).count() >= 2
+}
+
+fn contains_keyword(normalized: &str, keywords: &[&str]) -> bool {
+    keywords.iter().any(|k| {
+        normalized
+            .split(' ')
+            .collect::<Vec<&str>>()
+            .windows(k.split(' ').count())
+            .any(|w| w.join(" ") == *k)
+    })
+}
+
+/// Arma la línea de compra. Sin precio unitario impreso, se deduce del total.
+fn build_line(
+    description: &str,
+    quantity: f64,
+    unit_price: Option<i64>,
+    line_total: i64,
+) -> ParsedLine {
+    ParsedLine {
+        description: description.trim().to_string(),
+        quantity,
+        price: unit_price.unwrap_or((line_total as f64 / quantity).round() as i64),
+        line_total,
+    }
+}
+
+/// Interpreta el texto de un ticket línea por línea.
+fn parse_receipt_text(text: &str) -> ParsedReceipt {
+    let mut receipt = ParsedReceipt::default();
+    // "2 x 150,00" en una línea propia se aplica al ítem de la línea siguiente
+    let mut pending_quantity: Option<(f64, i64)> = None;
+
+    for (index, raw) in text.lines().enumerate() {
+        let line = raw.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let normalized = normalize_text(line);
+
+        if receipt.date.is_none() {
+            if let Some(date) = find_date(line) {
+                receipt.date = Some(date);
+                continue;
+            }
+        }
+
+        let trailing = split_trailing_amount(line);
+
+        // Total: la primera línea "TOTAL" con monto (no SUBTOTAL)
+        if normalized.split(' ').any(|w| w == "total") {
+            if let Some((_, amount)) = trailing {
+                receipt.total.get_or_insert(amount);
+            }
+            continue;
+        }
+
+        if let Some((quantity, price, rest)) = split_quantity(line) {
+            if !has_letters(&rest.replace(['x', 'X'], "")) {
+                pending_quantity = Some((quantity, price));
+                continue;
+            }
+        }
+
+        let Some((text_part, amount)) = trailing else {
+            // Nombre del comercio: la primera línea de texto del encabezado
+            if receipt.store_name.is_none()
+                && index < 6
+                && has_letters(line)
+                && !contains_keyword(&normalized, HEADER_KEYWORDS)
+            {
+                receipt.store_name = Some(line.to_string());
+            }
+            continue;
+        };
+
+        if contains_keyword(&normalized, NON_ITEM_KEYWORDS)
+            || amount <= 0
+            || !has_letters(&text_part)
+        {
+            receipt.unparsed.push(line.to_string());
+            pending_quantity = None;
+            continue;
+        }
+
+        let parsed = match split_quantity(&text_part) {
+            Some((quantity, price, description)) => {
+                build_line(&description, quantity, Some(price), amount)
+            }
+            None => match pending_quantity.take() {
+                Some((quantity, price)) => build_line(&text_part, quantity, Some(price), amount),
+                None => build_line(&text_part, 1.0, None, amount),
+            },
+        };
+
+        pending_quantity = None;
+        receipt.lines.push(parsed);
+    }
+
+    receipt
+}
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Cruza lo parseado con los ítems y tiendas existentes.
+fn build_draft(
+    conn: &rusqlite::Connection,
+    parsed: ParsedReceipt,
+) -> Result<ReceiptDraft, OrbitError> {
+    let mut stmt = conn.prepare("SELECT id, name, brand FROM items WHERE is_archived = 0")?;
+    let items = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, Option<String>>(2)?,
+            ))
+        })?
+        .collect::<Result<Vec<(i64, String, Option<String>)>, rusqlite::Error>>()?;
+
+    let purchases: Vec<DraftPurchase> = parsed
+        .lines
+        .into_iter()
+        .map(|line| {
+            // Se compara con el nombre solo y con "nombre marca"
+            let best = items
+                .iter()
+                .map(|(id, name, brand)| {
+                    let with_brand = match brand {
+                        Some(brand) => format!("{name} {brand}"),
+                        None => name.clone(),
+                    };
+                    let score = text_similarity(&line.description, name)
+                        .max(text_similarity(&line.description, &with_brand));
+                    (*id, name, score)
+                })
+                .max_by(|a, b| a.2.total_cmp(&b.2))
+                .filter(|(_, _, score)| *score >= ITEM_MATCH_THRESHOLD);
+
+            DraftPurchase {
+                description: line.description,
+                quantity: line.quantity,
+                price: line.price,
+                line_total: line.line_total,
+                item_id: best.map(|(id, _, _)| id),
+                item_name: best.map(|(_, name, _)| name.clone()),
+                match_score: best
+                    .map(|(_, _, score)| (score * 100.0).round() / 100.0)
+                    .unwrap_or(0.0),
+            }
+        })
+        .collect();
+
+    let mut store_id = None;
+    let mut store_name = parsed.store_name;
+    if let Some(name) = &store_name {
+        // Se compara contra el nombre y los alias, pero se muestra el nombre
+        let mut stmt = conn.prepare(
+            "SELECT id, name, name FROM stores
+             UNION ALL
+             SELECT s.id, s.name, a.alias FROM store_aliases a JOIN stores s ON s.id = a.store_id",
+        )?;
+        let best = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                ))
+            })?
+            .collect::<Result<Vec<(i64, String, String)>, rusqlite::Error>>()?
+            .into_iter()
+            .map(|(id, existing, candidate)| (text_similarity(name, &candidate), id, existing))
+            .max_by(|a, b| a.0.total_cmp(&b.0))
+            .filter(|(score, _, _)| *score >= STORE_MATCH_THRESHOLD);
+
+        if let Some((_, id, existing)) = best {
+            store_id = Some(id);
+            store_name = Some(existing);
+        }
+    }
+
+    let lines_total: i64 = purchases.iter().map(|p| p.line_total).sum();
+
+    Ok(ReceiptDraft {
+        movement: DraftMovement {
+            details: store_name.clone().unwrap_or_else(|| "Compra".to_string()),
+            date: parsed.date,
+            amount: parsed.total.unwrap_or(lines_total),
+            store_id,
+            store_name,
+        },
+        purchases,
+        total: parsed.total,
+        lines_total,
+        unparsed_lines: parsed.unparsed,
+    })
+}
+
+// ---------------------------------------------------------------------------
+// Commands
+// ---------------------------------------------------------------------------
+
+/// Interpreta el texto de un ticket (copiado o extraído por OCR) y devuelve un
+/// borrador de movimiento con sus compras. No guarda nada.
+#[tauri::command]
+pub fn parse_receipt(
+    state: tauri::State<crate::AppState>,
+    text: String,
+) -> Result<ReceiptDraft, OrbitError> {
+    let parsed = parse_receipt_text(&text);
+    if parsed.lines.is_empty() {
+        return Err(OrbitError::ValidationError(
+            "No se encontraron ítems en el ticket".into(),
+        ));
+    }
+
+    let conn = state.conn.lock().unwrap();
+    build_draft(&conn, parsed)
+}
+
+/// Igual que `parse_receipt`, pero a partir de un PDF con capa de texto
+/// (facturas electrónicas). Los PDF escaneados no tienen texto que extraer.
+#[tauri::command]
+pub fn parse_receipt_pdf(
+    state: tauri::State<crate::AppState>,
+    path: String,
+) -> Result<ReceiptDraft, OrbitError> {
+    // pdf_extract entra en pánico con algunos PDF dañados o cifrados: el
+    // archivo lo elige el usuario, así que no puede tirar abajo el comando
+    let text = std::panic::catch_unwind(|| pdf_extract::extract_text(&path))
+        .map_err(|_| {
+            OrbitError::ValidationError(
+                "No se pudo leer el PDF: el archivo está dañado o cifrado".into(),
+            )
+        })?
+        .map_err(|e| OrbitError::ValidationError(format!("No se pudo leer el PDF: {e}")))?;
+
+    parse_receipt(state, text)
+}
+
+/// Guarda el ticket revisado: crea el movimiento (gasto en ARS) y todas sus
+/// compras en una sola transacción. Los ítems sin `item_id` se crean.
+///
+/// Si se pasa el total impreso, las compras tienen que sumarlo (con hasta un
+/// centavo de redondeo por línea): un descuento o una línea que no se leyó
+/// se corrigen en el borrador, no se guardan en silencio.
+#[tauri::command]
+pub fn confirm_receipt(
+    state: tauri::State<crate::AppState>,
+    receipt: ConfirmReceipt,
+) -> Result<Movement, OrbitError> {
+    if receipt.purchases.is_empty() {
+        return Err(OrbitError::ValidationError(
+            "El ticket debe tener al menos una compra".into(),
+        ));
+    }
+    if receipt
+        .purchases
+        .iter()
+        .any(|p| p.quantity.is_nan() || p.quantity <= 0.0 || p.price < 0)
+    {
+        return Err(OrbitError::ValidationError(
+            "Cada compra debe tener cantidad mayor a cero y precio no negativo".into(),
+        ));
+    }
+    if receipt
+        .purchases
+        .iter()
+        .any(|p| p.item_id.is_none() && p.item_name.as_deref().is_none_or(|n| n.trim().is_empty()))
+    {
+        return Err(OrbitError::ValidationError(
+            "Las compras sin ítem existente deben indicar un nombre".into(),
+        ));
+    }
+    parse_date(&receipt.date)?;
+
+    let amount: i64 = receipt
+        .purchases
+        .iter()
+        .map(|p| line_total(p.price, p.quantity))
+        .sum();
+    if let Some(total) = receipt.total {
+        if (total - amount).abs() > receipt.purchases.len() as i64 {
+            return Err(OrbitError::ValidationError(format!(
+                "Las compras suman {:.2} pero el total del ticket es {:.2}",
+                amount as f64 / 100.0,
+                total as f64 / 100.0
+            )));
+        }
+    }
+
+    let mut conn = state.conn.lock().unwrap();
+    let tx = conn.transaction()?;
+    let op = history::begin(&tx, "Cargar ticket")?;
+
+    tx.execute(
+        "INSERT INTO movements (details, date, mov_type, currency, original_amount, ars_amount, account_id, category_id)
+         VALUES (?1, ?2, 'expense', 'ARS', ?3, ?3, ?4, ?5)",
+        params![receipt.details, receipt.date, amount, receipt.account_id, receipt.category_id],
+    )?;
+    let mov_id = tx.last_insert_rowid();
+
+    let store_id: Option<i64> = receipt
+        .store_name
+        .as_deref()
+        .filter(|s| !s.trim().is_empty())
+        .map(|name| find_or_create_store(&tx, name.trim()))
+        .transpose()?;
+
+    for purchase in &receipt.purchases {
+        let item_id = match purchase.item_id {
+            Some(id) => id,
+            None => {
+                let name = purchase.item_name.as_deref().unwrap_or_default().trim();
+                tx.execute("INSERT INTO items (name) VALUES (?1)", params![name])?;
+                tx.last_insert_rowid()
+            }
+        };
+
+        tx.execute(
+            "INSERT INTO purchases (price, quantity, package_size, mov_id, item_id, store_id) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                purchase.price,
+                purchase.quantity,
+                purchase.package_size,
+                mov_id,
+                item_id,
+                store_id
+            ],
+        )?;
+    }
+
+    op.finish();
+    tx.commit()?;
+
+    let movement = conn.query_row(
+        &format!("{MOVEMENT_SELECT} WHERE id = ?1"),
+        params![mov_id],
+        row_to_movement,
+    )?;
+
+    Ok(movement)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_amount_formats() {
+        assert_eq!(parse_amount("1.234,56"), Some(123456));
+        assert_eq!(parse_amount("1234.56"), Some(123456));
+        assert_eq!(parse_amount("$1.500"), Some(150000));
+        assert_eq!(parse_amount("12,5"), Some(1250));
+        assert_eq!(parse_amount("-300,00"), Some(-30000));
+        assert_eq!(parse_amount("1L"), None);
+    }
+
+    #[test]
+    fn test_parse_receipt_text() {
+        let text = "SUPERMERCADO DIA\n\
+                    CUIT 30-12345678-9\n\
+                    Fecha: 14/03/2025 10:32\n\
+                    2 x 1.250,00\n\
+                    LECHE ENTERA 1L          2.500,00\n\
+                    PAN LACTAL 3 x $900     2.700,00\n\
+                    QUESO CREMOSO 0,350 x 8.000,00   2.800,00\n\
+                    SUBTOTAL                 8.000,00\n\
+                    TOTAL                  $ 8.000,00\n\
+                    EFECTIVO                10.000,00";
+
+        let receipt = parse_receipt_text(text);
+
+        assert_eq!(receipt.store_name.as_deref(), Some("SUPERMERCADO DIA"));
+        assert_eq!(receipt.date.as_deref(), Some("2025-03-14"));
+        assert_eq!(receipt.total, Some(800000));
+        assert_eq!(
+            receipt.lines,
+            vec![
+                ParsedLine {
+                    description: "LECHE ENTERA 1L".into(),
+                    quantity: 2.0,
+                    price: 125000,
+                    line_total: 250000,
+                },
+                ParsedLine {
+                    description: "PAN LACTAL".into(),
+                    quantity: 3.0,
+                    price: 90000,
+                    line_total: 270000,
+                },
+                // Por peso: el precio impreso es por kg
+                ParsedLine {
+                    description: "QUESO CREMOSO".into(),
+                    quantity: 0.35,
+                    price: 800000,
+                    line_total: 280000,
+                },
+            ]
+        );
+        assert_eq!(receipt.unparsed.len(), 2);
+    }
+}
@@ -0,0 +1,441 @@
+use std::collections::HashMap;
+
+use rusqlite::params;
+use serde::Serialize;
+
+use crate::{
+    cpi::CpiSeries,
+    errors::OrbitError,
+    movements::RateType,
+    rates::{ars_to_usd, latest_rate, usd_to_ars, UsdConversion},
+    utils::{month_range, parse_month},
+};
+
+/// Maximum number of months a report can span.
+const MAX_REPORT_MONTHS: usize = 120;
+
+/// Change of a value against the previous month.
+#[derive(Debug, Clone, Serialize)]
+pub struct MonthDelta {
+    pub amount: i64,
+    /// `None` when the previous month was zero
+    pub percent: Option<f64>,
+}
+
+/// Monthly values (ARS cents), one per month of the report.
+#[derive(Debug, Clone, Serialize)]
+pub struct MonthlySeries {
+    pub values: Vec<i64>,
+    pub total: i64,
+    /// Average per month, counting months without movements as zero
+    pub average: i64,
+    /// Against the previous month; `None` for the first month
+    pub deltas: Vec<Option<MonthDelta>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CategoryRow {
+    pub category: String,
+    #[serde(flatten)]
+    pub series: MonthlySeries,
+}
+
+/// Month × category matrix, categories ordered by total descending.
+#[derive(Debug, Clone, Serialize)]
+pub struct CategoryMatrix {
+    pub categories: Vec<CategoryRow>,
+    /// Sum of every category per month
+    pub totals: MonthlySeries,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MonthlyReport {
+    /// YYYY-MM, consecutive; every series has one value per month
+    pub months: Vec<String>,
+    pub income: CategoryMatrix,
+    pub expense: CategoryMatrix,
+    /// Income minus expense
+    pub net: MonthlySeries,
+    /// Same report in constant pesos, when a reference month is requested
+    pub constant: Option<ConstantReport>,
+    /// Same report in USD, when a conversion is requested
+    pub usd: Option<UsdReport>,
+}
+
+/// Report amounts deflated by CPI to pesos of `reference_month`.
+#[derive(Debug, Clone, Serialize)]
+pub struct ConstantReport {
+    pub reference_month: String,
+    /// Multiplier applied to each month's nominal amounts
+    pub factors: Vec<f64>,
+    pub income: CategoryMatrix,
+    pub expense: CategoryMatrix,
+    pub net: MonthlySeries,
+}
+
+/// Report amounts converted to USD cents.
+#[derive(Debug, Clone, Serialize)]
+pub struct UsdReport {
+    pub conversion: UsdConversion,
+    pub income: CategoryMatrix,
+    pub expense: CategoryMatrix,
+    pub net: MonthlySeries,
+}
+
+/// Current balance of one account, in its currency and in both ARS and USD (cents).
+#[derive(Debug, Clone, Serialize)]
+pub struct AccountWorth {
+    pub account_id: i64,
+    pub account_name: String,
+    pub currency: String,
+    pub balance: i64,
+    pub balance_ars: i64,
+    pub balance_usd: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct NetWorth {
+    pub rate_type: RateType,
+    /// Latest stored rate of `rate_type`, ARS cents per USD
+    pub rate: i64,
+    pub accounts: Vec<AccountWorth>,
+    pub total_ars: i64,
+    pub total_usd: i64,
+}
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+fn build_series(values: Vec<i64>) -> MonthlySeries {
+    let total: i64 = values.iter().sum();
+    let average = if values.is_empty() {
+        0
+    } else {
+        (total as f64 / values.len() as f64).round() as i64
+    };
+
+    let deltas = std::iter::once(None)
+        .chain(values.windows(2).map(|pair| {
+            let (previous, current) = (pair[0], pair[1]);
+            Some(MonthDelta {
+                amount: current - previous,
+                percent: (previous != 0).then(|| {
+                    ((current - previous) as f64 / previous.abs() as f64 * 10_000.0).round() / 100.0
+                }),
+            })
+        }))
+        .take(values.len())
+        .collect();
+
+    MonthlySeries {
+        values,
+        total,
+        average,
+        deltas,
+    }
+}
+
+/// Arma la matriz mes × categoría a partir de una de las vistas mensuales.
+/// `view` debe ser un literal del código.
+fn category_matrix(
+    conn: &rusqlite::Connection,
+    view: &str,
+    months: &[String],
+) -> Result<CategoryMatrix, OrbitError> {
+    let (Some(first), Some(last)) = (months.first(), months.last()) else {
+        return Ok(matrix_from_rows(Vec::new(), months));
+    };
+
+    let mut stmt = conn.prepare(&format!(
+        "SELECT month, category, total_ars FROM {view}
+         WHERE month BETWEEN ?1 AND ?2"
+    ))?;
+    let rows = stmt
+        .query_map(params![first, last], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, i64>(2)?,
+            ))
+        })?
+        .collect::<Result<Vec<(String, String, i64)>, rusqlite::Error>>()?;
+
+    Ok(matrix_from_rows(rows, months))
+}
+
+/// Igual que `category_matrix` pero en centavos de USD. Las vistas mensuales
+/// solo tienen ARS, así que se agrupa sobre `v_movement_lines` con la misma
+/// lógica. `mov_type` debe ser un literal del código.
+fn usd_category_matrix(
+    conn: &rusqlite::Connection,
+    mov_type: &str,
+    conversion: &UsdConversion,
+    months: &[String],
+) -> Result<CategoryMatrix, OrbitError> {
+    let (Some(first), Some(last)) = (months.first(), months.last()) else {
+        return Ok(matrix_from_rows(Vec::new(), months));
+    };
+
+    let amount = conversion.amount_expr("l");
+    let mut stmt = conn.prepare(&format!(
+        "SELECT strftime('%Y-%m', l.date) AS month,
+                COALESCE(c.name, 'Sin categoría') AS category,
+                CAST(ROUND(SUM({amount})) AS INTEGER) AS total_usd
+         FROM v_movement_lines l
+         LEFT JOIN categories c ON l.category_id = c.id
+         WHERE l.mov_type = '{mov_type}' AND month BETWEEN ?1 AND ?2
+         GROUP BY month, c.name"
+    ))?;
+    let rows = stmt
+        .query_map(params![first, last], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, i64>(2)?,
+            ))
+        })?
+        .collect::<Result<Vec<(String, String, i64)>, rusqlite::Error>>()?;
+
+    Ok(matrix_from_rows(rows, months))
+}
+
+/// Ubica las filas (mes, categoría, monto) en la matriz; los meses fuera de
+/// `months` se descartan.
+fn matrix_from_rows(rows: Vec<(String, String, i64)>, months: &[String]) -> CategoryMatrix {
+    let position: HashMap<&str, usize> = months
+        .iter()
+        .enumerate()
+        .map(|(i, m)| (m.as_str(), i))
+        .collect();
+
+    let mut by_category: HashMap<String, Vec<i64>> = HashMap::new();
+    let mut totals = vec![0; months.len()];
+    for (month, category, amount) in rows {
+        let Some(&i) = position.get(month.as_str()) else {
+            continue;
+        };
+        by_category
+            .entry(category)
+            .or_insert_with(|| vec![0; months.len()])[i] += amount;
+        totals[i] += amount;
+    }
+
+    let mut categories: Vec<CategoryRow> = by_category
+        .into_iter()
+        .map(|(category, values)| CategoryRow {
+            category,
+            series: build_series(values),
+        })
+        .collect();
+    categories.sort_by(|a, b| {
+        b.series
+            .total
+            .cmp(&a.series.total)
+            .then(a.category.cmp(&b.category))
+    });
+
+    CategoryMatrix {
+        categories,
+        totals: build_series(totals),
+    }
+}
+
+/// Lleva una serie nominal a pesos constantes, mes a mes.
+fn deflate_series(series: &MonthlySeries, factors: &[f64]) -> MonthlySeries {
+    build_series(
+        series
+            .values
+            .iter()
+            .zip(factors)
+            .map(|(value, factor)| (*value as f64 * factor).round() as i64)
+            .collect(),
+    )
+}
+
+fn deflate_matrix(matrix: &CategoryMatrix, factors: &[f64]) -> CategoryMatrix {
+    let mut categories: Vec<CategoryRow> = matrix
+        .categories
+        .iter()
+        .map(|row| CategoryRow {
+            category: row.category.clone(),
+            series: deflate_series(&row.series, factors),
+        })
+        .collect();
+    // Con inflación el orden por total puede cambiar respecto del nominal
+    categories.sort_by(|a, b| {
+        b.series
+            .total
+            .cmp(&a.series.total)
+            .then(a.category.cmp(&b.category))
+    });
+
+    CategoryMatrix {
+        categories,
+        totals: deflate_series(&matrix.totals, factors),
+    }
+}
+
+fn net_series(income: &MonthlySeries, expense: &MonthlySeries) -> MonthlySeries {
+    build_series(
+        income
+            .values
+            .iter()
+            .zip(&expense.values)
+            .map(|(income, expense)| income - expense)
+            .collect(),
+    )
+}
+
+// ---------------------------------------------------------------------------
+// Commands
+// ---------------------------------------------------------------------------
+
+/// Reporte mensual de ingresos y gastos por categoría (en ARS) entre dos
+/// meses YYYY-MM, ambos incluidos. Los meses sin movimientos valen cero.
+///
+/// Usa las vistas `v_monthly_income_by_category` y
+/// `v_monthly_expenses_by_category`, que respetan los desgloses.
+///
+/// Con `reference_month` agrega el mismo reporte en pesos constantes de ese
+/// mes, según la serie de IPC cargada (ver cpi.rs).
+#[tauri::command]
+pub fn get_monthly_report(
+    state: tauri::State<crate::AppState>,
+    from: String,
+    to: String,
+    reference_month: Option<String>,
+    usd: Option<UsdConversion>,
+) -> Result<MonthlyReport, OrbitError> {
+    let from = parse_month(&from)?;
+    let to = parse_month(&to)?;
+    let reference_month = reference_month.as_deref().map(parse_month).transpose()?;
+    if from > to {
+        return Err(OrbitError::ValidationError(
+            "El mes inicial no puede ser posterior al final".into(),
+        ));
+    }
+
+    let months = month_range(&from, &to);
+    if months.len() > MAX_REPORT_MONTHS {
+        return Err(OrbitError::ValidationError(format!(
+            "El reporte no puede abarcar más de {MAX_REPORT_MONTHS} meses"
+        )));
+    }
+
+    let conn = state.conn.lock().unwrap();
+
+    let income = category_matrix(&conn, "v_monthly_income_by_category", &months)?;
+    let expense = category_matrix(&conn, "v_monthly_expenses_by_category", &months)?;
+
+    let net = net_series(&income.totals, &expense.totals);
+
+    let constant = match reference_month {
+        Some(reference_month) => {
+            let factors = CpiSeries::load(&conn)?.factors(&months, &reference_month)?;
+            let income = deflate_matrix(&income, &factors);
+            let expense = deflate_matrix(&expense, &factors);
+            let net = net_series(&income.totals, &expense.totals);
+            Some(ConstantReport {
+                reference_month,
+                factors,
+                income,
+                expense,
+                net,
+            })
+        }
+        None => None,
+    };
+
+    let usd = match usd {
+        Some(conversion) => {
+            conversion.ensure_rates(&conn)?;
+            let income = usd_category_matrix(&conn, "income", &conversion, &months)?;
+            let expense = usd_category_matrix(&conn, "expense", &conversion, &months)?;
+            let net = net_series(&income.totals, &expense.totals);
+            Some(UsdReport {
+                conversion,
+                income,
+                expense,
+                net,
+            })
+        }
+        None => None,
+    };
+
+    Ok(MonthlyReport {
+        months,
+        income,
+        expense,
+        net,
+        constant,
+        usd,
+    })
+}
+
+/// Patrimonio actual: saldo de cada cuenta en su moneda y en ARS y USD,
+/// convertido con la última cotización cargada de `rate_type`.
+#[tauri::command]
+pub fn get_net_worth(
+    state: tauri::State<crate::AppState>,
+    rate_type: RateType,
+) -> Result<NetWorth, OrbitError> {
+    let conn = state.conn.lock().unwrap();
+    let rate = latest_rate(&conn, rate_type)?;
+
+    let mut stmt = conn.prepare(
+        "SELECT account_id, account_name, currency, current_balance_original
+         FROM v_account_balance_original
+         ORDER BY account_name ASC",
+    )?;
+    let accounts = stmt
+        .query_map([], |row| {
+            let currency: String = row.get(2)?;
+            let balance: i64 = row.get(3)?;
+            let (balance_ars, balance_usd) = if currency == "USD" {
+                (usd_to_ars(balance, rate), balance)
+            } else {
+                (balance, ars_to_usd(balance, rate))
+            };
+            Ok(AccountWorth {
+                account_id: row.get(0)?,
+                account_name: row.get(1)?,
+                currency,
+                balance,
+                balance_ars,
+                balance_usd,
+            })
+        })?
+        .collect::<Result<Vec<AccountWorth>, rusqlite::Error>>()?;
+
+    Ok(NetWorth {
+        rate_type,
+        rate,
+        total_ars: accounts.iter().map(|a| a.balance_ars).sum(),
+        total_usd: accounts.iter().map(|a| a.balance_usd).sum(),
+        accounts,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_series_totals_and_deltas() {
+        let series = build_series(vec![1000, 0, 1500, 1200]);
+
+        assert_eq!(series.total, 3700);
+        assert_eq!(series.average, 925);
+        assert!(series.deltas[0].is_none());
+
+        let to_zero = series.deltas[1].as_ref().unwrap();
+        assert_eq!((to_zero.amount, to_zero.percent), (-1000, Some(-100.0)));
+
+        // Desde cero no hay porcentaje
+        let from_zero = series.deltas[2].as_ref().unwrap();
+        assert_eq!((from_zero.amount, from_zero.percent), (1500, None));
+
+        assert_eq!(series.deltas[3].as_ref().unwrap().percent, Some(-20.0));
+    }
+}
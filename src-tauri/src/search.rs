@@ -0,0 +1,132 @@
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+
+use crate::errors::OrbitError;
+
+/// Kind of entity a search result points to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchEntity {
+    Movement,
+    Item,
+    Store,
+}
+
+/// A single hit of the global search, ranked by relevance.
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchResult {
+    pub entity: SearchEntity,
+    /// ID of the movement, item or store
+    pub id: i64,
+    /// Main text to display (movement details, item name or store name)
+    pub title: String,
+    /// Secondary text: movement date, item brand or store branch
+    pub subtitle: Option<String>,
+    /// bm25 score: lower is more relevant
+    pub rank: f64,
+}
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Convierte el texto del usuario en una expresión MATCH de FTS5.
+///
+/// Cada palabra se busca como prefijo y todas deben aparecer ("caf leche" =>
+/// `"caf"* "leche"*`). Solo se conservan letras y dígitos, así ningún
+/// carácter del usuario se interpreta como sintaxis de FTS5.
+/// Devuelve `None` si no queda ninguna palabra.
+pub(crate) fn fts_match_query(input: &str) -> Option<String> {
+    let terms: Vec<String> = input
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|t| !t.is_empty())
+        .map(|t| format!("\"{t}\"*"))
+        .collect();
+
+    if terms.is_empty() {
+        None
+    } else {
+        Some(terms.join(" "))
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Commands
+// ---------------------------------------------------------------------------
+
+/// Búsqueda global sobre movimientos, ítems y tiendas.
+/// Ignora acentos y mayúsculas; los resultados vienen ordenados por relevancia.
+#[tauri::command]
+pub fn search(
+    state: tauri::State<crate::AppState>,
+    query: String,
+    limit: Option<i64>,
+) -> Result<Vec<SearchResult>, OrbitError> {
+    let Some(match_query) = fts_match_query(&query) else {
+        return Ok(Vec::new());
+    };
+
+    let conn = state.conn.lock().unwrap();
+
+    let mut stmt = conn.prepare(
+        "SELECT 'movement' AS entity, m.id, m.details, m.date, bm25(movements_fts) AS rank
+         FROM movements_fts
+         JOIN movements m ON m.id = movements_fts.rowid
+         WHERE movements_fts MATCH ?1
+         UNION ALL
+         SELECT 'item', i.id, i.name, i.brand, bm25(items_fts)
+         FROM items_fts
+         JOIN items i ON i.id = items_fts.rowid
+         WHERE items_fts MATCH ?1 AND i.is_archived = 0
+         UNION ALL
+         SELECT 'store', s.id, s.name, s.branch, bm25(stores_fts)
+         FROM stores_fts
+         JOIN stores s ON s.id = stores_fts.rowid
+         WHERE stores_fts MATCH ?1
+         ORDER BY rank ASC
+         LIMIT ?2",
+    )?;
+
+    let results = stmt
+        .query_map(params![match_query, limit.unwrap_or(50)], |row| {
+            let entity = match row.get::<_, String>(0)?.as_str() {
+                "movement" => SearchEntity::Movement,
+                "item" => SearchEntity::Item,
+                _ => SearchEntity::Store,
+            };
+
+            Ok(SearchResult {
+                entity,
+                id: row.get(1)?,
+                title: row.get(2)?,
+                subtitle: row.get(3)?,
+                rank: row.get(4)?,
+            })
+        })?
+        .collect::<Result<Vec<SearchResult>, rusqlite::Error>>()?;
+
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fts_match_query_prefixes_every_word() {
+        assert_eq!(
+            fts_match_query("café con leche"),
+            Some("\"café\"* \"con\"* \"leche\"*".to_string())
+        );
+    }
+
+    #[test]
+    fn test_fts_match_query_strips_fts_syntax() {
+        // Comillas, operadores y paréntesis no deben llegar a la expresión MATCH
+        assert_eq!(
+            fts_match_query("\"coto\" OR (dia*)"),
+            Some("\"coto\"* \"OR\"* \"dia\"*".to_string())
+        );
+        assert_eq!(fts_match_query("  -*\"  "), None);
+    }
+}
@@ -0,0 +1,841 @@
+use std::collections::HashMap;
+
+use rusqlite::{params, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    errors::OrbitError,
+    history,
+    items::ItemUnit,
+    movements::{row_to_movement, Movement, MOVEMENT_SELECT},
+    purchases::{find_or_create_store, line_total},
+    utils::{format_date, parse_date},
+};
+
+/// Days ahead to look for items that are due to be repurchased.
+const DEFAULT_SUGGESTION_HORIZON_DAYS: i64 = 7;
+
+/// Minimum number of distinct purchase dates to estimate a repurchase interval.
+const MIN_PURCHASES_FOR_SUGGESTION: usize = 3;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ShoppingList {
+    pub id: i64,
+    pub name: String,
+    /// Movement created when the list was completed
+    pub mov_id: Option<i64>,
+    /// `None` while the list is open
+    pub completed_at: Option<String>,
+    pub created_at: String,
+    pub item_count: i64,
+    pub checked_count: i64,
+}
+
+/// Last known price of an item at a store, per package, in ARS cents.
+#[derive(Debug, Clone, Serialize)]
+pub struct StorePrice {
+    pub store_id: i64,
+    pub store_name: String,
+    pub price: i64,
+    pub date: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ShoppingListItem {
+    pub id: i64,
+    pub item_id: i64,
+    pub item_name: String,
+    pub brand: Option<String>,
+    pub unit: ItemUnit,
+    /// Desired number of packages
+    pub quantity: f64,
+    pub checked: bool,
+    /// Most recent price paid for the item, at any store
+    pub last_price: Option<i64>,
+    /// `quantity` × `last_price`
+    pub expected_cost: Option<i64>,
+    /// Last known price at each store, cheapest first
+    pub store_prices: Vec<StorePrice>,
+}
+
+/// Expected cost of buying the list at a single store.
+#[derive(Debug, Clone, Serialize)]
+pub struct StoreEstimate {
+    pub store_id: i64,
+    pub store_name: String,
+    /// Sum of the expected cost of the items with a known price at this store
+    pub expected_total: i64,
+    /// How many of the list items have a known price at this store
+    pub items_priced: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ShoppingListDetails {
+    pub list: ShoppingList,
+    pub items: Vec<ShoppingListItem>,
+    /// Sum of `expected_cost` of the items with a known price
+    pub expected_total: i64,
+    /// Cheapest first, among the stores that price the most items
+    pub by_store: Vec<StoreEstimate>,
+}
+
+/// An item that is probably due to be bought again.
+#[derive(Debug, Clone, Serialize)]
+pub struct ShoppingSuggestion {
+    pub item_id: i64,
+    pub item_name: String,
+    pub brand: Option<String>,
+    /// Typical days between purchases (median)
+    pub interval_days: f64,
+    pub last_purchased: String,
+    /// `last_purchased` + `interval_days`
+    pub due_date: String,
+    /// Negative when the due date is still ahead
+    pub days_overdue: i64,
+    /// Usual number of packages per purchase
+    pub typical_quantity: f64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AddShoppingListItem {
+    pub item_id: i64,
+    #[serde(default = "default_quantity")]
+    pub quantity: f64,
+}
+
+fn default_quantity() -> f64 {
+    1.0
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateShoppingListItem {
+    pub quantity: f64,
+    pub checked: bool,
+}
+
+/// Price actually paid for a checked item. Overrides the last known price.
+#[derive(Debug, Deserialize)]
+pub struct CompleteShoppingListLine {
+    pub list_item_id: i64,
+    pub price: i64,
+    /// Defaults to the desired quantity of the list item
+    #[serde(default)]
+    pub quantity: Option<f64>,
+    #[serde(default)]
+    pub package_size: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CompleteShoppingList {
+    /// Defaults to the list name
+    #[serde(default)]
+    pub details: Option<String>,
+    pub date: String,
+    pub account_id: i64,
+    #[serde(default)]
+    pub category_id: Option<i64>,
+    #[serde(default)]
+    pub store_name: Option<String>,
+    #[serde(default)]
+    pub lines: Vec<CompleteShoppingListLine>,
+}
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Intervalo típico en días entre compras: la mediana de las distancias entre
+/// fechas consecutivas. La mediana ignora compras sueltas fuera de lo normal.
+fn repurchase_interval(dates: &[chrono::NaiveDate]) -> Option<f64> {
+    if dates.len() < MIN_PURCHASES_FOR_SUGGESTION {
+        return None;
+    }
+
+    let mut gaps: Vec<i64> = dates
+        .windows(2)
+        .map(|pair| (pair[1] - pair[0]).num_days())
+        .filter(|days| *days > 0)
+        .collect();
+    if gaps.is_empty() {
+        return None;
+    }
+    gaps.sort_unstable();
+
+    let middle = gaps.len() / 2;
+    Some(if gaps.len().is_multiple_of(2) {
+        (gaps[middle - 1] + gaps[middle]) as f64 / 2.0
+    } else {
+        gaps[middle] as f64
+    })
+}
+
+fn validate_quantity(quantity: f64) -> Result<(), OrbitError> {
+    if quantity.is_nan() || quantity <= 0.0 {
+        return Err(OrbitError::ValidationError(
+            "La cantidad debe ser mayor a cero".into(),
+        ));
+    }
+
+    Ok(())
+}
+
+const LIST_SELECT: &str = "SELECT l.id, l.name, l.mov_id, l.completed_at, l.created_at,
+        COUNT(i.id), COALESCE(SUM(i.checked), 0)
+     FROM shopping_lists l
+     LEFT JOIN shopping_list_items i ON i.list_id = l.id";
+
+fn row_to_list(row: &rusqlite::Row) -> rusqlite::Result<ShoppingList> {
+    Ok(ShoppingList {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        mov_id: row.get(2)?,
+        completed_at: row.get(3)?,
+        created_at: row.get(4)?,
+        item_count: row.get(5)?,
+        checked_count: row.get(6)?,
+    })
+}
+
+fn fetch_list(conn: &rusqlite::Connection, id: i64) -> Result<ShoppingList, OrbitError> {
+    match conn.query_row(
+        &format!("{LIST_SELECT} WHERE l.id = ?1 GROUP BY l.id"),
+        params![id],
+        row_to_list,
+    ) {
+        Ok(list) => Ok(list),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Err(OrbitError::NotFound(format!(
+            "No se encontró la lista de compras con ID {}",
+            id
+        ))),
+        Err(e) => Err(OrbitError::Database(e)),
+    }
+}
+
+/// Igual que `fetch_list`, pero falla si la lista ya fue completada.
+fn fetch_open_list(conn: &rusqlite::Connection, id: i64) -> Result<ShoppingList, OrbitError> {
+    let list = fetch_list(conn, id)?;
+    if list.completed_at.is_some() {
+        return Err(OrbitError::ValidationError(format!(
+            "La lista {:?} ya fue completada",
+            list.name
+        )));
+    }
+
+    Ok(list)
+}
+
+/// Lista a la que pertenece un ítem de lista, que tiene que estar abierta.
+fn open_list_of_item(conn: &rusqlite::Connection, list_item_id: i64) -> Result<i64, OrbitError> {
+    let list_id: i64 = match conn.query_row(
+        "SELECT list_id FROM shopping_list_items WHERE id = ?1",
+        params![list_item_id],
+        |row| row.get(0),
+    ) {
+        Ok(list_id) => list_id,
+        Err(rusqlite::Error::QueryReturnedNoRows) => {
+            return Err(OrbitError::NotFound(format!(
+                "No se encontró el ítem de lista con ID {}",
+                list_item_id
+            )))
+        }
+        Err(e) => return Err(OrbitError::Database(e)),
+    };
+
+    fetch_open_list(conn, list_id)?;
+    Ok(list_id)
+}
+
+/// Último precio por paquete (en ARS) de cada ítem en cada tienda.
+fn last_store_prices(
+    conn: &rusqlite::Connection,
+    item_ids: &[i64],
+) -> Result<HashMap<i64, Vec<StorePrice>>, OrbitError> {
+    let ids = format!(
+        "[{}]",
+        item_ids
+            .iter()
+            .map(|id| id.to_string())
+            .collect::<Vec<String>>()
+            .join(",")
+    );
+
+    let mut stmt = conn.prepare(
+        "SELECT item_id, store_id, store_name, price, date FROM (
+             SELECT p.item_id, p.store_id, s.name AS store_name, m.date,
+                    CAST(ROUND(p.price * m.ars_amount * 1.0 / m.original_amount) AS INTEGER) AS price,
+                    ROW_NUMBER() OVER (
+                        PARTITION BY p.item_id, p.store_id
+                        ORDER BY m.date DESC, p.id DESC
+                    ) AS recency
+             FROM purchases p
+             JOIN movements m ON m.id = p.mov_id
+             JOIN stores s ON s.id = p.store_id
+             WHERE p.item_id IN (SELECT value FROM json_each(?1)) AND m.original_amount <> 0
+         )
+         WHERE recency = 1
+         ORDER BY price ASC, date DESC",
+    )?;
+
+    let mut prices: HashMap<i64, Vec<StorePrice>> = HashMap::new();
+    let rows = stmt.query_map(params![ids], |row| {
+        Ok((
+            row.get::<_, i64>(0)?,
+            StorePrice {
+                store_id: row.get(1)?,
+                store_name: row.get(2)?,
+                price: row.get(3)?,
+                date: row.get(4)?,
+            },
+        ))
+    })?;
+    for row in rows {
+        let (item_id, price) = row?;
+        prices.entry(item_id).or_default().push(price);
+    }
+
+    Ok(prices)
+}
+
+/// Último precio por paquete (en ARS) de un ítem, en cualquier tienda o sin
+/// tienda.
+fn last_price(conn: &rusqlite::Connection, item_id: i64) -> Result<Option<i64>, OrbitError> {
+    match conn.query_row(
+        "SELECT CAST(ROUND(p.price * m.ars_amount * 1.0 / m.original_amount) AS INTEGER)
+         FROM purchases p
+         JOIN movements m ON m.id = p.mov_id
+         WHERE p.item_id = ?1 AND m.original_amount <> 0
+         ORDER BY m.date DESC, p.id DESC
+         LIMIT 1",
+        params![item_id],
+        |row| row.get(0),
+    ) {
+        Ok(price) => Ok(Some(price)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(OrbitError::Database(e)),
+    }
+}
+
+fn estimate_by_store(items: &[ShoppingListItem]) -> Vec<StoreEstimate> {
+    let mut estimates: HashMap<i64, StoreEstimate> = HashMap::new();
+    for item in items {
+        for price in &item.store_prices {
+            let estimate = estimates
+                .entry(price.store_id)
+                .or_insert_with(|| StoreEstimate {
+                    store_id: price.store_id,
+                    store_name: price.store_name.clone(),
+                    expected_total: 0,
+                    items_priced: 0,
+                });
+            estimate.expected_total += line_total(price.price, item.quantity);
+            estimate.items_priced += 1;
+        }
+    }
+
+    // Una tienda que cubre menos ítems siempre parece más barata: primero
+    // las que cubren más
+    let mut estimates: Vec<StoreEstimate> = estimates.into_values().collect();
+    estimates.sort_by(|a, b| {
+        b.items_priced
+            .cmp(&a.items_priced)
+            .then(a.expected_total.cmp(&b.expected_total))
+            .then(a.store_name.cmp(&b.store_name))
+    });
+
+    estimates
+}
+
+// ---------------------------------------------------------------------------
+// Commands
+// ---------------------------------------------------------------------------
+
+/// Todas las listas, primero las abiertas y después las más recientes.
+#[tauri::command]
+pub fn get_shopping_lists(
+    state: tauri::State<crate::AppState>,
+) -> Result<Vec<ShoppingList>, OrbitError> {
+    let conn = state.conn.lock().unwrap();
+    let mut stmt = conn.prepare(&format!(
+        "{LIST_SELECT} GROUP BY l.id
+         ORDER BY l.completed_at IS NOT NULL, l.created_at DESC, l.id DESC"
+    ))?;
+
+    let lists = stmt
+        .query_map([], row_to_list)?
+        .collect::<Result<Vec<ShoppingList>, rusqlite::Error>>()?;
+
+    Ok(lists)
+}
+
+/// Detalle de una lista con el costo esperado según los últimos precios
+/// conocidos, en total y por tienda.
+#[tauri::command]
+pub fn get_shopping_list(
+    state: tauri::State<crate::AppState>,
+    id: i64,
+) -> Result<ShoppingListDetails, OrbitError> {
+    let conn = state.conn.lock().unwrap();
+    let list = fetch_list(&conn, id)?;
+
+    let mut stmt = conn.prepare(
+        "SELECT li.id, li.item_id, i.name, i.brand, i.unit, li.quantity, li.checked
+         FROM shopping_list_items li
+         JOIN items i ON i.id = li.item_id
+         WHERE li.list_id = ?1
+         ORDER BY li.checked ASC, i.name ASC",
+    )?;
+    let mut items = stmt
+        .query_map(params![id], |row| {
+            Ok(ShoppingListItem {
+                id: row.get(0)?,
+                item_id: row.get(1)?,
+                item_name: row.get(2)?,
+                brand: row.get(3)?,
+                unit: row.get(4)?,
+                quantity: row.get(5)?,
+                checked: row.get(6)?,
+                last_price: None,
+                expected_cost: None,
+                store_prices: Vec::new(),
+            })
+        })?
+        .collect::<Result<Vec<ShoppingListItem>, rusqlite::Error>>()?;
+
+    let item_ids: Vec<i64> = items.iter().map(|i| i.item_id).collect();
+    let mut store_prices = last_store_prices(&conn, &item_ids)?;
+
+    for item in &mut items {
+        item.last_price = last_price(&conn, item.item_id)?;
+        item.expected_cost = item.last_price.map(|p| line_total(p, item.quantity));
+        item.store_prices = store_prices.remove(&item.item_id).unwrap_or_default();
+    }
+
+    let expected_total = items.iter().filter_map(|i| i.expected_cost).sum();
+    let by_store = estimate_by_store(&items);
+
+    Ok(ShoppingListDetails {
+        list,
+        items,
+        expected_total,
+        by_store,
+    })
+}
+
+#[tauri::command]
+pub fn add_shopping_list(
+    state: tauri::State<crate::AppState>,
+    name: String,
+) -> Result<ShoppingList, OrbitError> {
+    if name.trim().is_empty() {
+        return Err(OrbitError::ValidationError(
+            "El nombre de la lista no puede estar vacío".into(),
+        ));
+    }
+
+    let conn = state.conn.lock().unwrap();
+    let op = history::begin(&conn, "Agregar lista de compras")?;
+
+    conn.execute(
+        "INSERT INTO shopping_lists (name) VALUES (?1)",
+        params![name.trim()],
+    )?;
+
+    let new_id = conn.last_insert_rowid();
+    op.finish();
+    fetch_list(&conn, new_id)
+}
+
+#[tauri::command]
+pub fn rename_shopping_list(
+    state: tauri::State<crate::AppState>,
+    id: i64,
+    name: String,
+) -> Result<ShoppingList, OrbitError> {
+    if name.trim().is_empty() {
+        return Err(OrbitError::ValidationError(
+            "El nombre de la lista no puede estar vacío".into(),
+        ));
+    }
+
+    let conn = state.conn.lock().unwrap();
+    let op = history::begin(&conn, "Renombrar lista de compras")?;
+
+    let rows_affected = conn.execute(
+        "UPDATE shopping_lists SET name = ?1 WHERE id = ?2",
+        params![name.trim(), id],
+    )?;
+
+    if rows_affected == 0 {
+        return Err(OrbitError::NotFound(format!(
+            "No se encontró la lista de compras con ID {}",
+            id
+        )));
+    }
+
+    op.finish();
+
+    fetch_list(&conn, id)
+}
+
+/// Elimina la lista. El movimiento generado al completarla no se toca.
+#[tauri::command]
+pub fn delete_shopping_list(
+    state: tauri::State<crate::AppState>,
+    id: i64,
+) -> Result<(), OrbitError> {
+    let conn = state.conn.lock().unwrap();
+    let op = history::begin(&conn, "Eliminar lista de compras")?;
+
+    let rows_affected = conn.execute("DELETE FROM shopping_lists WHERE id = ?1", params![id])?;
+
+    if rows_affected == 0 {
+        return Err(OrbitError::NotFound(format!(
+            "No se encontró la lista de compras con ID {}",
+            id
+        )));
+    }
+
+    op.finish();
+
+    Ok(())
+}
+
+/// Agrega un ítem a la lista. Si ya estaba, se suma la cantidad.
+#[tauri::command]
+pub fn add_shopping_list_item(
+    state: tauri::State<crate::AppState>,
+    list_id: i64,
+    item: AddShoppingListItem,
+) -> Result<ShoppingList, OrbitError> {
+    validate_quantity(item.quantity)?;
+
+    let conn = state.conn.lock().unwrap();
+    fetch_open_list(&conn, list_id)?;
+
+    let item_exists: bool = conn.query_row(
+        "SELECT EXISTS (SELECT 1 FROM items WHERE id = ?1 AND is_archived = 0)",
+        params![item.item_id],
+        |row| row.get(0),
+    )?;
+    if !item_exists {
+        return Err(OrbitError::NotFound(format!(
+            "No se encontró el ítem activo con ID {}",
+            item.item_id
+        )));
+    }
+
+    let op = history::begin(&conn, "Agregar ítem a la lista")?;
+
+    conn.execute(
+        "INSERT INTO shopping_list_items (list_id, item_id, quantity) VALUES (?1, ?2, ?3)
+         ON CONFLICT (list_id, item_id) DO UPDATE SET quantity = quantity + excluded.quantity",
+        params![list_id, item.item_id, item.quantity],
+    )?;
+
+    op.finish();
+
+    fetch_list(&conn, list_id)
+}
+
+/// Cambia la cantidad deseada o marca/desmarca un ítem de la lista.
+#[tauri::command]
+pub fn update_shopping_list_item(
+    state: tauri::State<crate::AppState>,
+    id: i64,
+    item: UpdateShoppingListItem,
+) -> Result<ShoppingList, OrbitError> {
+    validate_quantity(item.quantity)?;
+
+    let conn = state.conn.lock().unwrap();
+    let list_id = open_list_of_item(&conn, id)?;
+    let op = history::begin(&conn, "Editar ítem de la lista")?;
+
+    conn.execute(
+        "UPDATE shopping_list_items SET quantity = ?1, checked = ?2 WHERE id = ?3",
+        params![item.quantity, item.checked, id],
+    )?;
+
+    op.finish();
+
+    fetch_list(&conn, list_id)
+}
+
+#[tauri::command]
+pub fn remove_shopping_list_item(
+    state: tauri::State<crate::AppState>,
+    id: i64,
+) -> Result<ShoppingList, OrbitError> {
+    let conn = state.conn.lock().unwrap();
+    let list_id = open_list_of_item(&conn, id)?;
+    let op = history::begin(&conn, "Quitar ítem de la lista")?;
+
+    conn.execute("DELETE FROM shopping_list_items WHERE id = ?1", params![id])?;
+
+    op.finish();
+
+    fetch_list(&conn, list_id)
+}
+
+/// Ítems que probablemente haya que volver a comprar: los que se compran con
+/// cierta regularidad y cuya próxima compra esperada cae dentro de los
+/// próximos `horizon_days` días (o ya pasó).
+///
+/// Si se indica `list_id`, se omiten los ítems que ya están en esa lista.
+#[tauri::command]
+pub fn get_shopping_suggestions(
+    state: tauri::State<crate::AppState>,
+    list_id: Option<i64>,
+    horizon_days: Option<i64>,
+) -> Result<Vec<ShoppingSuggestion>, OrbitError> {
+    let horizon_days = horizon_days.unwrap_or(DEFAULT_SUGGESTION_HORIZON_DAYS);
+    let today = chrono::Local::now().date_naive();
+
+    let conn = state.conn.lock().unwrap();
+
+    let mut stmt = conn.prepare(
+        "SELECT i.id, i.name, i.brand, m.date, SUM(p.quantity)
+         FROM purchases p
+         JOIN movements m ON m.id = p.mov_id
+         JOIN items i ON i.id = p.item_id
+         WHERE i.is_archived = 0
+           AND (?1 IS NULL OR i.id NOT IN (SELECT item_id FROM shopping_list_items WHERE list_id = ?1))
+         GROUP BY i.id, m.date
+         ORDER BY i.id, m.date",
+    )?;
+
+    // (nombre, marca, fechas, cantidades) por ítem
+    type History = (String, Option<String>, Vec<chrono::NaiveDate>, Vec<f64>);
+    let mut histories: HashMap<i64, History> = HashMap::new();
+    let rows = stmt.query_map(params![list_id], |row| {
+        Ok((
+            row.get::<_, i64>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, Option<String>>(2)?,
+            row.get::<_, String>(3)?,
+            row.get::<_, f64>(4)?,
+        ))
+    })?;
+    for row in rows {
+        let (item_id, name, brand, date, quantity) = row?;
+        let Ok(date) = parse_date(&date) else {
+            continue;
+        };
+        let history = histories
+            .entry(item_id)
+            .or_insert_with(|| (name, brand, Vec::new(), Vec::new()));
+        history.2.push(date);
+        history.3.push(quantity);
+    }
+
+    let mut suggestions: Vec<ShoppingSuggestion> = Vec::new();
+    for (item_id, (item_name, brand, dates, quantities)) in histories {
+        let Some(interval_days) = repurchase_interval(&dates) else {
+            continue;
+        };
+        let last = dates[dates.len() - 1];
+        let due = last + chrono::Duration::days(interval_days.round() as i64);
+        let days_overdue = (today - due).num_days();
+        if days_overdue < -horizon_days {
+            continue;
+        }
+
+        let mut sorted = quantities.clone();
+        sorted.sort_by(f64::total_cmp);
+
+        suggestions.push(ShoppingSuggestion {
+            item_id,
+            item_name,
+            brand,
+            interval_days,
+            last_purchased: format_date(last),
+            due_date: format_date(due),
+            days_overdue,
+            typical_quantity: sorted[sorted.len() / 2],
+        });
+    }
+
+    suggestions.sort_by(|a, b| {
+        b.days_overdue
+            .cmp(&a.days_overdue)
+            .then(a.item_name.cmp(&b.item_name))
+    });
+
+    Ok(suggestions)
+}
+
+/// Convierte los ítems marcados de la lista en un movimiento de gasto con sus
+/// compras, y cierra la lista.
+///
+/// El precio de cada ítem es el indicado en `lines` o, si no se indica, el
+/// último precio conocido. Los ítems sin marcar no se compran.
+#[tauri::command]
+pub fn complete_shopping_list(
+    state: tauri::State<crate::AppState>,
+    id: i64,
+    completion: CompleteShoppingList,
+) -> Result<Movement, OrbitError> {
+    parse_date(&completion.date)?;
+
+    let mut conn = state.conn.lock().unwrap();
+    let list = fetch_open_list(&conn, id)?;
+
+    // Los precios de las compras están en pesos: el gasto va a una cuenta en ARS
+    let currency: String = conn
+        .query_row(
+            "SELECT currency FROM accounts WHERE id = ?1",
+            params![completion.account_id],
+            |row| row.get(0),
+        )
+        .optional()?
+        .ok_or_else(|| {
+            OrbitError::NotFound(format!(
+                "No se encontró la cuenta con ID {}",
+                completion.account_id
+            ))
+        })?;
+    if currency != "ARS" {
+        return Err(OrbitError::ValidationError(format!(
+            "La cuenta está en {currency}; las compras solo pueden registrarse en una cuenta en ARS"
+        )));
+    }
+
+    let mut stmt = conn.prepare(
+        "SELECT id, item_id, quantity FROM shopping_list_items
+         WHERE list_id = ?1 AND checked = 1
+         ORDER BY id",
+    )?;
+    let checked = stmt
+        .query_map(params![id], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, i64>(1)?,
+                row.get::<_, f64>(2)?,
+            ))
+        })?
+        .collect::<Result<Vec<(i64, i64, f64)>, rusqlite::Error>>()?;
+    drop(stmt);
+
+    if checked.is_empty() {
+        return Err(OrbitError::ValidationError(
+            "No hay ítems marcados en la lista".into(),
+        ));
+    }
+
+    let lines: HashMap<i64, &CompleteShoppingListLine> = completion
+        .lines
+        .iter()
+        .map(|line| (line.list_item_id, line))
+        .collect();
+    if let Some(line) = completion.lines.iter().find(|line| {
+        !checked
+            .iter()
+            .any(|(list_item_id, _, _)| *list_item_id == line.list_item_id)
+    }) {
+        return Err(OrbitError::ValidationError(format!(
+            "El ítem de lista con ID {} no está marcado en la lista",
+            line.list_item_id
+        )));
+    }
+
+    // (item_id, precio, cantidad, tamaño del paquete)
+    let mut purchases: Vec<(i64, i64, f64, Option<f64>)> = Vec::with_capacity(checked.len());
+    for (list_item_id, item_id, quantity) in &checked {
+        let purchase = match lines.get(list_item_id) {
+            Some(line) => (
+                *item_id,
+                line.price,
+                line.quantity.unwrap_or(*quantity),
+                line.package_size,
+            ),
+            None => match last_price(&conn, *item_id)? {
+                Some(price) => (*item_id, price, *quantity, None),
+                None => {
+                    return Err(OrbitError::ValidationError(format!(
+                    "El ítem de lista con ID {} no tiene precio conocido; indique cuánto se pagó",
+                    list_item_id
+                )))
+                }
+            },
+        };
+
+        if purchase.1 < 0 {
+            return Err(OrbitError::ValidationError(
+                "El precio no puede ser negativo".into(),
+            ));
+        }
+        validate_quantity(purchase.2)?;
+        purchases.push(purchase);
+    }
+
+    let amount: i64 = purchases
+        .iter()
+        .map(|(_, price, quantity, _)| line_total(*price, *quantity))
+        .sum();
+
+    let tx = conn.transaction()?;
+    let op = history::begin(&tx, "Completar lista de compras")?;
+
+    tx.execute(
+        "INSERT INTO movements (details, date, mov_type, currency, original_amount, ars_amount, account_id, category_id)
+         VALUES (?1, ?2, 'expense', 'ARS', ?3, ?3, ?4, ?5)",
+        params![
+            completion.details.unwrap_or(list.name),
+            completion.date,
+            amount,
+            completion.account_id,
+            completion.category_id
+        ],
+    )?;
+    let mov_id = tx.last_insert_rowid();
+
+    let store_id: Option<i64> = completion
+        .store_name
+        .as_deref()
+        .filter(|s| !s.trim().is_empty())
+        .map(|name| find_or_create_store(&tx, name))
+        .transpose()?;
+
+    for (item_id, price, quantity, package_size) in &purchases {
+        tx.execute(
+            "INSERT INTO purchases (price, quantity, package_size, mov_id, item_id, store_id) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![price, quantity, package_size, mov_id, item_id, store_id],
+        )?;
+    }
+
+    tx.execute(
+        "UPDATE shopping_lists SET mov_id = ?1, completed_at = datetime('now') WHERE id = ?2",
+        params![mov_id, id],
+    )?;
+
+    op.finish();
+    tx.commit()?;
+
+    let movement = conn.query_row(
+        &format!("{MOVEMENT_SELECT} WHERE id = ?1"),
+        params![mov_id],
+        row_to_movement,
+    )?;
+
+    Ok(movement)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_repurchase_interval_uses_median_gap() {
+        let date = |d: &str| chrono::NaiveDate::parse_from_str(d, "%Y-%m-%d").unwrap();
+
+        // Intervalos de 7, 7 y 30 días: la compra atrasada no mueve el típico
+        let dates = vec![
+            date("2025-01-01"),
+            date("2025-01-08"),
+            date("2025-01-15"),
+            date("2025-02-14"),
+        ];
+        assert_eq!(repurchase_interval(&dates), Some(7.0));
+
+        assert_eq!(repurchase_interval(&dates[..2]), None);
+    }
+}
@@ -0,0 +1,144 @@
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+
+use crate::{errors::OrbitError, history};
+
+/// A line of a split movement.
+///
+/// A movement can be divided into several lines, each one with its own amount and
+/// category. When a movement has lines, category reports use them instead of
+/// `movements.category_id`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MovementSplit {
+    pub id: i64,
+    pub mov_id: i64,
+    /// Amount of the line in the movement's original currency, stored in cents
+    pub amount: i64,
+    pub category_id: Option<i64>,
+    pub category_name: Option<String>,
+    pub note: Option<String>,
+    pub created_at: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AddMovementSplit {
+    pub amount: i64,
+    pub category_id: Option<i64>,
+    pub note: Option<String>,
+}
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Valida que las líneas sumen exactamente el monto original del movimiento.
+fn validate_splits(original_amount: i64, splits: &[AddMovementSplit]) -> Result<(), OrbitError> {
+    if splits.iter().any(|s| s.amount <= 0) {
+        return Err(OrbitError::ValidationError(
+            "El monto de cada línea debe ser mayor a cero".into(),
+        ));
+    }
+
+    let total: i64 = splits.iter().map(|s| s.amount).sum();
+    if total != original_amount {
+        return Err(OrbitError::ValidationError(format!(
+            "La suma de las líneas ({}) no coincide con el monto del movimiento ({})",
+            total, original_amount
+        )));
+    }
+
+    Ok(())
+}
+
+pub(crate) fn fetch_splits_for_movement(
+    conn: &rusqlite::Connection,
+    mov_id: i64,
+) -> Result<Vec<MovementSplit>, OrbitError> {
+    let mut stmt = conn.prepare(
+        "SELECT s.id, s.mov_id, s.amount, s.category_id, c.name, s.note, s.created_at
+         FROM movement_splits s
+         LEFT JOIN categories c ON c.id = s.category_id
+         WHERE s.mov_id = ?1
+         ORDER BY s.id ASC",
+    )?;
+
+    let splits = stmt
+        .query_map(params![mov_id], |row| {
+            Ok(MovementSplit {
+                id: row.get(0)?,
+                mov_id: row.get(1)?,
+                amount: row.get(2)?,
+                category_id: row.get(3)?,
+                category_name: row.get(4)?,
+                note: row.get(5)?,
+                created_at: row.get(6)?,
+            })
+        })?
+        .collect::<Result<Vec<MovementSplit>, rusqlite::Error>>()?;
+
+    Ok(splits)
+}
+
+// ---------------------------------------------------------------------------
+// Commands
+// ---------------------------------------------------------------------------
+
+/// Devuelve las líneas de desglose de un movimiento (vacío si no está dividido).
+#[tauri::command]
+pub fn get_movement_splits(
+    state: tauri::State<crate::AppState>,
+    mov_id: i64,
+) -> Result<Vec<MovementSplit>, OrbitError> {
+    let conn = state.conn.lock().unwrap();
+    fetch_splits_for_movement(&conn, mov_id)
+}
+
+/// Reemplaza todo el desglose de un movimiento en una sola transacción.
+/// Una lista vacía elimina el desglose y el movimiento vuelve a usar su categoría.
+#[tauri::command]
+pub fn set_movement_splits(
+    state: tauri::State<crate::AppState>,
+    mov_id: i64,
+    splits: Vec<AddMovementSplit>,
+) -> Result<Vec<MovementSplit>, OrbitError> {
+    let mut conn = state.conn.lock().unwrap();
+
+    let original_amount: i64 = match conn.query_row(
+        "SELECT original_amount FROM movements WHERE id = ?1",
+        params![mov_id],
+        |row| row.get(0),
+    ) {
+        Ok(amount) => amount,
+        Err(rusqlite::Error::QueryReturnedNoRows) => {
+            return Err(OrbitError::NotFound(format!(
+                "No se encontró el movimiento con ID {}",
+                mov_id
+            )))
+        }
+        Err(e) => return Err(OrbitError::Database(e)),
+    };
+
+    if !splits.is_empty() {
+        validate_splits(original_amount, &splits)?;
+    }
+
+    let tx = conn.transaction()?;
+    let op = history::begin(&tx, "Dividir movimiento")?;
+
+    tx.execute(
+        "DELETE FROM movement_splits WHERE mov_id = ?1",
+        params![mov_id],
+    )?;
+
+    for split in &splits {
+        tx.execute(
+            "INSERT INTO movement_splits (mov_id, amount, category_id, note) VALUES (?1, ?2, ?3, ?4)",
+            params![mov_id, split.amount, split.category_id, split.note],
+        )?;
+    }
+
+    op.finish();
+    tx.commit()?;
+
+    fetch_splits_for_movement(&conn, mov_id)
+}
@@ -0,0 +1,399 @@
+use std::collections::HashSet;
+
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    errors::OrbitError,
+    history,
+    utils::{normalize_text, random_hex_color},
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Store {
+    pub id: i64,
+    pub name: String,
+    pub color: Option<String>,
+    /// Branch name or number, e.g. "Sucursal Palermo"
+    pub branch: Option<String>,
+    pub address: Option<String>,
+    /// Other names the store is recognized by
+    pub aliases: Vec<String>,
+    pub created_at: String,
+    pub total_purchases: i64,
+    pub total_expenses: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AddStore {
+    pub name: String,
+    /// "#rrggbb"; a random color is assigned if missing
+    #[serde(default)]
+    pub color: Option<String>,
+    #[serde(default)]
+    pub branch: Option<String>,
+    #[serde(default)]
+    pub address: Option<String>,
+    #[serde(default)]
+    pub aliases: Vec<String>,
+}
+
+/// Replaces every editable field of the store, aliases included.
+#[derive(Debug, Deserialize)]
+pub struct UpdateStore {
+    pub name: String,
+    pub color: Option<String>,
+    #[serde(default)]
+    pub branch: Option<String>,
+    #[serde(default)]
+    pub address: Option<String>,
+    #[serde(default)]
+    pub aliases: Vec<String>,
+}
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// IDs de las tiendas cuyo nombre o alias coincide con `name` ignorando
+/// mayúsculas, acentos, puntuación y espacios.
+fn stores_named(conn: &rusqlite::Connection, name: &str) -> Result<Vec<i64>, OrbitError> {
+    let wanted = normalize_text(name);
+    if wanted.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut stmt = conn.prepare(
+        "SELECT id, name FROM stores
+         UNION ALL
+         SELECT store_id, alias FROM store_aliases
+         ORDER BY 1",
+    )?;
+
+    let names = stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+        })?
+        .collect::<Result<Vec<(i64, String)>, rusqlite::Error>>()?;
+
+    let mut ids: Vec<i64> = names
+        .into_iter()
+        .filter(|(_, existing)| normalize_text(existing) == wanted)
+        .map(|(id, _)| id)
+        .collect();
+    ids.dedup();
+
+    Ok(ids)
+}
+
+/// Busca una tienda por nombre o alias (ver `stores_named`). Si hay varias
+/// sucursales con ese nombre, devuelve la más antigua.
+pub(crate) fn find_store_by_name(
+    conn: &rusqlite::Connection,
+    name: &str,
+) -> Result<Option<i64>, OrbitError> {
+    Ok(stores_named(conn, name)?.first().copied())
+}
+
+fn trimmed(value: Option<String>) -> Option<String> {
+    value
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+fn validate_color(color: &Option<String>) -> Result<(), OrbitError> {
+    if let Some(color) = color {
+        let valid = color.len() == 7
+            && color.starts_with('#')
+            && color[1..].chars().all(|c| c.is_ascii_hexdigit());
+        if !valid {
+            return Err(OrbitError::ValidationError(format!(
+                "Color inválido: {color:?} (formato #rrggbb)"
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+/// Valida el nombre y los alias de una tienda y devuelve los alias limpios,
+/// sin repetidos ni iguales al nombre.
+///
+/// Ningún nombre puede coincidir (normalizado) con otra tienda distinta de
+/// `store_id` de la misma sucursal: "Coto" puede repetirse solo con
+/// sucursales distintas.
+fn validate_names(
+    conn: &rusqlite::Connection,
+    store_id: Option<i64>,
+    name: &str,
+    branch: &Option<String>,
+    aliases: &[String],
+) -> Result<Vec<String>, OrbitError> {
+    if normalize_text(name).is_empty() {
+        return Err(OrbitError::ValidationError(
+            "El nombre de la tienda no puede estar vacío".into(),
+        ));
+    }
+
+    let mut seen: HashSet<String> = HashSet::from([normalize_text(name)]);
+    let mut clean: Vec<String> = Vec::new();
+    for alias in aliases {
+        let normalized = normalize_text(alias);
+        if !normalized.is_empty() && seen.insert(normalized) {
+            clean.push(alias.trim().to_string());
+        }
+    }
+
+    let branch = normalize_text(branch.as_deref().unwrap_or_default());
+
+    for candidate in std::iter::once(name).chain(clean.iter().map(String::as_str)) {
+        for existing in stores_named(conn, candidate)? {
+            if Some(existing) == store_id {
+                continue;
+            }
+
+            let existing_branch: Option<String> = conn.query_row(
+                "SELECT branch FROM stores WHERE id = ?1",
+                params![existing],
+                |row| row.get(0),
+            )?;
+            if normalize_text(existing_branch.as_deref().unwrap_or_default()) == branch {
+                return Err(OrbitError::ValidationError(format!(
+                    "Ya existe una tienda llamada {:?}",
+                    candidate.trim()
+                )));
+            }
+        }
+    }
+
+    Ok(clean)
+}
+
+fn replace_aliases(
+    conn: &rusqlite::Connection,
+    store_id: i64,
+    aliases: &[String],
+) -> Result<(), OrbitError> {
+    conn.execute(
+        "DELETE FROM store_aliases WHERE store_id = ?1",
+        params![store_id],
+    )?;
+    for alias in aliases {
+        conn.execute(
+            "INSERT INTO store_aliases (store_id, alias) VALUES (?1, ?2)",
+            params![store_id, alias],
+        )?;
+    }
+
+    Ok(())
+}
+
+const STORE_SELECT: &str = "SELECT
+        s.id,
+        s.name,
+        s.color,
+        s.branch,
+        s.address,
+        (SELECT json_group_array(alias) FROM
+            (SELECT alias FROM store_aliases WHERE store_id = s.id ORDER BY alias)),
+        s.created_at,
+        COUNT(DISTINCT p.mov_id) AS total_purchases,
+        CAST(ROUND(COALESCE(SUM(p.price * p.quantity), 0)) AS INTEGER) AS total_expenses
+    FROM stores s
+    LEFT JOIN purchases p ON p.store_id = s.id";
+
+fn row_to_store(row: &rusqlite::Row) -> rusqlite::Result<Store> {
+    let aliases: String = row.get(5)?;
+
+    Ok(Store {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        color: row.get(2)?,
+        branch: row.get(3)?,
+        address: row.get(4)?,
+        aliases: serde_json::from_str(&aliases).unwrap_or_default(),
+        created_at: row.get(6)?,
+        total_purchases: row.get(7)?,
+        total_expenses: row.get(8)?,
+    })
+}
+
+fn fetch_store(conn: &rusqlite::Connection, id: i64) -> Result<Store, OrbitError> {
+    match conn.query_row(
+        &format!("{STORE_SELECT} WHERE s.id = ?1 GROUP BY s.id"),
+        params![id],
+        row_to_store,
+    ) {
+        Ok(store) => Ok(store),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Err(OrbitError::NotFound(format!(
+            "No se encontró la tienda con ID {}",
+            id
+        ))),
+        Err(e) => Err(OrbitError::Database(e)),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Commands
+// ---------------------------------------------------------------------------
+
+#[tauri::command]
+pub fn get_stores(state: tauri::State<crate::AppState>) -> Result<Vec<Store>, OrbitError> {
+    let conn = state.conn.lock().unwrap();
+    let mut stmt = conn.prepare(&format!("{STORE_SELECT} GROUP BY s.id ORDER BY s.name ASC"))?;
+
+    let stores = stmt
+        .query_map([], row_to_store)?
+        .collect::<Result<Vec<Store>, rusqlite::Error>>()?;
+
+    Ok(stores)
+}
+
+#[tauri::command]
+pub fn add_store(
+    state: tauri::State<crate::AppState>,
+    store: AddStore,
+) -> Result<Store, OrbitError> {
+    validate_color(&store.color)?;
+
+    let mut conn = state.conn.lock().unwrap();
+    let aliases = validate_names(&conn, None, &store.name, &store.branch, &store.aliases)?;
+
+    let tx = conn.transaction()?;
+    let op = history::begin(&tx, "Agregar tienda")?;
+
+    tx.execute(
+        "INSERT INTO stores (name, color, branch, address) VALUES (?1, ?2, ?3, ?4)",
+        params![
+            store.name.trim(),
+            store.color.unwrap_or_else(random_hex_color),
+            trimmed(store.branch),
+            trimmed(store.address)
+        ],
+    )?;
+    let store_id = tx.last_insert_rowid();
+    replace_aliases(&tx, store_id, &aliases)?;
+
+    op.finish();
+    tx.commit()?;
+
+    fetch_store(&conn, store_id)
+}
+
+#[tauri::command]
+pub fn update_store(
+    state: tauri::State<crate::AppState>,
+    id: i64,
+    store: UpdateStore,
+) -> Result<Store, OrbitError> {
+    validate_color(&store.color)?;
+
+    let mut conn = state.conn.lock().unwrap();
+    fetch_store(&conn, id)?;
+    let aliases = validate_names(&conn, Some(id), &store.name, &store.branch, &store.aliases)?;
+
+    let tx = conn.transaction()?;
+    let op = history::begin(&tx, "Editar tienda")?;
+
+    tx.execute(
+        "UPDATE stores SET name = ?1, color = ?2, branch = ?3, address = ?4 WHERE id = ?5",
+        params![
+            store.name.trim(),
+            store.color,
+            trimmed(store.branch),
+            trimmed(store.address),
+            id
+        ],
+    )?;
+    replace_aliases(&tx, id, &aliases)?;
+
+    op.finish();
+    tx.commit()?;
+
+    fetch_store(&conn, id)
+}
+
+/// Elimina la tienda. Sus compras se conservan, sin tienda asignada.
+#[tauri::command]
+pub fn delete_store(state: tauri::State<crate::AppState>, id: i64) -> Result<(), OrbitError> {
+    let conn = state.conn.lock().unwrap();
+    let op = history::begin(&conn, "Eliminar tienda")?;
+
+    // purchases.store_id pasa a NULL y los alias caen por ON DELETE CASCADE
+    let rows_affected = conn.execute("DELETE FROM stores WHERE id = ?1", params![id])?;
+
+    if rows_affected == 0 {
+        return Err(OrbitError::NotFound(format!(
+            "No se encontró la tienda con ID {}",
+            id
+        )));
+    }
+
+    op.finish();
+
+    Ok(())
+}
+
+/// Fusiona tiendas repetidas en `keep_id`: las compras pasan a esa tienda y
+/// los nombres y alias de las fusionadas quedan como alias suyos.
+#[tauri::command]
+pub fn merge_stores(
+    state: tauri::State<crate::AppState>,
+    keep_id: i64,
+    merge_ids: Vec<i64>,
+) -> Result<Store, OrbitError> {
+    if merge_ids.is_empty() {
+        return Err(OrbitError::ValidationError(
+            "Debe indicar al menos una tienda a fusionar".into(),
+        ));
+    }
+    if merge_ids.contains(&keep_id) {
+        return Err(OrbitError::ValidationError(
+            "La tienda a conservar no puede estar entre las fusionadas".into(),
+        ));
+    }
+
+    let mut conn = state.conn.lock().unwrap();
+
+    let keep = fetch_store(&conn, keep_id)?;
+    let mut names: Vec<String> = keep.aliases;
+    for id in &merge_ids {
+        let merged = fetch_store(&conn, *id)?;
+        names.push(merged.name);
+        names.extend(merged.aliases);
+    }
+
+    // Sin repetidos ni el propio nombre de la tienda conservada
+    let mut seen: HashSet<String> = HashSet::from([normalize_text(&keep.name)]);
+    names.retain(|name| seen.insert(normalize_text(name)));
+
+    let tx = conn.transaction()?;
+    let op = history::begin(&tx, "Fusionar tiendas")?;
+
+    for id in &merge_ids {
+        tx.execute(
+            "UPDATE purchases SET store_id = ?1 WHERE store_id = ?2",
+            params![keep_id, id],
+        )?;
+        tx.execute("DELETE FROM stores WHERE id = ?1", params![id])?;
+    }
+    replace_aliases(&tx, keep_id, &names)?;
+
+    op.finish();
+    tx.commit()?;
+
+    fetch_store(&conn, keep_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_color() {
+        assert!(validate_color(&None).is_ok());
+        assert!(validate_color(&Some("#a1B2c3".to_string())).is_ok());
+        assert!(validate_color(&Some("a1b2c3".to_string())).is_err());
+        assert!(validate_color(&Some("#a1b2cz".to_string())).is_err());
+    }
+}
@@ -0,0 +1,601 @@
+use std::collections::{HashMap, HashSet};
+
+use chrono::NaiveDate;
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    errors::OrbitError,
+    forecast::{detect_period, Period},
+    history,
+    movements::{Currency, RateType},
+    rates::{ars_to_usd, latest_rate, usd_to_ars},
+    utils::{format_date, normalize_text, parse_date},
+};
+
+/// Months of history scanned to detect subscriptions.
+const DETECTION_LOOKBACK_MONTHS: u32 = 18;
+
+/// The recent charges of a subscription differ at most by this factor.
+const MAX_AMOUNT_SPREAD: f64 = 1.5;
+
+/// A price and the date it applies from.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PriceChange {
+    pub date: String,
+    pub amount: i64,
+}
+
+/// A confirmed subscription.
+#[derive(Debug, Clone, Serialize)]
+pub struct Subscription {
+    pub id: i64,
+    pub name: String,
+    pub merchant: String,
+    /// Current price, in cents of `currency`
+    pub amount: i64,
+    pub currency: String,
+    pub period: Period,
+    pub account_id: i64,
+    pub category_id: Option<i64>,
+    /// Stored date rolled forward past today for active subscriptions
+    pub next_charge_date: String,
+    pub is_active: bool,
+    /// Price normalized to a month, same currency
+    pub monthly_cost: i64,
+    /// Oldest first
+    pub price_history: Vec<PriceChange>,
+}
+
+/// Recurring expense that looks like an untracked subscription.
+#[derive(Debug, Clone, Serialize)]
+pub struct SubscriptionCandidate {
+    pub name: String,
+    pub merchant: String,
+    pub account_id: i64,
+    pub currency: String,
+    pub category_id: Option<i64>,
+    /// Latest charge, in cents of `currency`
+    pub amount: i64,
+    pub period: Period,
+    pub occurrences: i64,
+    pub last_date: String,
+    pub next_charge_date: String,
+    pub price_history: Vec<PriceChange>,
+    pub movement_ids: Vec<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AddSubscription {
+    pub name: String,
+    /// Normalized details that identify the charges; defaults to the name
+    #[serde(default)]
+    pub merchant: Option<String>,
+    pub amount: i64,
+    #[serde(default)]
+    pub currency: Currency,
+    pub period: Period,
+    pub account_id: i64,
+    #[serde(default)]
+    pub category_id: Option<i64>,
+    pub next_charge_date: String,
+    /// Earlier prices, e.g. from a detected candidate. The current amount is
+    /// recorded as of today if it differs from the last one
+    #[serde(default)]
+    pub price_history: Vec<PriceChange>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateSubscription {
+    pub name: String,
+    #[serde(default)]
+    pub merchant: Option<String>,
+    pub amount: i64,
+    #[serde(default)]
+    pub currency: Currency,
+    pub period: Period,
+    pub account_id: i64,
+    #[serde(default)]
+    pub category_id: Option<i64>,
+    pub next_charge_date: String,
+    pub is_active: bool,
+    /// When a new price applies from; defaults to today
+    #[serde(default)]
+    pub price_effective_date: Option<String>,
+}
+
+/// Cost of the active subscriptions, each currency converted at the latest
+/// stored rate of `rate_type`. Amounts in cents.
+#[derive(Debug, Clone, Serialize)]
+pub struct SubscriptionSummary {
+    pub rate_type: RateType,
+    pub rate: i64,
+    pub active: i64,
+    pub monthly_ars: i64,
+    pub monthly_usd: i64,
+    pub annual_ars: i64,
+    pub annual_usd: i64,
+}
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Primera fecha de cobro posterior a `today` siguiendo el período desde
+/// `anchor` (ver `Period::nth`).
+fn roll_forward(period: Period, anchor: NaiveDate, today: NaiveDate) -> NaiveDate {
+    (0..)
+        .map_while(|n| period.nth(anchor, n))
+        .find(|date| *date > today)
+        .unwrap_or(anchor)
+}
+
+fn monthly_cost(amount: i64, period: Period) -> i64 {
+    (amount as f64 * period.per_year() as f64 / 12.0).round() as i64
+}
+
+/// Cambios de precio de una serie de cobros (fecha, monto) ordenada: el
+/// primero y cada uno cuyo monto difiere del anterior.
+fn price_changes(charges: &[(NaiveDate, i64)]) -> Vec<PriceChange> {
+    let mut changes: Vec<PriceChange> = Vec::new();
+    for (date, amount) in charges {
+        if changes.last().map(|c| c.amount) != Some(*amount) {
+            changes.push(PriceChange {
+                date: format_date(*date),
+                amount: *amount,
+            });
+        }
+    }
+    changes
+}
+
+/// Valida los campos comunes y devuelve (nombre, comercio normalizado).
+fn validate_fields(
+    name: &str,
+    merchant: &Option<String>,
+    amount: i64,
+    next_charge_date: &str,
+) -> Result<(String, String), OrbitError> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(OrbitError::ValidationError(
+            "El nombre no puede estar vacío".into(),
+        ));
+    }
+    if amount <= 0 {
+        return Err(OrbitError::ValidationError(
+            "El precio debe ser mayor a cero".into(),
+        ));
+    }
+    parse_date(next_charge_date)?;
+
+    let merchant = normalize_text(merchant.as_deref().unwrap_or(name));
+    if merchant.is_empty() {
+        return Err(OrbitError::ValidationError(
+            "El detalle de los cobros no puede estar vacío".into(),
+        ));
+    }
+
+    Ok((name.to_string(), merchant))
+}
+
+/// Registra `amount` como precio vigente desde `date`, salvo que ya lo sea.
+fn record_price(
+    conn: &rusqlite::Connection,
+    subscription_id: i64,
+    amount: i64,
+    date: &str,
+) -> Result<(), OrbitError> {
+    let current: Option<i64> = conn
+        .query_row(
+            "SELECT amount FROM subscription_prices
+             WHERE subscription_id = ?1 AND effective_date <= ?2
+             ORDER BY effective_date DESC LIMIT 1",
+            params![subscription_id, date],
+            |row| row.get(0),
+        )
+        .map(Some)
+        .or_else(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => Ok(None),
+            e => Err(e),
+        })?;
+    if current == Some(amount) {
+        return Ok(());
+    }
+
+    conn.execute(
+        "INSERT INTO subscription_prices (subscription_id, amount, effective_date)
+         VALUES (?1, ?2, ?3)
+         ON CONFLICT (subscription_id, effective_date) DO UPDATE SET amount = excluded.amount",
+        params![subscription_id, amount, date],
+    )?;
+
+    Ok(())
+}
+
+const SUBSCRIPTION_SELECT: &str = "SELECT id, name, merchant, amount, currency, period, account_id,
+        category_id, next_charge_date, is_active
+ FROM subscriptions";
+
+fn fetch_subscriptions(
+    conn: &rusqlite::Connection,
+    where_clause: &str,
+    params: impl rusqlite::Params,
+) -> Result<Vec<Subscription>, OrbitError> {
+    let today = chrono::Local::now().date_naive();
+
+    let mut stmt = conn.prepare(&format!(
+        "{SUBSCRIPTION_SELECT} {where_clause} ORDER BY is_active DESC, name COLLATE NOCASE ASC"
+    ))?;
+    let mut subscriptions = stmt
+        .query_map(params, |row| {
+            let period: String = row.get(5)?;
+            let amount: i64 = row.get(3)?;
+            let period = Period::from_db_str(&period).unwrap_or(Period::Monthly);
+            let is_active: bool = row.get(9)?;
+            let next_charge_date: String = row.get(8)?;
+            Ok(Subscription {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                merchant: row.get(2)?,
+                amount,
+                currency: row.get(4)?,
+                period,
+                account_id: row.get(6)?,
+                category_id: row.get(7)?,
+                next_charge_date: match parse_date(&next_charge_date) {
+                    Ok(date) if is_active => format_date(roll_forward(period, date, today)),
+                    _ => next_charge_date,
+                },
+                is_active,
+                monthly_cost: monthly_cost(amount, period),
+                price_history: Vec::new(),
+            })
+        })?
+        .collect::<Result<Vec<Subscription>, rusqlite::Error>>()?;
+
+    let mut prices_stmt = conn.prepare(
+        "SELECT effective_date, amount FROM subscription_prices
+         WHERE subscription_id = ?1
+         ORDER BY effective_date ASC",
+    )?;
+    for subscription in &mut subscriptions {
+        subscription.price_history = prices_stmt
+            .query_map(params![subscription.id], |row| {
+                Ok(PriceChange {
+                    date: row.get(0)?,
+                    amount: row.get(1)?,
+                })
+            })?
+            .collect::<Result<Vec<PriceChange>, rusqlite::Error>>()?;
+    }
+
+    Ok(subscriptions)
+}
+
+fn fetch_subscription(conn: &rusqlite::Connection, id: i64) -> Result<Subscription, OrbitError> {
+    fetch_subscriptions(conn, "WHERE id = ?1", params![id])?
+        .pop()
+        .ok_or_else(|| OrbitError::NotFound(format!("No se encontró la suscripción con ID {id}")))
+}
+
+// ---------------------------------------------------------------------------
+// Commands
+// ---------------------------------------------------------------------------
+
+#[tauri::command]
+pub fn get_subscriptions(
+    state: tauri::State<crate::AppState>,
+) -> Result<Vec<Subscription>, OrbitError> {
+    let conn = state.conn.lock().unwrap();
+    fetch_subscriptions(&conn, "", [])
+}
+
+/// Gastos recurrentes que parecen suscripciones y todavía no se cargaron:
+/// mismo detalle (normalizado), cuenta y moneda, con un período regular,
+/// montos parecidos y un cobro reciente (hace menos de dos períodos).
+#[tauri::command]
+pub fn get_subscription_candidates(
+    state: tauri::State<crate::AppState>,
+) -> Result<Vec<SubscriptionCandidate>, OrbitError> {
+    let today = chrono::Local::now().date_naive();
+    let since = today
+        .checked_sub_months(chrono::Months::new(DETECTION_LOOKBACK_MONTHS))
+        .unwrap_or(today);
+
+    let conn = state.conn.lock().unwrap();
+
+    let mut tracked_stmt = conn.prepare("SELECT account_id, merchant FROM subscriptions")?;
+    let tracked = tracked_stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+        })?
+        .collect::<Result<HashSet<(i64, String)>, rusqlite::Error>>()?;
+
+    let mut stmt = conn.prepare(
+        "SELECT id, details, date, original_amount, currency, account_id, category_id
+         FROM movements
+         WHERE mov_type = 'expense' AND date >= ?1 AND date <= ?2
+         ORDER BY date, id",
+    )?;
+    let rows = stmt.query_map(params![format_date(since), format_date(today)], |row| {
+        Ok((
+            row.get::<_, i64>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, String>(2)?,
+            row.get::<_, i64>(3)?,
+            row.get::<_, String>(4)?,
+            row.get::<_, i64>(5)?,
+            row.get::<_, Option<i64>>(6)?,
+        ))
+    })?;
+
+    // (detalle original, categoría, cobros (id, fecha, monto)) por (cuenta, moneda, comercio)
+    type Group = (String, Option<i64>, Vec<(i64, NaiveDate, i64)>);
+    let mut groups: HashMap<(i64, String, String), Group> = HashMap::new();
+    for row in rows {
+        let (id, details, date, amount, currency, account_id, category_id) = row?;
+        let merchant = normalize_text(&details);
+        if merchant.is_empty() || tracked.contains(&(account_id, merchant.clone())) {
+            continue;
+        }
+        let Ok(date) = parse_date(&date) else {
+            continue;
+        };
+        let group = groups
+            .entry((account_id, currency, merchant))
+            .or_insert_with(|| (details.clone(), category_id, Vec::new()));
+        // Dos cobros el mismo día no son dos ocurrencias
+        if group.2.last().is_some_and(|(_, last, _)| *last == date) {
+            continue;
+        }
+        group.0 = details;
+        group.1 = category_id;
+        group.2.push((id, date, amount));
+    }
+
+    let mut candidates: Vec<SubscriptionCandidate> = Vec::new();
+    for ((account_id, currency, merchant), (name, category_id, charges)) in groups {
+        let dates: Vec<NaiveDate> = charges.iter().map(|(_, date, _)| *date).collect();
+        let Some(period) = detect_period(&dates) else {
+            continue;
+        };
+        let Some(&(_, last, amount)) = charges.last() else {
+            continue;
+        };
+        if (today - last).num_days() > period.days().0 * 2 {
+            continue;
+        }
+
+        // Una suscripción cobra montos parecidos; un gasto recurrente
+        // variable (súper, nafta) no
+        let recent: Vec<i64> = charges.iter().rev().take(3).map(|c| c.2).collect();
+        let (Some(&low), Some(&high)) = (recent.iter().min(), recent.iter().max()) else {
+            continue;
+        };
+        if low <= 0 || high as f64 > low as f64 * MAX_AMOUNT_SPREAD {
+            continue;
+        }
+
+        let history: Vec<(NaiveDate, i64)> = charges.iter().map(|c| (c.1, c.2)).collect();
+        candidates.push(SubscriptionCandidate {
+            name,
+            merchant,
+            account_id,
+            currency,
+            category_id,
+            amount,
+            period,
+            occurrences: charges.len() as i64,
+            last_date: format_date(last),
+            next_charge_date: format_date(roll_forward(period, last, today)),
+            price_history: price_changes(&history),
+            movement_ids: charges.iter().map(|c| c.0).collect(),
+        });
+    }
+
+    candidates.sort_by(|a, b| {
+        a.next_charge_date
+            .cmp(&b.next_charge_date)
+            .then(a.name.cmp(&b.name))
+    });
+
+    Ok(candidates)
+}
+
+/// Confirma una suscripción (detectada o cargada a mano).
+#[tauri::command]
+pub fn add_subscription(
+    state: tauri::State<crate::AppState>,
+    subscription: AddSubscription,
+) -> Result<Subscription, OrbitError> {
+    let (name, merchant) = validate_fields(
+        &subscription.name,
+        &subscription.merchant,
+        subscription.amount,
+        &subscription.next_charge_date,
+    )?;
+    for change in &subscription.price_history {
+        parse_date(&change.date)?;
+        if change.amount <= 0 {
+            return Err(OrbitError::ValidationError(
+                "Los precios del historial deben ser mayores a cero".into(),
+            ));
+        }
+    }
+
+    let mut conn = state.conn.lock().unwrap();
+    let tx = conn.transaction()?;
+    let op = history::begin(&tx, "Agregar suscripción")?;
+
+    tx.execute(
+        "INSERT INTO subscriptions
+            (name, merchant, amount, currency, period, account_id, category_id, next_charge_date)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![
+            name,
+            merchant,
+            subscription.amount,
+            subscription.currency,
+            subscription.period.as_db_str(),
+            subscription.account_id,
+            subscription.category_id,
+            subscription.next_charge_date
+        ],
+    )?;
+    let id = tx.last_insert_rowid();
+
+    let mut history = subscription.price_history;
+    history.sort_by(|a, b| a.date.cmp(&b.date));
+    for change in &history {
+        record_price(&tx, id, change.amount, &change.date)?;
+    }
+    let today = format_date(chrono::Local::now().date_naive());
+    record_price(&tx, id, subscription.amount, &today)?;
+
+    let subscription = fetch_subscription(&tx, id)?;
+
+    op.finish();
+    tx.commit()?;
+
+    Ok(subscription)
+}
+
+/// Edita la suscripción. Si cambia el precio, el nuevo queda registrado en
+/// el historial desde `price_effective_date` (hoy si no viene).
+#[tauri::command]
+pub fn update_subscription(
+    state: tauri::State<crate::AppState>,
+    id: i64,
+    subscription: UpdateSubscription,
+) -> Result<Subscription, OrbitError> {
+    let (name, merchant) = validate_fields(
+        &subscription.name,
+        &subscription.merchant,
+        subscription.amount,
+        &subscription.next_charge_date,
+    )?;
+    let effective_date = match &subscription.price_effective_date {
+        Some(date) => format_date(parse_date(date)?),
+        None => format_date(chrono::Local::now().date_naive()),
+    };
+
+    let mut conn = state.conn.lock().unwrap();
+    let tx = conn.transaction()?;
+    let op = history::begin(&tx, "Editar suscripción")?;
+
+    let updated = tx.execute(
+        "UPDATE subscriptions
+         SET name = ?1, merchant = ?2, amount = ?3, currency = ?4, period = ?5,
+             account_id = ?6, category_id = ?7, next_charge_date = ?8, is_active = ?9
+         WHERE id = ?10",
+        params![
+            name,
+            merchant,
+            subscription.amount,
+            subscription.currency,
+            subscription.period.as_db_str(),
+            subscription.account_id,
+            subscription.category_id,
+            subscription.next_charge_date,
+            subscription.is_active,
+            id
+        ],
+    )?;
+    if updated == 0 {
+        return Err(OrbitError::NotFound(format!(
+            "No se encontró la suscripción con ID {id}"
+        )));
+    }
+    record_price(&tx, id, subscription.amount, &effective_date)?;
+
+    let subscription = fetch_subscription(&tx, id)?;
+
+    op.finish();
+    tx.commit()?;
+
+    Ok(subscription)
+}
+
+#[tauri::command]
+pub fn delete_subscription(
+    state: tauri::State<crate::AppState>,
+    id: i64,
+) -> Result<(), OrbitError> {
+    let conn = state.conn.lock().unwrap();
+    let op = history::begin(&conn, "Eliminar suscripción")?;
+
+    let deleted = conn.execute("DELETE FROM subscriptions WHERE id = ?1", params![id])?;
+    if deleted == 0 {
+        return Err(OrbitError::NotFound(format!(
+            "No se encontró la suscripción con ID {id}"
+        )));
+    }
+
+    op.finish();
+
+    Ok(())
+}
+
+/// Costo mensual y anual de las suscripciones activas en ARS y en USD, con
+/// la última cotización cargada de `rate_type`.
+#[tauri::command]
+pub fn get_subscription_summary(
+    state: tauri::State<crate::AppState>,
+    rate_type: RateType,
+) -> Result<SubscriptionSummary, OrbitError> {
+    let conn = state.conn.lock().unwrap();
+    let rate = latest_rate(&conn, rate_type)?;
+    let subscriptions = fetch_subscriptions(&conn, "WHERE is_active = 1", [])?;
+
+    let (mut monthly_ars, mut monthly_usd) = (0, 0);
+    for subscription in &subscriptions {
+        let cost = subscription.monthly_cost;
+        if subscription.currency == "USD" {
+            monthly_ars += usd_to_ars(cost, rate);
+            monthly_usd += cost;
+        } else {
+            monthly_ars += cost;
+            monthly_usd += ars_to_usd(cost, rate);
+        }
+    }
+
+    Ok(SubscriptionSummary {
+        rate_type,
+        rate,
+        active: subscriptions.len() as i64,
+        monthly_ars,
+        monthly_usd,
+        annual_ars: monthly_ars * 12,
+        annual_usd: monthly_usd * 12,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_price_changes_and_monthly_cost() {
+        let date = |d: &str| NaiveDate::parse_from_str(d, "%Y-%m-%d").unwrap();
+        let charges = [
+            (date("2024-01-05"), 1000),
+            (date("2024-02-05"), 1000),
+            (date("2024-03-05"), 1200),
+            (date("2024-04-05"), 1200),
+        ];
+        let changes = price_changes(&charges);
+        assert_eq!(changes.len(), 2);
+        assert_eq!(
+            (changes[1].date.as_str(), changes[1].amount),
+            ("2024-03-05", 1200)
+        );
+
+        assert_eq!(monthly_cost(1200, Period::Yearly), 100);
+        assert_eq!(monthly_cost(1000, Period::Weekly), 4333);
+        assert_eq!(
+            roll_forward(Period::Monthly, date("2024-01-31"), date("2024-03-10")),
+            date("2024-03-31")
+        );
+    }
+}
@@ -0,0 +1,330 @@
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+
+use crate::{errors::OrbitError, history};
+
+/// A free-form label attachable to movements.
+///
+/// Unlike groups, tags carry no description and are attached or removed
+/// incrementally instead of rewriting the whole membership.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Tag {
+    pub id: i64,
+    pub name: String,
+    pub created_at: String,
+    /// Number of movements carrying this tag
+    pub usage_count: i64,
+}
+
+/// Summary returned by the bulk tag commands.
+#[derive(Debug, Clone, Serialize)]
+pub struct TagUpdateResult {
+    /// Number of (movement, tag) links actually created or removed
+    pub affected: i64,
+    /// Tags involved in the operation, with their updated usage count
+    pub tags: Vec<Tag>,
+}
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+fn row_to_tag(row: &rusqlite::Row) -> rusqlite::Result<Tag> {
+    Ok(Tag {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        created_at: row.get(2)?,
+        usage_count: row.get(3)?,
+    })
+}
+
+/// Normaliza los nombres recibidos: recorta espacios, descarta vacíos y
+/// elimina duplicados sin distinguir mayúsculas/minúsculas.
+pub(crate) fn normalize_tag_names(names: &[String]) -> Vec<String> {
+    let mut result: Vec<String> = Vec::new();
+
+    for name in names.iter().map(|n| n.trim()).filter(|n| !n.is_empty()) {
+        if !result
+            .iter()
+            .any(|r| r.to_lowercase() == name.to_lowercase())
+        {
+            result.push(name.to_string());
+        }
+    }
+
+    result
+}
+
+/// Busca una etiqueta por nombre (sin distinguir mayúsculas). Si no existe, la crea.
+fn find_or_create_tag(conn: &rusqlite::Connection, name: &str) -> Result<i64, OrbitError> {
+    let query_result = conn.query_row(
+        "SELECT id FROM tags WHERE name = ?1",
+        params![name],
+        |row| row.get(0),
+    );
+
+    match query_result {
+        Ok(id) => Ok(id),
+        Err(rusqlite::Error::QueryReturnedNoRows) => {
+            conn.execute("INSERT INTO tags (name) VALUES (?1)", params![name])?;
+            Ok(conn.last_insert_rowid())
+        }
+        Err(e) => Err(OrbitError::Database(e)),
+    }
+}
+
+/// Ids de las etiquetas con esos nombres (ya normalizados). Con `create`,
+/// las que no existen se crean; si no, se omiten.
+pub(crate) fn resolve_tag_ids(
+    conn: &rusqlite::Connection,
+    names: &[String],
+    create: bool,
+) -> Result<Vec<i64>, OrbitError> {
+    let mut tag_ids: Vec<i64> = Vec::with_capacity(names.len());
+
+    for name in names {
+        if create {
+            tag_ids.push(find_or_create_tag(conn, name)?);
+            continue;
+        }
+        let found = conn.query_row(
+            "SELECT id FROM tags WHERE name = ?1",
+            params![name],
+            |row| row.get(0),
+        );
+        match found {
+            Ok(id) => tag_ids.push(id),
+            // Etiqueta inexistente: no hay nada que quitar
+            Err(rusqlite::Error::QueryReturnedNoRows) => {}
+            Err(e) => return Err(OrbitError::Database(e)),
+        }
+    }
+
+    Ok(tag_ids)
+}
+
+/// Vincula las etiquetas a los movimientos. Devuelve la cantidad de vínculos
+/// creados; los ya existentes se ignoran.
+pub(crate) fn link_tags(
+    conn: &rusqlite::Connection,
+    movement_ids: &[i64],
+    tag_ids: &[i64],
+) -> Result<i64, OrbitError> {
+    let mut affected = 0;
+    for mov_id in movement_ids {
+        for tag_id in tag_ids {
+            affected += conn.execute(
+                "INSERT OR IGNORE INTO movements_tags (mov_id, tag_id) VALUES (?1, ?2)",
+                params![mov_id, tag_id],
+            )? as i64;
+        }
+    }
+
+    Ok(affected)
+}
+
+/// Quita las etiquetas de los movimientos. Devuelve la cantidad de vínculos borrados.
+pub(crate) fn unlink_tags(
+    conn: &rusqlite::Connection,
+    movement_ids: &[i64],
+    tag_ids: &[i64],
+) -> Result<i64, OrbitError> {
+    let mut affected = 0;
+    for mov_id in movement_ids {
+        for tag_id in tag_ids {
+            affected += conn.execute(
+                "DELETE FROM movements_tags WHERE mov_id = ?1 AND tag_id = ?2",
+                params![mov_id, tag_id],
+            )? as i64;
+        }
+    }
+
+    Ok(affected)
+}
+
+/// Escapa los comodines de LIKE (`%`, `_`) y el propio escape, para usar
+/// el texto como literal con `ESCAPE '\'`.
+fn escape_like(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+fn fetch_tag(conn: &rusqlite::Connection, id: i64) -> Result<Tag, OrbitError> {
+    let tag = conn.query_row(
+        "SELECT t.id, t.name, t.created_at, COUNT(mt.id) AS usage_count
+         FROM tags t
+         LEFT JOIN movements_tags mt ON mt.tag_id = t.id
+         WHERE t.id = ?1
+         GROUP BY t.id",
+        params![id],
+        row_to_tag,
+    )?;
+
+    Ok(tag)
+}
+
+// ---------------------------------------------------------------------------
+// Commands
+// ---------------------------------------------------------------------------
+
+/// Lista las etiquetas con su cantidad de usos, las más usadas primero.
+/// Si se pasa `query`, filtra por prefijo (pensado para autocompletar).
+#[tauri::command]
+pub fn get_tags(
+    state: tauri::State<crate::AppState>,
+    query: Option<String>,
+) -> Result<Vec<Tag>, OrbitError> {
+    let conn = state.conn.lock().unwrap();
+
+    let prefix = query
+        .as_deref()
+        .map(str::trim)
+        .filter(|q| !q.is_empty())
+        .map(|q| format!("{}%", escape_like(q)));
+
+    let mut stmt = conn.prepare(
+        "SELECT t.id, t.name, t.created_at, COUNT(mt.id) AS usage_count
+         FROM tags t
+         LEFT JOIN movements_tags mt ON mt.tag_id = t.id
+         WHERE ?1 IS NULL OR t.name LIKE ?1 ESCAPE '\\'
+         GROUP BY t.id
+         ORDER BY usage_count DESC, t.name ASC",
+    )?;
+
+    let tags = stmt
+        .query_map(params![prefix], row_to_tag)?
+        .collect::<Result<Vec<Tag>, rusqlite::Error>>()?;
+
+    Ok(tags)
+}
+
+/// Devuelve las etiquetas de un movimiento.
+#[tauri::command]
+pub fn tags_by_movement(
+    state: tauri::State<crate::AppState>,
+    mov_id: i64,
+) -> Result<Vec<Tag>, OrbitError> {
+    let conn = state.conn.lock().unwrap();
+
+    let mut stmt = conn.prepare(
+        "SELECT t.id, t.name, t.created_at,
+                (SELECT COUNT(*) FROM movements_tags x WHERE x.tag_id = t.id) AS usage_count
+         FROM tags t
+         INNER JOIN movements_tags mt ON mt.tag_id = t.id
+         WHERE mt.mov_id = ?1
+         ORDER BY t.name ASC",
+    )?;
+
+    let tags = stmt
+        .query_map(params![mov_id], row_to_tag)?
+        .collect::<Result<Vec<Tag>, rusqlite::Error>>()?;
+
+    Ok(tags)
+}
+
+/// Agrega las etiquetas a todos los movimientos indicados en una sola transacción.
+/// Las etiquetas que no existen se crean; los vínculos ya existentes se ignoran.
+#[tauri::command]
+pub fn add_tags_to_movements(
+    state: tauri::State<crate::AppState>,
+    movement_ids: Vec<i64>,
+    tags: Vec<String>,
+) -> Result<TagUpdateResult, OrbitError> {
+    let names = normalize_tag_names(&tags);
+    if names.is_empty() {
+        return Err(OrbitError::ValidationError(
+            "Debe indicar al menos una etiqueta".into(),
+        ));
+    }
+
+    let mut conn = state.conn.lock().unwrap();
+    let tx = conn.transaction()?;
+    let op = history::begin(&tx, "Etiquetar movimientos")?;
+
+    let tag_ids = resolve_tag_ids(&tx, &names, true)?;
+    let affected = link_tags(&tx, &movement_ids, &tag_ids)?;
+
+    op.finish();
+    tx.commit()?;
+
+    let tags = tag_ids
+        .into_iter()
+        .map(|id| fetch_tag(&conn, id))
+        .collect::<Result<Vec<Tag>, OrbitError>>()?;
+
+    Ok(TagUpdateResult { affected, tags })
+}
+
+/// Quita las etiquetas de todos los movimientos indicados en una sola transacción.
+/// Las etiquetas en sí no se eliminan aunque queden sin uso.
+#[tauri::command]
+pub fn remove_tags_from_movements(
+    state: tauri::State<crate::AppState>,
+    movement_ids: Vec<i64>,
+    tags: Vec<String>,
+) -> Result<TagUpdateResult, OrbitError> {
+    let names = normalize_tag_names(&tags);
+
+    let mut conn = state.conn.lock().unwrap();
+    let tx = conn.transaction()?;
+    let op = history::begin(&tx, "Quitar etiquetas")?;
+
+    let tag_ids = resolve_tag_ids(&tx, &names, false)?;
+    let affected = unlink_tags(&tx, &movement_ids, &tag_ids)?;
+
+    op.finish();
+    tx.commit()?;
+
+    let tags = tag_ids
+        .into_iter()
+        .map(|id| fetch_tag(&conn, id))
+        .collect::<Result<Vec<Tag>, OrbitError>>()?;
+
+    Ok(TagUpdateResult { affected, tags })
+}
+
+#[tauri::command]
+pub fn rename_tag(
+    state: tauri::State<crate::AppState>,
+    id: i64,
+    name: String,
+) -> Result<Tag, OrbitError> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(OrbitError::ValidationError(
+            "El nombre de la etiqueta no puede estar vacío".into(),
+        ));
+    }
+
+    let conn = state.conn.lock().unwrap();
+    let op = history::begin(&conn, "Renombrar etiqueta")?;
+
+    let rows_affected =
+        conn.execute("UPDATE tags SET name = ?1 WHERE id = ?2", params![name, id])?;
+
+    if rows_affected == 0 {
+        return Err(OrbitError::NotFound("Etiqueta no encontrada".into()));
+    }
+
+    op.finish();
+
+    fetch_tag(&conn, id)
+}
+
+#[tauri::command]
+pub fn delete_tag(state: tauri::State<crate::AppState>, id: i64) -> Result<(), OrbitError> {
+    let conn = state.conn.lock().unwrap();
+    let op = history::begin(&conn, "Eliminar etiqueta")?;
+
+    // movements_tags se limpia automáticamente por ON DELETE CASCADE
+    let rows_affected = conn.execute("DELETE FROM tags WHERE id = ?1", params![id])?;
+
+    if rows_affected == 0 {
+        return Err(OrbitError::NotFound("Etiqueta no encontrada".into()));
+    }
+
+    op.finish();
+
+    Ok(())
+}
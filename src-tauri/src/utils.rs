@@ -10,6 +10,143 @@ pub fn random_hex_color() -> String {
     format!("#{:02X}{:02X}{:02X}", r, g, b)
 }
 
+/// Nombres de las columnas de una tabla, en orden, vía `PRAGMA table_info`.
+/// `table` debe ser un literal del código, nunca texto del usuario.
+pub(crate) fn table_columns(
+    conn: &rusqlite::Connection,
+    table: &str,
+) -> rusqlite::Result<Vec<String>> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({table})"))?;
+    let columns = stmt
+        .query_map([], |row| row.get::<_, String>(1))?
+        .collect::<rusqlite::Result<Vec<String>>>()?;
+
+    Ok(columns)
+}
+
+/// Expresión SQL que arma un objeto JSON con todas las columnas de la fila
+/// `alias` (ej: `NEW` u `OLD` dentro de un trigger).
+pub(crate) fn json_row_expr(columns: &[String], alias: &str) -> String {
+    let pairs: Vec<String> = columns
+        .iter()
+        .map(|c| format!("'{c}', {alias}.\"{c}\""))
+        .collect();
+    format!("json_object({})", pairs.join(", "))
+}
+
+/// Valida un mes YYYY-MM y lo devuelve normalizado.
+pub(crate) fn parse_month(value: &str) -> Result<String, crate::errors::OrbitError> {
+    chrono::NaiveDate::parse_from_str(&format!("{}-01", value.trim()), "%Y-%m-%d")
+        .map(|d| d.format("%Y-%m").to_string())
+        .map_err(|_| {
+            crate::errors::OrbitError::ValidationError(format!(
+                "Mes inválido: {value:?} (formato YYYY-MM)"
+            ))
+        })
+}
+
+/// Valida una fecha YYYY-MM-DD.
+pub(crate) fn parse_date(value: &str) -> Result<chrono::NaiveDate, crate::errors::OrbitError> {
+    chrono::NaiveDate::parse_from_str(value.trim(), "%Y-%m-%d").map_err(|_| {
+        crate::errors::OrbitError::ValidationError(format!("Fecha inválida: {value:?}"))
+    })
+}
+
+/// Fecha en el formato en que se guarda en la base (YYYY-MM-DD).
+pub(crate) fn format_date(date: chrono::NaiveDate) -> String {
+    date.format("%Y-%m-%d").to_string()
+}
+
+/// Meses consecutivos (YYYY-MM) entre `first` y `last`, ambos incluidos.
+pub(crate) fn month_range(first: &str, last: &str) -> Vec<String> {
+    let parse = |m: &str| chrono::NaiveDate::parse_from_str(&format!("{m}-01"), "%Y-%m-%d").ok();
+    let (Some(mut current), Some(end)) = (parse(first), parse(last)) else {
+        return Vec::new();
+    };
+
+    let mut months = Vec::new();
+    while current <= end {
+        months.push(current.format("%Y-%m").to_string());
+        current = match current.checked_add_months(chrono::Months::new(1)) {
+            Some(next) => next,
+            None => break,
+        };
+    }
+
+    months
+}
+
+/// Minúsculas, sin acentos y solo letras/dígitos separados por un espacio.
+pub(crate) fn normalize_text(text: &str) -> String {
+    let folded: String = text
+        .to_lowercase()
+        .chars()
+        .map(|c| match c {
+            'á' | 'à' | 'ä' | 'â' => 'a',
+            'é' | 'è' | 'ë' | 'ê' => 'e',
+            'í' | 'ì' | 'ï' | 'î' => 'i',
+            'ó' | 'ò' | 'ö' | 'ô' => 'o',
+            'ú' | 'ù' | 'ü' | 'û' => 'u',
+            'ñ' => 'n',
+            c if c.is_alphanumeric() => c,
+            _ => ' ',
+        })
+        .collect();
+
+    folded.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+fn levenshtein(a: &[char], b: &[char]) -> usize {
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut curr = vec![0; b.len() + 1];
+
+    for (i, ca) in a.iter().enumerate() {
+        curr[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let cost = usize::from(ca != cb);
+            curr[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(curr[j] + 1);
+        }
+        std::mem::swap(&mut prev, &mut curr);
+    }
+
+    prev[b.len()]
+}
+
+/// Similitud entre dos textos cortos (descripciones, nombres), de 0.0 a 1.0.
+///
+/// Toma la mejor de dos medidas: distancia de edición (errores de tipeo,
+/// "Netflx" vs "Netflix") y palabras en común (orden distinto o texto extra
+/// que agregan los bancos, "COMPRA COTO 123" vs "Coto").
+pub(crate) fn text_similarity(a: &str, b: &str) -> f64 {
+    let a = normalize_text(a);
+    let b = normalize_text(b);
+
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    if a == b {
+        return 1.0;
+    }
+
+    let a_chars: Vec<char> = a.chars().collect();
+    let b_chars: Vec<char> = b.chars().collect();
+    let max_len = a_chars.len().max(b_chars.len()) as f64;
+    let edit = 1.0 - levenshtein(&a_chars, &b_chars) as f64 / max_len;
+
+    // Proporción de palabras del texto más corto presentes en el otro
+    let a_words: Vec<&str> = a.split(' ').collect();
+    let b_words: Vec<&str> = b.split(' ').collect();
+    let (short, long) = if a_words.len() <= b_words.len() {
+        (&a_words, &b_words)
+    } else {
+        (&b_words, &a_words)
+    };
+    let shared = short.iter().filter(|w| long.contains(w)).count() as f64;
+    let words = shared / short.len() as f64;
+
+    edit.max(words)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -34,6 +171,21 @@ mod tests {
         // Note: This test could theoretically fail due to random chance, but probability is negligible
         assert_ne!(color1, color2);
     }
+
+    #[test]
+    fn test_text_similarity_ignores_case_accents_and_noise() {
+        assert_eq!(text_similarity("Café Martínez", "CAFE MARTINEZ"), 1.0);
+        // Texto extra agregado por el banco
+        assert_eq!(text_similarity("Coto", "COMPRA COTO 1234"), 1.0);
+        // Error de tipeo
+        assert!(text_similarity("Netflix", "Netflx") > 0.8);
+    }
+
+    #[test]
+    fn test_text_similarity_unrelated_is_low() {
+        assert!(text_similarity("Sueldo", "Farmacia") < 0.3);
+        assert_eq!(text_similarity("", "Coto"), 0.0);
+    }
 }
 
 // Run tests with: cargo test